@@ -0,0 +1,28 @@
+-- Snapshot of each guild's player so a restart can offer to pick up where
+-- it left off. `player_session_tracks.position` 0 is the now-playing track
+-- (only present when something was playing); 1.. is the queue in order.
+CREATE TABLE IF NOT EXISTS player_sessions
+(
+    guild_id         INTEGER PRIMARY KEY                NOT NULL,
+    voice_channel_id INTEGER                            NOT NULL,
+    text_channel_id  INTEGER                            NOT NULL,
+    position_ms      INTEGER  DEFAULT 0                 NOT NULL,
+    updated_at       DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS player_session_tracks
+(
+    guild_id    INTEGER NOT NULL,
+    position    INTEGER NOT NULL,
+    track_id    TEXT    NOT NULL,
+    media_id    TEXT    NOT NULL,
+    title       TEXT    NOT NULL,
+    channel     TEXT    NOT NULL,
+    track_url   TEXT    NOT NULL,
+    play_url    TEXT,
+    duration_ms INTEGER,
+    added_by    TEXT    NOT NULL,
+    source      TEXT    NOT NULL,
+    local_path  TEXT,
+    PRIMARY KEY (guild_id, position)
+);
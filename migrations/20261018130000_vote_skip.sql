@@ -0,0 +1,3 @@
+-- Share of the listeners in the bot's voice channel that must vote before
+-- a track is vote-skipped.
+ALTER TABLE guilds ADD COLUMN vote_skip_percent INTEGER DEFAULT 50;
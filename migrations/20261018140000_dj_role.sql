@@ -0,0 +1,8 @@
+-- Optional per-guild DJ role. Commands listed in `gated_commands` (space
+-- separated) are reserved for members with the role once one is set.
+CREATE TABLE IF NOT EXISTS dj_settings
+(
+    guild_id       INTEGER PRIMARY KEY                                         NOT NULL,
+    role_id        INTEGER,
+    gated_commands TEXT DEFAULT 'clear stop shuffle volume remove playnow' NOT NULL
+);
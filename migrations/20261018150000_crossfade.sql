@@ -0,0 +1,2 @@
+-- Seconds the end of one track overlaps the start of the next (0 = off).
+ALTER TABLE guilds ADD COLUMN crossfade_secs INTEGER DEFAULT 0;
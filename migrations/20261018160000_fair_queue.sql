@@ -0,0 +1,5 @@
+-- Draw the next track round-robin across requesters instead of in order.
+ALTER TABLE guilds ADD COLUMN fair_queue INTEGER DEFAULT 0;
+
+-- Requesters are matched by user id; the name is only for display.
+ALTER TABLE player_session_tracks ADD COLUMN added_by_id INTEGER;
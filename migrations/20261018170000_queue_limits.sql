@@ -0,0 +1,4 @@
+-- Per-guild queue caps. NULL means unlimited.
+ALTER TABLE guilds ADD COLUMN max_tracks_per_user INTEGER;
+ALTER TABLE guilds ADD COLUMN max_queue_duration_secs INTEGER;
+ALTER TABLE guilds ADD COLUMN max_playlist_size INTEGER;
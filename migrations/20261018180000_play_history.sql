@@ -0,0 +1,17 @@
+-- Every track the bot started, per guild. `outcome` is NULL while the track
+-- is playing, then 'finished' or 'skipped'; `listened_ms` is filled in then.
+CREATE TABLE IF NOT EXISTS play_history
+(
+    id             INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id       INTEGER                            NOT NULL,
+    track_id       TEXT                               NOT NULL,
+    source         TEXT                               NOT NULL,
+    title          TEXT                               NOT NULL,
+    requester_id   INTEGER,
+    requester_name TEXT                               NOT NULL,
+    started_at     DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    listened_ms    INTEGER,
+    outcome        TEXT
+);
+
+CREATE INDEX IF NOT EXISTS play_history_guild_started ON play_history (guild_id, started_at);
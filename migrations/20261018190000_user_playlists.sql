@@ -0,0 +1,33 @@
+-- Named playlists saved by users. Each entry keeps the full track metadata
+-- and source so loading a playlist never has to go back to YouTube or
+-- Spotify. A playlist is private to its owner until shared with the guild.
+-- Names compare case-insensitively, the same way they're looked up.
+CREATE TABLE IF NOT EXISTS user_playlists
+(
+    id         INTEGER PRIMARY KEY AUTOINCREMENT,
+    guild_id   INTEGER                            NOT NULL,
+    owner_id   INTEGER                            NOT NULL,
+    name       TEXT COLLATE NOCASE                NOT NULL,
+    shared     INTEGER  DEFAULT 0                 NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP NOT NULL,
+    UNIQUE (guild_id, owner_id, name)
+);
+
+CREATE TABLE IF NOT EXISTS user_playlist_tracks
+(
+    playlist_id    INTEGER NOT NULL,
+    position       INTEGER NOT NULL,
+    media_id       TEXT    NOT NULL,
+    title          TEXT    NOT NULL,
+    channel        TEXT    NOT NULL,
+    track_url      TEXT    NOT NULL,
+    play_url       TEXT,
+    thumbnail      TEXT,
+    duration_ms    INTEGER,
+    source         TEXT    NOT NULL,
+    local_path     TEXT,
+    -- Part of the video the entry plays; NULL start plays all of it.
+    range_start_ms INTEGER,
+    range_end_ms   INTEGER,
+    PRIMARY KEY (playlist_id, position)
+);
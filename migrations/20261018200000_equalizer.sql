@@ -0,0 +1,3 @@
+-- Equalizer band gains in dB, space separated from the lowest band up.
+-- NULL plays tracks flat.
+ALTER TABLE guilds ADD COLUMN eq_gains TEXT;
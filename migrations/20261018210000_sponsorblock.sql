@@ -0,0 +1,3 @@
+-- Whether tracks have their SponsorBlock sponsor and non-music segments
+-- cut or skipped. Off unless a guild turns it on.
+ALTER TABLE guilds ADD COLUMN sponsorblock INTEGER DEFAULT 0;
@@ -0,0 +1,4 @@
+-- Part of the video a saved track plays, when only part was asked for.
+-- NULL start plays the whole video; NULL end plays to the end.
+ALTER TABLE player_session_tracks ADD COLUMN range_start_ms INTEGER;
+ALTER TABLE player_session_tracks ADD COLUMN range_end_ms INTEGER;
@@ -1,11 +1,12 @@
 use crate::commands;
 use crate::commands::{activity, music, reputation, utility};
-use crate::handlers::{error_handler, message_handler, voice_handler};
+use crate::handlers::{error_handler, guild_handler, message_handler, voice_handler};
 use crate::player::player::Player;
 use crate::player::track::PlaybackError;
 use crate::service::emoticon_service::EmoticonService;
 use crate::service::gather_service::GatherState;
 use crate::service::notifier_service::{Notifier, NotifierError};
+use crate::service::{attachment_service, session_service};
 use crate::sources::spotify_player::{SpotifyClient, SpotifyError};
 use crate::sources::youtube_player::{SearchError, YoutubeClient};
 use dotenv::var;
@@ -24,7 +25,8 @@ pub struct MusicBotData {
     pub youtube_client: YoutubeClient,
     pub spotify_client: SpotifyClient,
     pub database_pool: Arc<Database>,
-    pub player: Arc<RwLock<Player>>,
+    /// One `Player` per guild, created on demand through `player_service`.
+    pub players: Arc<RwLock<HashMap<GuildId, Arc<RwLock<Player>>>>>,
     pub notifier: Arc<RwLock<Notifier>>,
     pub gatherings: Arc<RwLock<HashMap<GuildId, Arc<GatherState>>>>,
     pub emoticon_service: Arc<EmoticonService>,
@@ -108,15 +110,31 @@ impl MusicBotClient {
                     music::cmd_play::play(),
                     music::cmd_play::play_top(),
                     music::cmd_play::play_now(),
+                    music::cmd_play::play_audio_context(),
                     music::cmd_pause::pause(),
                     music::cmd_resume::resume(),
                     music::cmd_skip::skip(),
+                    music::cmd_voteskip::voteskip(),
+                    music::cmd_dj::dj(),
+                    music::cmd_seek::seek(),
+                    music::cmd_seek::forward(),
+                    music::cmd_seek::rewind(),
+                    music::cmd_chapter::chapter(),
                     music::cmd_stop::stop(),
                     music::cmd_vol::volume(),
                     music::cmd_join::join(),
                     music::cmd_queue::queue(),
                     music::cmd_clear::clear(),
                     music::cmd_remove::remove(),
+                    music::cmd_move::move_track(),
+                    music::cmd_swap::swap(),
+                    music::cmd_dedupe::dedupe(),
+                    music::cmd_fairqueue::fairqueue(),
+                    music::cmd_limits::limits(),
+                    music::cmd_stats::stats(),
+                    music::cmd_filter::filter(),
+                    music::cmd_eq::eq(),
+                    music::cmd_playlist::playlist(),
                     music::cmd_leave::leave(),
                     music::cmd_shuffle::shuffle(),
                     music::cmd_playing::playing(),
@@ -124,6 +142,10 @@ impl MusicBotClient {
                     music::cmd_local::local(),
                     music::cmd_silent::silent(),
                     music::cmd_normalize::normalize(),
+                    music::cmd_autoplay::autoplay(),
+                    music::cmd_crossfade::crossfade(),
+                    music::cmd_sponsorblock::sponsorblock(),
+                    music::cmd_loop::loop_mode(),
                     utility::cmd_uwu::uwu(),
                     utility::cmd_uwu::uwu_me(),
                     activity::cmd_gather::gather(),
@@ -152,8 +174,9 @@ impl MusicBotClient {
                         error_handler::schedule_prefix_delete(ctx);
                     })
                 },
-                event_handler: |ctx, event, _fw, data| {
+                event_handler: |ctx, event, fw, data| {
                     Box::pin(async move {
+                        guild_handler::handle(ctx, event, fw, data).await?;
                         voice_handler::handle(ctx, event, data).await?;
                         message_handler::handle(ctx, event, data).await?;
                         Ok(())
@@ -168,21 +191,19 @@ impl MusicBotClient {
             })
             .setup(move |ctx, ready, fw| {
                 Box::pin(async move {
-                    let guild_id: GuildId = ready.guilds[0].id;
-                    let guild_id_map: i64 = guild_id.get() as i64;
-
                     tracing::info!("Bot ready");
                     tracing::info!("Logged in as {}", ready.user.name);
 
-                    crate::player::player::set_idle(ctx);
+                    crate::player::player::refresh_activity(ctx);
 
-                    tracing::info!("Registering commands in guild");
-                    poise::builtins::register_in_guild(ctx, &fw.options().commands, ready.guilds[0].id)
-                        .await
-                        .map_err(|e| {
-                            tracing::error!("Failed to register commands in guild: {:?}", e);
-                            MusicBotError::InternalError(e.to_string())
-                        })?;
+                    // Guilds joined after startup get their commands from
+                    // `guild_handler` on `GuildCreate`.
+                    tracing::info!("Registering commands in {} guild(s)", ready.guilds.len());
+                    for guild in &ready.guilds {
+                        if let Err(e) = poise::builtins::register_in_guild(ctx, &fw.options().commands, guild.id).await {
+                            tracing::error!("Failed to register commands in guild {}: {:?}", guild.id, e);
+                        }
+                    }
 
                     tracing::info!("Connecting to database");
                     let database: Arc<Database> = Arc::new(
@@ -204,20 +225,10 @@ impl MusicBotClient {
                             MusicBotError::InternalError(e.to_string())
                         })?;
 
-                    let _ = sqlx::query!(
-                        "INSERT OR IGNORE INTO guilds (guild_id, volume) VALUES ($1, $2)",
-                        guild_id_map,
-                        0.5
-                    )
-                    .execute(&*database)
-                    .await
-                    .map_err(|e| {
-                        tracing::error!("Failed to insert guild into database: {:?}", e);
-                        MusicBotError::InternalError(e.to_string())
-                    })?;
-
-                    let player: Player = Player::new(guild_id, database.clone()).await;
-                    let player_handle: Arc<RwLock<Player>> = Arc::new(RwLock::new(player));
+                    attachment_service::clear().await;
+
+                    let players: Arc<RwLock<HashMap<GuildId, Arc<RwLock<Player>>>>> = Arc::new(RwLock::new(HashMap::new()));
+                    session_service::spawn_autosave(ctx.clone(), players.clone(), database.clone());
 
                     let notifier: Notifier = Notifier::new(ctx.clone(), database.clone()).await;
                     let notifier_handle: Arc<RwLock<Notifier>> = Arc::new(RwLock::new(notifier));
@@ -246,7 +257,7 @@ impl MusicBotClient {
                         youtube_client: YoutubeClient::new(),
                         spotify_client: SpotifyClient::new(),
                         database_pool: database,
-                        player: player_handle,
+                        players,
                         notifier: notifier_handle,
                         gatherings: Arc::new(RwLock::new(HashMap::new())),
                         emoticon_service,
@@ -1,2 +1,4 @@
 pub mod channel_checks;
+pub mod dj_checks;
+pub mod permission_checks;
 pub mod player_checks;
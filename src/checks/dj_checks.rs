@@ -0,0 +1,115 @@
+use crate::bot::{Context, Database, MusicBotError};
+use crate::embeds::bot::bot_embeds::BotEmbed;
+use crate::player::player::Player;
+use crate::service::embed_service::SendEmbed;
+use crate::service::{channel_service, dj_service, player_service};
+use serenity::all::{GuildId, RoleId, UserId};
+use serenity::prelude::Context as SerenityContext;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Reserve the invoked command for the guild's DJ role when the guild gates
+/// it. Attach to every command listed in `dj_service::GATEABLE_COMMANDS`.
+pub async fn check_dj(ctx: Context<'_>) -> Result<bool, MusicBotError> {
+    let command = ctx.command().name.clone();
+    require_dj(ctx, &command).await
+}
+
+/// Whether the author may run `command` under the guild's DJ settings. The
+/// rule relaxes for someone alone with the bot, or who queued the current
+/// track. Sends an explanation and returns `false` when they may not.
+pub async fn require_dj(
+    ctx: Context<'_>,
+    command: &str,
+) -> Result<bool, MusicBotError> {
+    let Some(guild_id) = ctx.guild_id() else {
+        return Ok(true);
+    };
+
+    let Some(role_id) = gating_role(&ctx.data().database_pool, guild_id, command).await? else {
+        return Ok(true);
+    };
+
+    let has_role = ctx
+        .author_member()
+        .await
+        .is_some_and(|member| member.roles.contains(&role_id));
+    if has_role {
+        return Ok(true);
+    }
+
+    let player_arc = player_service::player(ctx).await?;
+    if is_exempt(
+        ctx.serenity_context(),
+        &player_arc,
+        guild_id,
+        ctx.author().id,
+    )
+    .await
+    {
+        return Ok(true);
+    }
+
+    BotEmbed::DjRoleRequired(&role_id)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(false)
+}
+
+/// The role `command` is reserved for in `guild_id`, or `None` when anyone
+/// may run it.
+pub async fn gating_role(
+    database: &Database,
+    guild_id: GuildId,
+    command: &str,
+) -> Result<Option<RoleId>, MusicBotError> {
+    let settings = dj_service::load(database, guild_id).await?;
+    Ok(settings.role_id.filter(|_| settings.gates(command)))
+}
+
+/// Whether `user_id` may run gated commands without the DJ role: they're
+/// alone with the bot, or they queued the current track.
+pub async fn is_exempt(
+    serenity_ctx: &SerenityContext,
+    player_arc: &Arc<RwLock<Player>>,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> bool {
+    let alone_with_bot = channel_service::bot_voice_channel(serenity_ctx, guild_id)
+        .map(|channel| channel_service::listeners(serenity_ctx, guild_id, channel))
+        .is_some_and(|listeners| listeners == [user_id]);
+    if alone_with_bot {
+        return true;
+    }
+
+    player_arc
+        .read()
+        .await
+        .current_track
+        .as_ref()
+        .is_some_and(|track| track.added_by.is(user_id))
+}
+
+/// Whether `user_id` may skip outright rather than vote: they hold the
+/// guild's DJ role (whichever commands it gates), or `is_exempt` lets them
+/// off. Everyone else goes through `vote_skip_service::cast_vote`. Guilds
+/// without a DJ role therefore always vote.
+pub async fn may_skip_instantly(
+    serenity_ctx: &SerenityContext,
+    database: &Database,
+    player_arc: &Arc<RwLock<Player>>,
+    guild_id: GuildId,
+    user_id: UserId,
+    roles: &[RoleId],
+) -> Result<bool, MusicBotError> {
+    let settings = dj_service::load(database, guild_id).await?;
+    if settings
+        .role_id
+        .is_some_and(|role_id| roles.contains(&role_id))
+    {
+        return Ok(true);
+    }
+    Ok(is_exempt(serenity_ctx, player_arc, guild_id, user_id).await)
+}
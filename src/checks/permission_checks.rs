@@ -0,0 +1,28 @@
+use crate::bot::{Context, MusicBotError};
+use crate::embeds::bot::bot_embeds::BotEmbed;
+use crate::service::embed_service::SendEmbed;
+
+/// Whether the author has Manage Server here, for commands that need it to
+/// change a setting but not to look at one. Sends an explanation and returns
+/// `false` when they don't.
+pub async fn require_manage_guild(ctx: Context<'_>) -> Result<bool, MusicBotError> {
+    let channel = ctx.guild_channel().await;
+    let member = ctx.author_member().await;
+
+    let permitted = match (channel, member) {
+        (Some(channel), Some(member)) => ctx
+            .guild()
+            .is_some_and(|guild| guild.user_permissions_in(&channel, &member).manage_guild()),
+        _ => false,
+    };
+    if permitted {
+        return Ok(true);
+    }
+
+    BotEmbed::ManageServerRequired
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(false)
+}
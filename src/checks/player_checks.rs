@@ -3,10 +3,12 @@ use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::embeds::music::queue_embed::QueueEmbed;
 use crate::player::player::Player;
 use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
 use tokio::sync::RwLockReadGuard;
 
 pub async fn check_if_player_is_playing(ctx: Context<'_>) -> Result<bool, MusicBotError> {
-    let player: RwLockReadGuard<Player> = ctx.data().player.read().await;
+    let player_arc = player_service::player(ctx).await?;
+    let player: RwLockReadGuard<Player> = player_arc.read().await;
 
     if player.is_playing {
         Ok(true)
@@ -21,7 +23,8 @@ pub async fn check_if_player_is_playing(ctx: Context<'_>) -> Result<bool, MusicB
 }
 
 pub async fn check_if_player_is_paused(ctx: Context<'_>) -> Result<bool, MusicBotError> {
-    let player: RwLockReadGuard<Player> = ctx.data().player.read().await;
+    let player_arc = player_service::player(ctx).await?;
+    let player: RwLockReadGuard<Player> = player_arc.read().await;
 
     if player.is_paused {
         Ok(true)
@@ -36,7 +39,8 @@ pub async fn check_if_player_is_paused(ctx: Context<'_>) -> Result<bool, MusicBo
 }
 
 pub async fn check_if_queue_is_not_empty(ctx: Context<'_>) -> Result<bool, MusicBotError> {
-    let player: RwLockReadGuard<Player> = ctx.data().player.read().await;
+    let player_arc = player_service::player(ctx).await?;
+    let player: RwLockReadGuard<Player> = player_arc.read().await;
 
     if player.queue.is_empty() {
         QueueEmbed::IsEmpty
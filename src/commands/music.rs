@@ -1,18 +1,35 @@
+pub mod cmd_autoplay;
+pub mod cmd_chapter;
 pub mod cmd_clear;
+pub mod cmd_crossfade;
+pub mod cmd_dedupe;
+pub mod cmd_dj;
 pub mod cmd_download;
+pub mod cmd_eq;
+pub mod cmd_fairqueue;
+pub mod cmd_filter;
 pub mod cmd_history;
 pub mod cmd_join;
 pub mod cmd_leave;
+pub mod cmd_limits;
 pub mod cmd_local;
+pub mod cmd_loop;
+pub mod cmd_move;
 pub mod cmd_normalize;
 pub mod cmd_pause;
 pub mod cmd_play;
 pub mod cmd_playing;
+pub mod cmd_playlist;
 pub mod cmd_queue;
 pub mod cmd_remove;
 pub mod cmd_resume;
+pub mod cmd_seek;
 pub mod cmd_shuffle;
 pub mod cmd_silent;
 pub mod cmd_skip;
+pub mod cmd_sponsorblock;
+pub mod cmd_stats;
 pub mod cmd_stop;
+pub mod cmd_swap;
 pub mod cmd_vol;
+pub mod cmd_voteskip;
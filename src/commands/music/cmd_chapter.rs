@@ -0,0 +1,96 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks;
+use crate::checks::player_checks::check_if_player_is_playing;
+use crate::commands::music::cmd_seek::{self, SeekTarget};
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::player::Player;
+use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
+use tokio::sync::RwLockReadGuard;
+
+/// List the current track's chapters, or jump between them.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("list", "next", "prev"),
+    aliases("chapters", "ch")
+)]
+pub async fn chapter(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    list_inner(ctx).await
+}
+
+/// List the current track's chapters.
+#[poise::command(prefix_command, slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    list_inner(ctx).await
+}
+
+/// Jump to the next chapter.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_if_player_is_playing",
+    aliases("skip")
+)]
+pub async fn next(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    // Jumping is seeking, so it follows the guild's DJ setting for `seek`.
+    if !dj_checks::require_dj(ctx, "seek").await? {
+        return Ok(());
+    }
+    cmd_seek::seek_current(ctx, SeekTarget::NextChapter).await
+}
+
+/// Go back to the start of this chapter, or to the previous one if it only just began.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_if_player_is_playing",
+    aliases("previous", "back")
+)]
+pub async fn prev(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    if !dj_checks::require_dj(ctx, "seek").await? {
+        return Ok(());
+    }
+    cmd_seek::seek_current(ctx, SeekTarget::PreviousChapter).await
+}
+
+async fn list_inner(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let player: RwLockReadGuard<Player> = player_arc.read().await;
+
+    let (Some(track), Some(handle)) = (player.current_track.as_ref(), player.track_handle.as_ref()) else {
+        drop(player);
+        PlayerEmbed::NoSongPlaying
+            .to_embed()
+            .send_context(ctx, true, Some(30))
+            .await?;
+        return Ok(());
+    };
+
+    if track.chapters().is_empty() {
+        PlayerEmbed::NoChapters(track)
+            .to_embed()
+            .send_context(ctx, true, Some(30))
+            .await?;
+        return Ok(());
+    }
+
+    let position = handle
+        .get_info()
+        .await
+        .map(|state| player.track_position(state.position))
+        .unwrap_or_default();
+
+    PlayerEmbed::ChapterList {
+        track,
+        current: track.chapter_at(position),
+    }
+    .to_embed()
+    .send_context(ctx, true, Some(30))
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks;
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::player::MAX_CROSSFADE;
+use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
+use std::time::Duration;
+
+/// Show or set how many seconds (0-12) consecutive tracks overlap.
+#[poise::command(prefix_command, slash_command, aliases("xfade", "fade"))]
+pub async fn crossfade(
+    ctx: Context<'_>,
+    #[description = "Seconds of overlap (0-12), or `off`"] seconds: Option<String>,
+) -> Result<(), MusicBotError> {
+    let desired: Option<Duration> = match seconds
+        .as_deref()
+        .map(str::trim)
+        .map(str::to_ascii_lowercase)
+    {
+        None => None,
+        Some(s) if matches!(s.as_str(), "off" | "false" | "no" | "n") => Some(Duration::ZERO),
+        Some(s) => match s.trim_end_matches('s').parse::<u64>() {
+            Ok(secs) if secs <= MAX_CROSSFADE.as_secs() => Some(Duration::from_secs(secs)),
+            _ => {
+                return Err(MusicBotError::InternalError(format!(
+                    "Invalid crossfade `{s}`. Use a number of seconds from 0 to {}, or `off`.",
+                    MAX_CROSSFADE.as_secs()
+                )));
+            }
+        },
+    };
+
+    // Anyone can look; changing it is for the people listening.
+    if desired.is_some() && (!check_author_in_same_voice_channel(ctx).await? || !dj_checks::require_dj(ctx, "crossfade").await?) {
+        return Ok(());
+    }
+
+    let player_arc = player_service::player(ctx).await?;
+    let mut player = player_arc.write().await;
+
+    if let Some(desired) = desired {
+        player.set_crossfade(desired).await?;
+    }
+    let crossfade = player.crossfade;
+    drop(player);
+
+    PlayerEmbed::CrossfadeState(crossfade)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
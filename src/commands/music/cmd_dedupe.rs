@@ -0,0 +1,33 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks::check_dj;
+use crate::checks::player_checks::check_if_queue_is_not_empty;
+use crate::commands::music::cmd_queue;
+use crate::embeds::music::queue_embed::QueueEmbed;
+use crate::player::player::Player;
+use crate::service::embed_service;
+use crate::service::player_service;
+use tokio::sync::RwLockWriteGuard;
+
+/// Remove duplicate tracks from the queue, keeping the first of each.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj",
+    check = "check_if_queue_is_not_empty",
+    aliases("dedup")
+)]
+pub async fn dedupe(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+
+    let removed = player.dedupe_queue().await;
+
+    let confirmation = QueueEmbed::Deduplicated(removed).to_embed();
+    let embeds = cmd_queue::edit_result_embeds(&player, confirmation, 1);
+    drop(player);
+    embed_service::send_context_embeds(ctx, embeds, true, Some(30)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use crate::bot::{Context, MusicBotError};
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::service::dj_service;
+use crate::service::embed_service::SendEmbed;
+use serenity::all::Role;
+
+/// Show the DJ role and the commands reserved for it.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("show", "role", "off", "commands")
+)]
+pub async fn dj(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    // Default action when called without a subcommand: show the settings.
+    show_inner(ctx).await
+}
+
+/// Show the DJ role and the commands reserved for it.
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    show_inner(ctx).await
+}
+
+/// Set the DJ role.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn role(
+    ctx: Context<'_>,
+    #[description = "Role whose members may use DJ-only commands"] role: Role,
+) -> Result<(), MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    dj_service::set_role(&ctx.data().database_pool, guild_id, Some(role.id)).await?;
+    show_inner(ctx).await
+}
+
+/// Remove the DJ role, opening every command to everyone again.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn off(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    dj_service::set_role(&ctx.data().database_pool, guild_id, None).await?;
+    show_inner(ctx).await
+}
+
+/// Choose which commands are DJ-only (`default` restores the default set).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn commands(
+    ctx: Context<'_>,
+    #[description = "Command names separated by spaces, or `default`"] names: Vec<String>,
+) -> Result<(), MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+
+    let names: Vec<String> = names
+        .iter()
+        .flat_map(|n| n.split([' ', ',']))
+        .map(|n| n.trim().trim_start_matches('!').to_ascii_lowercase())
+        .filter(|n| !n.is_empty())
+        .collect();
+
+    let gated: Vec<String> = if names.iter().any(|n| n == "default") {
+        dj_service::DEFAULT_GATED_COMMANDS
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    } else {
+        if let Some(unknown) = names
+            .iter()
+            .find(|n| !dj_service::GATEABLE_COMMANDS.contains(&n.as_str()))
+        {
+            return Err(MusicBotError::InternalError(format!(
+                "`{unknown}` can't be made DJ-only. Choose from: {}.",
+                dj_service::GATEABLE_COMMANDS.join(", ")
+            )));
+        }
+        let mut gated: Vec<String> = Vec::new();
+        for name in names {
+            if !gated.contains(&name) {
+                gated.push(name);
+            }
+        }
+        gated
+    };
+
+    dj_service::set_gated_commands(&ctx.data().database_pool, guild_id, &gated).await?;
+    show_inner(ctx).await
+}
+
+async fn show_inner(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    let settings = dj_service::load(&ctx.data().database_pool, guild_id).await?;
+
+    PlayerEmbed::DjSettings {
+        role_id: settings.role_id,
+        gated: &settings.gated_commands,
+    }
+    .to_embed()
+    .send_context(ctx, true, Some(60))
+    .await?;
+
+    Ok(())
+}
@@ -1,8 +1,9 @@
 //! Helpers for fetching audio into the local library. Exposed for the
-//! `local download` subcommand in `cmd_local`.
+//! `local download` subcommand in `cmd_local`, and for `play`'s one-off
+//! attachments.
 
 use crate::bot::{Context, MusicBotError};
-use crate::player::track::{Track, TrackMetadata, TrackSource};
+use crate::player::track::{Requester, Track, TrackMetadata, TrackSource};
 use crate::sources::local_player;
 use std::path::PathBuf;
 
@@ -73,14 +74,7 @@ pub async fn save_to_library(
                     "Attachment `{filename}` doesn't look like an audio file."
                 )));
             }
-            let mut name = local_player::sanitize_filename(filename);
-            // Discord allows audio files without recognized extensions; add
-            // one so `local list` can find the file later.
-            if !local_player::has_audio_extension(&name) {
-                let ext = audio_ext_from_content_type(content_type.as_deref()).unwrap_or("mp3");
-                name = format!("{name}.{ext}");
-            }
-            name
+            attachment_filename(filename, content_type.as_deref())
         }
         DownloadSource::Url(_) => filename_from_response(url, &response),
     };
@@ -126,7 +120,22 @@ fn extension_of(name: &str) -> Option<&str> {
     Some(&name[idx + 1..])
 }
 
-fn is_audio(
+/// A safe filename for an attachment. Discord allows audio files without
+/// recognized extensions; one is added so `local list` can find the file
+/// later and the decoder knows what it's reading.
+pub fn attachment_filename(
+    filename: &str,
+    content_type: Option<&str>,
+) -> String {
+    let name = local_player::sanitize_filename(filename);
+    if local_player::has_audio_extension(&name) {
+        return name;
+    }
+    let ext = audio_ext_from_content_type(content_type).unwrap_or("mp3");
+    format!("{name}.{ext}")
+}
+
+pub fn is_audio(
     filename: &str,
     content_type: Option<&str>,
 ) -> bool {
@@ -205,7 +214,7 @@ fn percent_decode(input: &str) -> String {
 
 pub fn build_local_track(
     path: PathBuf,
-    added_by: String,
+    added_by: Requester,
 ) -> Track {
     let title = local_player::track_title(&path);
     let id = path.to_string_lossy().to_string();
@@ -219,9 +228,13 @@ pub fn build_local_track(
             channel: "Local file".to_string(),
             track_url: display_url,
             play_url: None,
+            thumbnail: None,
             duration: None,
+            skip_segments: Vec::new(),
+            chapters: None,
         },
         added_by,
         source: TrackSource::Local(path),
+        range: None,
     }
 }
@@ -0,0 +1,146 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks;
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::playback::Playback;
+use crate::service::embed_service::SendEmbed;
+use crate::service::filter_service::{EqPreset, Equalizer, MAX_EQ_GAIN};
+use crate::service::player_service;
+
+/// Show the equalizer, or apply a preset (flat, bass, vocal, treble).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    subcommands("show", "preset", "band", "reset"),
+    aliases("equalizer")
+)]
+pub async fn eq(
+    ctx: Context<'_>,
+    #[description = "flat, bass, vocal or treble"] preset: Option<String>,
+) -> Result<(), MusicBotError> {
+    // Default action when called without a subcommand: apply the preset, or
+    // show the equalizer when there's none.
+    match preset {
+        Some(name) => apply_preset(ctx, &name).await,
+        None => show_inner(ctx).await,
+    }
+}
+
+/// Show the equalizer curve.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn show(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    show_inner(ctx).await
+}
+
+/// Replace the curve with a preset.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn preset(
+    ctx: Context<'_>,
+    #[description = "flat, bass, vocal or treble"] name: String,
+) -> Result<(), MusicBotError> {
+    apply_preset(ctx, &name).await
+}
+
+/// Set one band's gain, e.g. `band 62 +4` or `band 8 -3` (-12 to 12 dB).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn band(
+    ctx: Context<'_>,
+    #[description = "Band number (1-10) or frequency, e.g. 62 or 4k"] band: String,
+    #[description = "Gain in dB, from -12 to 12"] gain: f32,
+) -> Result<(), MusicBotError> {
+    let Some(index) = Equalizer::band_index(&band) else {
+        return Err(MusicBotError::InternalError(format!(
+            "Unknown band `{}`. Use a band number from 1 to 10 or a frequency like 62, 1k or 16k.",
+            band.trim()
+        )));
+    };
+    if !(-MAX_EQ_GAIN..=MAX_EQ_GAIN).contains(&gain) {
+        return Err(MusicBotError::InternalError(format!(
+            "Invalid gain `{gain}`. Use a value from -{MAX_EQ_GAIN} to {MAX_EQ_GAIN} dB."
+        )));
+    }
+    update(ctx, |equalizer| equalizer.set_band(index, gain)).await
+}
+
+/// Set every band back to 0 dB.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    aliases("flat", "off", "clear")
+)]
+pub async fn reset(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    update(ctx, |equalizer| *equalizer = Equalizer::default()).await
+}
+
+async fn apply_preset(
+    ctx: Context<'_>,
+    name: &str,
+) -> Result<(), MusicBotError> {
+    let Some(preset) = EqPreset::parse(name) else {
+        return Err(MusicBotError::InternalError(format!(
+            "Unknown preset `{}`. Use flat, bass, vocal or treble.",
+            name.trim()
+        )));
+    };
+    update(ctx, |equalizer| *equalizer = Equalizer::from_preset(preset)).await
+}
+
+/// Change and save the equalizer, then re-render the current track with it,
+/// picking up where it was.
+async fn update(
+    ctx: Context<'_>,
+    change: impl FnOnce(&mut Equalizer),
+) -> Result<(), MusicBotError> {
+    if !dj_checks::require_dj(ctx, "eq").await? {
+        return Ok(());
+    }
+
+    let player_arc = player_service::player(ctx).await?;
+    let mut player = player_arc.write().await;
+
+    let mut equalizer = player.equalizer;
+    change(&mut equalizer);
+
+    if equalizer != player.equalizer {
+        player.set_equalizer(equalizer).await?;
+
+        if player.is_playing {
+            let playback = Playback::from_context(ctx).await?;
+            playback.apply_effects(&mut player).await;
+        }
+    }
+    drop(player);
+
+    PlayerEmbed::EqualizerState(equalizer)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
+
+async fn show_inner(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let equalizer = player_arc.read().await.equalizer;
+
+    PlayerEmbed::EqualizerState(equalizer)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,47 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks::check_dj;
+use crate::commands::music::cmd_queue;
+use crate::embeds::music::queue_embed::QueueEmbed;
+use crate::player::player::Player;
+use crate::service::embed_service;
+use crate::service::player_service;
+use tokio::sync::RwLockWriteGuard;
+
+/// Toggle fair queueing — requesters take turns instead of playing in order.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj",
+    aliases("fair")
+)]
+pub async fn fairqueue(
+    ctx: Context<'_>,
+    state: Option<String>,
+) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+
+    let desired = match state.as_deref().map(str::trim).map(str::to_ascii_lowercase) {
+        None => !player.fair_queue,
+        Some(s) => match s.as_str() {
+            "on" | "true" | "1" | "yes" | "y" => true,
+            "off" | "false" | "0" | "no" | "n" => false,
+            _ => {
+                return Err(MusicBotError::InternalError(format!(
+                    "Unknown fair queue state `{s}`. Use `on` or `off`."
+                )));
+            }
+        },
+    };
+
+    player.set_fair_queue(desired).await?;
+
+    let confirmation = QueueEmbed::FairQueueState(desired).to_embed();
+    let embeds = cmd_queue::edit_result_embeds(&player, confirmation, 1);
+    drop(player);
+    embed_service::send_context_embeds(ctx, embeds, true, Some(30)).await?;
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks;
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::playback::Playback;
+use crate::service::embed_service::SendEmbed;
+use crate::service::filter_service::{AudioFilters, FilterPreset, MAX_PITCH_SEMITONES, MAX_SPEED, MIN_SPEED};
+use crate::service::player_service;
+
+/// Show the audio filters, or apply a preset (nightcore, vaporwave, bassboost, 8d, karaoke).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    subcommands("show", "preset", "speed", "pitch", "off"),
+    aliases("filters", "fx")
+)]
+pub async fn filter(
+    ctx: Context<'_>,
+    #[description = "nightcore, vaporwave, bassboost, 8d or karaoke"] preset: Option<String>,
+) -> Result<(), MusicBotError> {
+    // Default action when called without a subcommand: apply the preset, or
+    // show the filters when there's none.
+    match preset {
+        Some(name) => apply_preset(ctx, &name).await,
+        None => show_inner(ctx).await,
+    }
+}
+
+/// Show the audio filters in use.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn show(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    show_inner(ctx).await
+}
+
+/// Apply a preset. Nightcore and vaporwave set speed and pitch; the rest toggle.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn preset(
+    ctx: Context<'_>,
+    #[description = "nightcore, vaporwave, bassboost, 8d or karaoke"] name: String,
+) -> Result<(), MusicBotError> {
+    apply_preset(ctx, &name).await
+}
+
+/// Play faster or slower, e.g. `1.25` (0.5-2).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn speed(
+    ctx: Context<'_>,
+    #[description = "Playback speed, from 0.5 to 2"] value: f32,
+) -> Result<(), MusicBotError> {
+    if !(MIN_SPEED..=MAX_SPEED).contains(&value) {
+        return Err(MusicBotError::InternalError(format!(
+            "Invalid speed `{value}`. Use a value from {MIN_SPEED} to {MAX_SPEED}."
+        )));
+    }
+    update(ctx, |filters| filters.speed = value).await
+}
+
+/// Shift the pitch by a number of semitones, e.g. `3` or `-2.5` (-12 to 12).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn pitch(
+    ctx: Context<'_>,
+    #[description = "Semitones up or down, from -12 to 12"] semitones: f32,
+) -> Result<(), MusicBotError> {
+    if !(-MAX_PITCH_SEMITONES..=MAX_PITCH_SEMITONES).contains(&semitones) {
+        return Err(MusicBotError::InternalError(format!(
+            "Invalid pitch `{semitones}`. Use a number of semitones from -{MAX_PITCH_SEMITONES} to {MAX_PITCH_SEMITONES}."
+        )));
+    }
+    update(ctx, |filters| filters.set_pitch_semitones(semitones)).await
+}
+
+/// Turn every filter off.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    aliases("clear", "reset")
+)]
+pub async fn off(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    update(ctx, |filters| *filters = AudioFilters::default()).await
+}
+
+async fn apply_preset(
+    ctx: Context<'_>,
+    name: &str,
+) -> Result<(), MusicBotError> {
+    let Some(preset) = FilterPreset::parse(name) else {
+        return Err(MusicBotError::InternalError(format!(
+            "Unknown filter `{}`. Use nightcore, vaporwave, bassboost, 8d or karaoke.",
+            name.trim()
+        )));
+    };
+    update(ctx, |filters| filters.apply_preset(preset)).await
+}
+
+/// Change the filters and re-render the current track with them, picking up
+/// where it was.
+async fn update(
+    ctx: Context<'_>,
+    change: impl FnOnce(&mut AudioFilters),
+) -> Result<(), MusicBotError> {
+    if !dj_checks::require_dj(ctx, "filter").await? {
+        return Ok(());
+    }
+
+    let player_arc = player_service::player(ctx).await?;
+    let mut player = player_arc.write().await;
+
+    let mut filters = player.filters;
+    change(&mut filters);
+
+    if filters != player.filters {
+        player.filters = filters;
+        tracing::info!("Filters set to {}", filters);
+
+        if player.is_playing {
+            let playback = Playback::from_context(ctx).await?;
+            playback.apply_effects(&mut player).await;
+        }
+    }
+    drop(player);
+
+    PlayerEmbed::FilterState(filters)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
+
+async fn show_inner(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let filters = player_arc.read().await.filters;
+
+    PlayerEmbed::FilterState(filters)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
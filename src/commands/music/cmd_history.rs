@@ -6,6 +6,7 @@ use crate::player::track::Track;
 use crate::service::channel_service;
 use crate::service::embed_service::SendEmbed;
 use crate::service::interaction_service::DeferredInteractionStream;
+use crate::service::player_service;
 use serenity::all::{ButtonStyle, CreateActionRow, CreateButton, CreateInteractionResponseFollowup, Message};
 use std::collections::{HashMap, VecDeque};
 use std::time::{Duration, Instant};
@@ -18,7 +19,8 @@ use tokio::sync::RwLockWriteGuard;
     check = "check_author_in_same_voice_channel"
 )]
 pub async fn history(ctx: Context<'_>) -> Result<(), MusicBotError> {
-    let player = ctx.data().player.read().await;
+    let player_arc = player_service::player(ctx).await?;
+    let player = player_arc.read().await;
     let history: VecDeque<Track> = player.history.clone();
     drop(player);
 
@@ -117,7 +119,7 @@ pub async fn history(ctx: Context<'_>) -> Result<(), MusicBotError> {
             .unwrap();
         let track: Track = tracks_rev[track_index].clone();
 
-        let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+        let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
         player.add_track_to_queue(ctx, track, false).await?;
         drop(player);
 
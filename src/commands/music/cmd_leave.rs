@@ -1,5 +1,6 @@
 use crate::bot::{Context, MusicBotError};
 use crate::checks::channel_checks::check_author_in_voice_channel;
+use crate::checks::dj_checks::check_dj;
 use crate::service::channel_service;
 use crate::service::embed_service::SendEmbed;
 use serenity::all::{Color, CreateEmbed};
@@ -8,11 +9,14 @@ use serenity::all::{Color, CreateEmbed};
 #[poise::command(
     prefix_command,
     slash_command,
-    check = "check_author_in_voice_channel"
+    check = "check_author_in_voice_channel",
+    check = "check_dj"
 )]
 pub async fn leave(ctx: Context<'_>) -> Result<(), MusicBotError> {
     channel_service::leave_channel(ctx).await?;
-    crate::player::player::set_idle(ctx.serenity_context());
+    if let Some(guild_id) = ctx.guild_id() {
+        crate::player::player::set_idle(ctx.serenity_context(), guild_id);
+    }
 
     CreateEmbed::new()
         .color(Color::DARK_BLUE)
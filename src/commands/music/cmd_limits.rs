@@ -0,0 +1,116 @@
+use crate::bot::{Context, MusicBotError};
+use crate::embeds::music::queue_embed::QueueEmbed;
+use crate::player::player::QueueLimits;
+use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
+use crate::utils::time_utils;
+use std::time::Duration;
+
+/// Show the queue limits for this server.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("show", "user", "duration", "playlist"),
+    aliases("limit")
+)]
+pub async fn limits(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    // Default action when called without a subcommand: show the limits.
+    show_inner(ctx).await
+}
+
+/// Show the queue limits for this server.
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    show_inner(ctx).await
+}
+
+/// Cap how many tracks one person can have queued (`off` to remove).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn user(
+    ctx: Context<'_>,
+    #[description = "Most tracks per person, or `off`"] max: String,
+) -> Result<(), MusicBotError> {
+    let max = parse_count(&max)?;
+    update(ctx, |limits| limits.max_tracks_per_user = max).await
+}
+
+/// Cap the total length of the queue, e.g. `2h` or `90m` (`off` to remove).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn duration(
+    ctx: Context<'_>,
+    #[description = "Longest total queue, e.g. 2h or 1h30m, or `off`"] max: String,
+) -> Result<(), MusicBotError> {
+    let max: Option<Duration> = match max.trim().to_ascii_lowercase().as_str() {
+        "off" | "none" | "0" => None,
+        text => Some(
+            time_utils::parse_duration_from_string(text)
+                .filter(|d| !d.is_zero())
+                .ok_or_else(|| {
+                    MusicBotError::InternalError(format!(
+                        "Invalid duration `{text}`. Use something like `2h` or `90m`, or `off`."
+                    ))
+                })?,
+        ),
+    };
+    update(ctx, |limits| limits.max_queue_duration = max).await
+}
+
+/// Cap how many tracks one playlist import may add (`off` to remove).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    required_permissions = "MANAGE_GUILD"
+)]
+pub async fn playlist(
+    ctx: Context<'_>,
+    #[description = "Most tracks per playlist import, or `off`"] max: String,
+) -> Result<(), MusicBotError> {
+    let max = parse_count(&max)?;
+    update(ctx, |limits| limits.max_playlist_size = max).await
+}
+
+fn parse_count(text: &str) -> Result<Option<u32>, MusicBotError> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "off" | "none" | "0" => Ok(None),
+        text => text.parse::<u32>().map(Some).map_err(|_| {
+            MusicBotError::InternalError(format!(
+                "Invalid limit `{text}`. Use a positive number, or `off`."
+            ))
+        }),
+    }
+}
+
+async fn update(
+    ctx: Context<'_>,
+    change: impl FnOnce(&mut QueueLimits),
+) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let mut player = player_arc.write().await;
+
+    let mut limits = player.limits;
+    change(&mut limits);
+    player.set_queue_limits(limits).await?;
+    drop(player);
+
+    show_inner(ctx).await
+}
+
+async fn show_inner(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let limits = player_arc.read().await.limits;
+
+    QueueEmbed::Limits(&limits)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
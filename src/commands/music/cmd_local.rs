@@ -1,13 +1,15 @@
 use crate::bot::{Context, MusicBotError};
 use crate::checks::channel_checks::check_author_in_same_voice_channel;
 use crate::commands::music::cmd_download::{build_local_track, save_to_library, DownloadSource};
+use crate::commands::music::cmd_play;
 use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::embeds::music::queue_embed::QueueEmbed;
 use crate::player::player::Player;
-use crate::player::track::Track;
+use crate::player::track::{Requester, Track};
 use crate::service::channel_service;
 use crate::service::embed_service::SendEmbed;
 use crate::service::picker_service::{self, PickerOutcome};
+use crate::service::player_service;
 use crate::sources::local_player;
 use serenity::all::Attachment;
 use std::path::PathBuf;
@@ -408,8 +410,15 @@ async fn enqueue_path(
     ctx: Context<'_>,
     path: PathBuf,
 ) -> Result<(), MusicBotError> {
-    let track: Track = build_local_track(path, ctx.author().name.clone());
-    let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+    let track: Track = build_local_track(path, Requester::user(ctx.author()));
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+
+    if let Err(error) = player.push_track(track.clone(), false) {
+        drop(player);
+        cmd_play::report_playback_error(ctx, error).await?;
+        return Ok(());
+    }
 
     if player.is_playing {
         QueueEmbed::TrackAdded(&track)
@@ -418,12 +427,9 @@ async fn enqueue_path(
             .await?;
     }
 
-    if let Err(error) = player.add_track_to_queue(ctx, track, false).await {
+    if let Err(error) = player.kick_off_playback(ctx, false).await {
         drop(player);
-        PlayerEmbed::PlaybackErrorEmbed(error.to_string())
-            .to_embed()
-            .send_context(ctx, true, Some(30))
-            .await?;
+        cmd_play::report_playback_error(ctx, error).await?;
         return Ok(());
     }
     drop(player);
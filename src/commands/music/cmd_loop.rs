@@ -0,0 +1,62 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks::check_dj;
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::player::{LoopMode, Player};
+use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
+use tokio::sync::RwLockWriteGuard;
+
+/// Set the loop mode (off, track, queue), optionally for a number of repeats.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "loop",
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj",
+    aliases("repeat")
+)]
+pub async fn loop_mode(
+    ctx: Context<'_>,
+    #[description = "off, track or queue (cycles through them when omitted)"] mode: Option<String>,
+    #[description = "How many more times to repeat (loops until turned off when omitted)"] count: Option<u32>,
+) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+
+    let desired = match mode.as_deref().map(str::trim).map(str::to_ascii_lowercase) {
+        None => match player.looping.mode {
+            LoopMode::Off => LoopMode::Track,
+            LoopMode::Track => LoopMode::Queue,
+            LoopMode::Queue => LoopMode::Off,
+        },
+        Some(s) => match s.as_str() {
+            "off" | "none" | "false" | "0" | "no" | "n" => LoopMode::Off,
+            "track" | "song" | "one" | "single" => LoopMode::Track,
+            "queue" | "all" | "playlist" => LoopMode::Queue,
+            _ => {
+                return Err(MusicBotError::InternalError(format!(
+                    "Unknown loop mode `{s}`. Use `off`, `track` or `queue`."
+                )));
+            }
+        },
+    };
+
+    // A repeat count of zero is the same as not looping at all.
+    let (desired, count) = match count {
+        Some(0) => (LoopMode::Off, None),
+        _ if desired == LoopMode::Off => (LoopMode::Off, None),
+        count => (desired, count),
+    };
+
+    player.set_looping(desired, count);
+    let looping = player.looping;
+    drop(player);
+
+    PlayerEmbed::LoopState(looping)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
@@ -3,6 +3,7 @@ use crate::checks::channel_checks::check_author_in_same_voice_channel;
 use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::player::player::{self, Player};
 use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
 use tokio::sync::RwLockWriteGuard;
 
 /// Toggle session-only cross-track loudness normalization (resets on restart).
@@ -16,7 +17,7 @@ pub async fn normalize(
     ctx: Context<'_>,
     state: Option<String>,
 ) -> Result<(), MusicBotError> {
-    let player_arc = ctx.data().player.clone();
+    let player_arc = player_service::player(ctx).await?;
     let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
 
     let desired = match state.as_deref().map(str::trim).map(str::to_ascii_lowercase) {
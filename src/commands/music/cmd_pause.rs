@@ -4,6 +4,7 @@ use crate::checks::player_checks::check_if_player_is_playing;
 use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::player::player::Player;
 use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
 use tokio::sync::RwLockWriteGuard;
 
 #[poise::command(
@@ -13,7 +14,8 @@ use tokio::sync::RwLockWriteGuard;
     check = "check_if_player_is_playing"
 )]
 pub async fn pause(ctx: Context<'_>) -> Result<(), MusicBotError> {
-    let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
 
     player.pause().await?;
 
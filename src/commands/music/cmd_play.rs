@@ -1,20 +1,33 @@
 use crate::bot::{Context, MusicBotError};
 use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks::check_dj;
+use crate::commands::music::cmd_download;
 use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::embeds::music::queue_embed::QueueEmbed;
 use crate::player::player::Player;
-use crate::player::track::{Track, MAX_TRACK_DURATION};
+use crate::player::track::{PlaybackError, Playlist, Requester, Track, TrackRange, TrackSource, MAX_TRACK_DURATION};
+use crate::service::attachment_service;
 use crate::service::channel_service;
 use crate::service::embed_service::SendEmbed;
 use crate::service::picker_service::{self, PickerOutcome};
+use crate::service::player_service;
+use crate::service::sponsorblock_service;
 use crate::sources::spotify_player::{SpotifyClient, SpotifyError, SpotifySearchResult};
-use crate::sources::youtube_player::{SearchError, YouTubeSearchResult};
+use crate::sources::youtube_player::{self, SearchError, YouTubeSearchResult};
+use crate::sources::{generic_player, local_player, radio_player};
+use crate::utils::time_utils;
+use regex::Regex;
+use serenity::all::{Attachment, Message};
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::Duration;
 use tokio::sync::RwLockWriteGuard;
 
 const YOUTUBE_VIDEO_URL: &str = "https://www.youtube.com/watch?v=";
 const YOUTUBE_PLAYLIST_URL: &str = "https://www.youtube.com/playlist?list=";
 
-/// Play a track or playlist from YouTube or Spotify.
+/// Play a track or playlist from YouTube or Spotify, or an attached audio
+/// file without saving it.
 #[poise::command(
     prefix_command,
     slash_command,
@@ -23,16 +36,61 @@ const YOUTUBE_PLAYLIST_URL: &str = "https://www.youtube.com/playlist?list=";
 pub async fn play(
     ctx: Context<'_>,
     track_source: Vec<String>,
+    #[description = "Audio file to play once, without saving it"] file: Option<Attachment>,
 ) -> Result<(), MusicBotError> {
+    // Prefix users just attach their files to the message, and poise fills
+    // `file` with the first of them whatever it is. Only audio attachments
+    // count there, so a stray screenshot doesn't hijack a search.
+    let attachments: Vec<Attachment> = match ctx {
+        poise::Context::Application(_) => file.into_iter().collect(),
+        poise::Context::Prefix(prefix) => prefix
+            .msg
+            .attachments
+            .iter()
+            .filter(|attachment| is_audio_attachment(attachment))
+            .cloned()
+            .collect(),
+    };
+
+    if !attachments.is_empty() {
+        return play_attachments(ctx, attachments).await;
+    }
     do_play(ctx, track_source.join(" "), false).await
 }
 
+/// Play a message's audio attachments via right-click → Apps → Play audio,
+/// without saving them to the local library.
+#[poise::command(
+    context_menu_command = "Play audio",
+    guild_only,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn play_audio_context(
+    ctx: Context<'_>,
+    message: Message,
+) -> Result<(), MusicBotError> {
+    let attachments: Vec<Attachment> = message
+        .attachments
+        .into_iter()
+        .filter(is_audio_attachment)
+        .collect();
+    if attachments.is_empty() {
+        PlayerEmbed::DownloadFailed("That message has no audio attachments.".to_string())
+            .to_embed()
+            .send_context(ctx, true, Some(30))
+            .await?;
+        return Ok(());
+    }
+    play_attachments(ctx, attachments).await
+}
+
 /// Play a track or playlist immediately by inserting it at the front of the queue.
 #[poise::command(
     prefix_command,
     slash_command,
     rename = "playtop",
-    check = "check_author_in_same_voice_channel"
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj"
 )]
 pub async fn play_top(
     ctx: Context<'_>,
@@ -46,7 +104,8 @@ pub async fn play_top(
     prefix_command,
     slash_command,
     rename = "playnow",
-    check = "check_author_in_same_voice_channel"
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj"
 )]
 pub async fn play_now(
     ctx: Context<'_>,
@@ -63,7 +122,8 @@ pub async fn play_now(
 
     ctx.defer().await?;
 
-    let result = resolve_source(ctx, &source).await?;
+    let player_arc = player_service::player(ctx).await?;
+    let result = resolve_for_queue(ctx, &source).await?;
 
     match result {
         Ok(YouTubeSearchResult::Track(mut track)) => {
@@ -77,8 +137,8 @@ pub async fn play_now(
                 .await?;
                 return Ok(());
             }
-            track.added_by = ctx.author().name.clone();
-            let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+            track.added_by = Requester::user(ctx.author());
+            let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
             if let Err(error) = player.force_play_track(ctx, track).await {
                 drop(player);
                 report_playback_error(ctx, error).await?;
@@ -111,8 +171,8 @@ pub async fn play_now(
                         .await?;
                         return Ok(());
                     }
-                    track.added_by = ctx.author().name.clone();
-                    let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+                    track.added_by = Requester::user(ctx.author());
+                    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
                     if let Err(error) = player.force_play_track(ctx, track).await {
                         drop(player);
                         report_playback_error(ctx, error).await?;
@@ -133,16 +193,17 @@ pub async fn play_now(
         }
 
         Ok(YouTubeSearchResult::Playlist(mut playlist)) => {
-            let added_by = ctx.author().name.clone();
+            let added_by = Requester::user(ctx.author());
             playlist.tracks.retain(|t| !t.is_known_too_long());
             for track in &mut playlist.tracks {
                 track.added_by = added_by.clone();
             }
-            QueueEmbed::PlaylistAdded(&playlist)
-                .to_embed()
-                .send_context(ctx, true, Some(30))
-                .await?;
-            let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+            let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+            if let Err(error) = announce_playlist(ctx, &player, &mut playlist).await? {
+                drop(player);
+                report_playback_error(ctx, error).await?;
+                return Ok(());
+            }
             if let Err(error) = player.force_play_playlist(ctx, playlist).await {
                 drop(player);
                 report_playback_error(ctx, error).await?;
@@ -166,6 +227,13 @@ pub async fn play_now(
                 .await?;
         }
 
+        Err(SearchError::InvalidRange(reason)) => {
+            PlayerEmbed::InvalidRange(reason)
+                .to_embed()
+                .send_context(ctx, true, Some(30))
+                .await?;
+        }
+
         Err(error) => {
             return Err(MusicBotError::from(error));
         }
@@ -174,16 +242,67 @@ pub async fn play_now(
     Ok(())
 }
 
-async fn resolve_source(
+/// `resolve_source` for tracks about to be queued: with the guild's
+/// SponsorBlock mode on, they're looked up right away, so the length cap
+/// and queue times already go by what's left of them.
+pub async fn resolve_for_queue(
     ctx: Context<'_>,
     track_source: &str,
 ) -> Result<Result<YouTubeSearchResult, SearchError>, MusicBotError> {
-    if track_source.starts_with(YOUTUBE_VIDEO_URL) {
-        let result = ctx
+    let (track_source, range) = match split_range(track_source) {
+        Ok(split) => split,
+        Err(error) => return Ok(Err(error)),
+    };
+    let mut result = resolve_source(ctx, track_source).await?;
+
+    // An explicit range wins over a `t=` in the URL.
+    if let Some(range) = range {
+        let tracks: &mut [Track] = match &mut result {
+            Ok(YouTubeSearchResult::Track(track)) => std::slice::from_mut(track),
+            Ok(YouTubeSearchResult::Tracks(tracks)) => tracks,
+            Ok(YouTubeSearchResult::Playlist(_)) => {
+                return Ok(Err(SearchError::InvalidRange(
+                    "Ranges only apply to a single video, not a playlist.".to_string(),
+                )));
+            }
+            Err(_) => &mut [],
+        };
+        // A radio stream has no timeline to take part of.
+        for track in tracks
+            .iter_mut()
+            .filter(|t| !matches!(t.source, TrackSource::Radio))
+        {
+            track.range = Some(range);
+        }
+    }
+
+    let sponsorblock = player_service::player(ctx).await?.read().await.sponsorblock;
+    if sponsorblock {
+        let tracks: &mut [Track] = match &mut result {
+            Ok(YouTubeSearchResult::Track(track)) => std::slice::from_mut(track),
+            Ok(YouTubeSearchResult::Tracks(tracks)) => tracks,
+            Ok(YouTubeSearchResult::Playlist(playlist)) => &mut playlist.tracks,
+            Err(_) => &mut [],
+        };
+        sponsorblock_service::annotate_all(&ctx.data().request_client, tracks).await;
+    }
+
+    Ok(result)
+}
+
+pub async fn resolve_source(
+    ctx: Context<'_>,
+    track_source: &str,
+) -> Result<Result<YouTubeSearchResult, SearchError>, MusicBotError> {
+    if let Some(link) = youtube_player::parse_video_url(track_source) {
+        let mut result = ctx
             .data()
             .youtube_client
-            .search_track_url(track_source.to_owned(), 1)
+            .search_track_url(format!("{YOUTUBE_VIDEO_URL}{}", link.id), 1)
             .await;
+        if let (Ok(YouTubeSearchResult::Track(track)), Some(start)) = (&mut result, link.start) {
+            track.range = Some(TrackRange { start, end: None });
+        }
         return Ok(result);
     }
 
@@ -206,6 +325,15 @@ async fn resolve_source(
         return Ok(result);
     }
 
+    if generic_player::is_web_url(track_source) {
+        // Live station streams play as they are; everything else, audio
+        // files included, goes through yt-dlp.
+        if let Some(track) = radio_player::resolve_url(&ctx.data().request_client, track_source.trim()).await {
+            return Ok(Ok(YouTubeSearchResult::Track(track)));
+        }
+        return Ok(generic_player::resolve_url(track_source.trim()).await);
+    }
+
     Ok(ctx
         .data()
         .youtube_client
@@ -214,7 +342,52 @@ async fn resolve_source(
 }
 
 fn is_direct_url(source: &str) -> bool {
-    source.starts_with(YOUTUBE_VIDEO_URL) || source.starts_with(YOUTUBE_PLAYLIST_URL) || SpotifyClient::is_spotify_url(source)
+    // Only the link itself, without a `from … to …` after it.
+    let source = source.split_whitespace().next().unwrap_or("");
+    youtube_player::parse_video_url(source).is_some() || source.starts_with(YOUTUBE_PLAYLIST_URL) || SpotifyClient::is_spotify_url(source) || generic_player::is_web_url(source)
+}
+
+/// Split a trailing `from 1:30 to 4:00` (either half alone works too) off
+/// a link to a single video or page. Searches are left alone, since "from"
+/// and "to" are as likely to be part of a title ("hits from 2000"), and so
+/// are Spotify links, which are played from a search too.
+fn split_range(source: &str) -> Result<(&str, Option<TrackRange>), SearchError> {
+    static RANGE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?i)^(.+?)(?:\s+from\s+(\S+))?(?:\s+to\s+(\S+))?$").unwrap());
+    let Some(captures) = RANGE.captures(source.trim()) else {
+        return Ok((source, None));
+    };
+    let (from, to) = (captures.get(2), captures.get(3));
+    let query = captures.get(1).map_or(source, |m| m.as_str());
+    if (from.is_none() && to.is_none()) || !is_direct_url(query) || SpotifyClient::is_spotify_url(query) {
+        return Ok((source, None));
+    }
+
+    let start = match from {
+        Some(text) => parse_range_time(text.as_str())?,
+        None => Duration::ZERO,
+    };
+    let end = match to {
+        Some(text) => Some(parse_range_time(text.as_str())?),
+        None => None,
+    };
+
+    if end.is_some_and(|end| end <= start) {
+        return Err(SearchError::InvalidRange(format!(
+            "The end time `{}` has to come after the start time `{}`.",
+            time_utils::format_mmss(end.unwrap_or_default()),
+            time_utils::format_mmss(start)
+        )));
+    }
+
+    Ok((query, Some(TrackRange { start, end })))
+}
+
+fn parse_range_time(text: &str) -> Result<Duration, SearchError> {
+    time_utils::parse_timestamp(text).ok_or_else(|| {
+        SearchError::InvalidRange(format!(
+            "`{text}` isn't a time. Use something like `1:30`, `1m30s` or `90`."
+        ))
+    })
 }
 
 async fn do_play(
@@ -232,6 +405,8 @@ async fn do_play(
 
     ctx.defer().await?;
 
+    let player_arc = player_service::player(ctx).await?;
+
     // For direct URLs the metadata fetch can take several seconds. Send an
     // acknowledgment now so the user sees something right away, before the
     // YouTube / Spotify API call finishes.
@@ -242,7 +417,7 @@ async fn do_play(
             .await?;
     }
 
-    let result = resolve_source(ctx, &track_source).await?;
+    let result = resolve_for_queue(ctx, &track_source).await?;
 
     match result {
         Ok(YouTubeSearchResult::Track(mut track)) => {
@@ -257,14 +432,18 @@ async fn do_play(
                 return Ok(());
             }
 
-            track.added_by = ctx.author().name.clone();
+            track.added_by = Requester::user(ctx.author());
 
             // Push first (infallible), confirm to the user, then kick off
             // playback. This order guarantees TrackAdded lands before
             // NowPlaying for an idle queue, and that we never show success
             // before a playback error — kick_off_playback is what can fail.
-            let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
-            player.push_track(track.clone(), top);
+            let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+            if let Err(error) = player.push_track(track.clone(), top) {
+                drop(player);
+                report_playback_error(ctx, error).await?;
+                return Ok(());
+            }
 
             QueueEmbed::TrackAdded(&track)
                 .to_embed()
@@ -304,10 +483,14 @@ async fn do_play(
                         .await?;
                         return Ok(());
                     }
-                    track.added_by = ctx.author().name.clone();
+                    track.added_by = Requester::user(ctx.author());
 
-                    let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
-                    player.push_track(track.clone(), top);
+                    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+                    if let Err(error) = player.push_track(track.clone(), top) {
+                        drop(player);
+                        report_playback_error(ctx, error).await?;
+                        return Ok(());
+                    }
 
                     QueueEmbed::TrackAdded(&track)
                         .to_embed()
@@ -335,7 +518,7 @@ async fn do_play(
         }
 
         Ok(YouTubeSearchResult::Playlist(mut playlist)) => {
-            let added_by = ctx.author().name.clone();
+            let added_by = Requester::user(ctx.author());
             // Strip out tracks already known to exceed the length cap (Spotify
             // and yt-dlp lazy playlists carry duration; YouTube Data API does
             // not, so those slip through and get gated again at playback).
@@ -344,12 +527,13 @@ async fn do_play(
                 track.added_by = added_by.clone();
             }
 
-            let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+            let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
 
-            QueueEmbed::PlaylistAdded(&playlist)
-                .to_embed()
-                .send_context(ctx, true, Some(30))
-                .await?;
+            if let Err(error) = announce_playlist(ctx, &player, &mut playlist).await? {
+                drop(player);
+                report_playback_error(ctx, error).await?;
+                return Ok(());
+            }
 
             if let Err(error) = player.add_playlist_to_queue(ctx, playlist, top).await {
                 drop(player);
@@ -374,6 +558,13 @@ async fn do_play(
                 .await?;
         }
 
+        Err(SearchError::InvalidRange(reason)) => {
+            PlayerEmbed::InvalidRange(reason)
+                .to_embed()
+                .send_context(ctx, true, Some(30))
+                .await?;
+        }
+
         Err(error) => {
             return Err(MusicBotError::from(error));
         }
@@ -382,13 +573,190 @@ async fn do_play(
     Ok(())
 }
 
-async fn report_playback_error(
+fn is_audio_attachment(attachment: &Attachment) -> bool {
+    cmd_download::is_audio(&attachment.filename, attachment.content_type.as_deref())
+}
+
+/// Queue `attachments` as one-off tracks. Each is fetched into the
+/// temporary attachment cache rather than `downloads/`, and its file is
+/// cleaned up once the track has left the history.
+async fn play_attachments(
     ctx: Context<'_>,
-    error: crate::player::track::PlaybackError,
+    attachments: Vec<Attachment>,
 ) -> Result<(), MusicBotError> {
-    PlayerEmbed::PlaybackErrorEmbed(error.to_string())
+    ctx.defer().await?;
+
+    let mut tracks: Vec<Track> = Vec::new();
+    for attachment in attachments {
+        if !is_audio_attachment(&attachment) {
+            PlayerEmbed::DownloadFailed(format!(
+                "Attachment `{}` doesn't look like an audio file.",
+                attachment.filename
+            ))
+            .to_embed()
+            .send_context(ctx, true, Some(30))
+            .await?;
+            continue;
+        }
+
+        let filename = cmd_download::attachment_filename(&attachment.filename, attachment.content_type.as_deref());
+        match attachment_service::fetch(
+            &ctx.data().request_client,
+            attachment.id,
+            &attachment.url,
+            &filename,
+        )
+        .await
+        {
+            Ok(path) => {
+                let mut track = cmd_download::build_local_track(path, Requester::user(ctx.author()));
+                track.metadata.title = local_player::track_title(Path::new(&filename));
+                track.metadata.channel = "Attachment".to_string();
+                tracks.push(track);
+            }
+            Err(error) => {
+                PlayerEmbed::DownloadFailed(error.to_string())
+                    .to_embed()
+                    .send_context(ctx, true, Some(30))
+                    .await?;
+            }
+        }
+    }
+    if tracks.is_empty() {
+        return Ok(());
+    }
+
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+    let mut tracks = tracks.into_iter();
+    while let Some(track) = tracks.next() {
+        if let Err(error) = player.push_track(track.clone(), false) {
+            drop(player);
+            // Nothing refers to these files now.
+            attachment_service::discard(&track);
+            tracks.for_each(|track| attachment_service::discard(&track));
+            report_playback_error(ctx, error).await?;
+            return Ok(());
+        }
+        QueueEmbed::TrackAdded(&track)
+            .to_embed()
+            .send_context(ctx, true, Some(30))
+            .await?;
+    }
+
+    if let Err(error) = player.kick_off_playback(ctx, false).await {
+        drop(player);
+        report_playback_error(ctx, error).await?;
+        return Ok(());
+    }
+    drop(player);
+
+    channel_service::join_user_channel(ctx).await?;
+    Ok(())
+}
+
+/// Trim `playlist` to the queue limits, then confirm it (and any trimming)
+/// to the user. The inner error means nothing fit; nothing was sent then.
+pub async fn announce_playlist(
+    ctx: Context<'_>,
+    player: &Player,
+    playlist: &mut Playlist,
+) -> Result<Result<(), PlaybackError>, MusicBotError> {
+    let total = playlist.tracks.len();
+    let trimmed_by = match player.admit_playlist(&mut playlist.tracks) {
+        Ok(trimmed_by) => trimmed_by,
+        Err(error) => return Ok(Err(error)),
+    };
+
+    QueueEmbed::PlaylistAdded(playlist)
         .to_embed()
         .send_context(ctx, true, Some(30))
         .await?;
+
+    if let Some(limit) = trimmed_by {
+        let kept = playlist.tracks.len();
+        QueueEmbed::PlaylistTrimmed { kept, dropped: total - kept, limit }
+            .to_embed()
+            .send_context(ctx, true, Some(30))
+            .await?;
+    }
+
+    Ok(Ok(()))
+}
+
+pub async fn report_playback_error(
+    ctx: Context<'_>,
+    error: PlaybackError,
+) -> Result<(), MusicBotError> {
+    let embed = match error {
+        PlaybackError::UserTrackLimit(max) => QueueEmbed::UserTrackLimit(max).to_embed(),
+        PlaybackError::QueueDurationLimit(max) => QueueEmbed::QueueDurationLimit(max).to_embed(),
+        error => PlayerEmbed::PlaybackErrorEmbed(error.to_string()).to_embed(),
+    };
+    embed.send_context(ctx, true, Some(30)).await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VIDEO: &str = "https://www.youtube.com/watch?v=dQw4w9WgXcQ";
+
+    #[test]
+    fn search_keeps_from_and_to() {
+        for query in ["hits from 2000", "road to 5", "letter from home", "from me to you"] {
+            let (rest, range) = split_range(query).unwrap();
+            assert_eq!(rest, query);
+            assert!(range.is_none());
+        }
+    }
+
+    #[test]
+    fn splits_range_off_url() {
+        let source = format!("{VIDEO} from 1:30 to 4:00");
+        let (rest, range) = split_range(&source).unwrap();
+        assert_eq!(rest, VIDEO);
+        assert_eq!(
+            range,
+            Some(TrackRange {
+                start: Duration::from_secs(90),
+                end: Some(Duration::from_secs(240)),
+            })
+        );
+
+        let (_, range) = split_range(&format!("{VIDEO} to 2m")).unwrap();
+        assert_eq!(
+            range,
+            Some(TrackRange {
+                start: Duration::ZERO,
+                end: Some(Duration::from_secs(120)),
+            })
+        );
+    }
+
+    #[test]
+    fn url_without_range() {
+        assert_eq!(split_range(VIDEO).unwrap(), (VIDEO, None));
+    }
+
+    #[test]
+    fn rejects_end_before_start() {
+        for source in [format!("{VIDEO} from 4:00 to 1:30"), format!("{VIDEO} from 1:30 to 1:30")] {
+            assert!(matches!(
+                split_range(&source),
+                Err(SearchError::InvalidRange(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_timestamp() {
+        for source in [format!("{VIDEO} from soon"), format!("{VIDEO} from 1:30 to 4:75")] {
+            assert!(matches!(
+                split_range(&source),
+                Err(SearchError::InvalidRange(_))
+            ));
+        }
+    }
+}
@@ -2,15 +2,17 @@ use crate::bot::{Context, MusicBotError};
 use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::player::player::Player;
 use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
 use tokio::sync::RwLockReadGuard;
 
 /// Display the currently playing track.
 #[poise::command(prefix_command, slash_command)]
 pub async fn playing(ctx: Context<'_>) -> Result<(), MusicBotError> {
-    let player: RwLockReadGuard<Player> = ctx.data().player.read().await;
+    let player_arc = player_service::player(ctx).await?;
+    let player: RwLockReadGuard<Player> = player_arc.read().await;
 
     if let Some(track) = &player.current_track {
-        PlayerEmbed::NowPlaying(track)
+        PlayerEmbed::NowPlaying { track, looping: player.looping }
             .to_embed()
             .send_context(ctx, true, Some(30))
             .await?;
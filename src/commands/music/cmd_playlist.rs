@@ -0,0 +1,360 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::commands::music::cmd_play;
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::embeds::music::playlist_embed::PlaylistEmbed;
+use crate::player::player::Player;
+use crate::player::track::{Requester, Track};
+use crate::service::attachment_service;
+use crate::service::channel_service;
+use crate::service::embed_service::SendEmbed;
+use crate::service::picker_service::{self, PickerOutcome};
+use crate::service::player_service;
+use crate::service::playlist_service::{self, SavedPlaylist, MAX_NAME_LENGTH};
+use crate::sources::youtube_player::{SearchError, YouTubeSearchResult};
+use tokio::sync::RwLockWriteGuard;
+
+/// Save, edit and play your own named playlists.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands(
+        "create", "delete", "add", "remove", "list", "show", "play", "share"
+    ),
+    aliases("pl")
+)]
+pub async fn playlist(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    // Default action when called without a subcommand: list the playlists.
+    list_inner(ctx).await
+}
+
+/// Create an empty playlist.
+#[poise::command(prefix_command, slash_command)]
+pub async fn create(
+    ctx: Context<'_>,
+    #[description = "Name of the new playlist (one word)"] name: String,
+) -> Result<(), MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH || name.contains(char::is_whitespace) {
+        return send(ctx, PlaylistEmbed::InvalidName).await;
+    }
+
+    match playlist_service::create(&ctx.data().database_pool, guild_id, ctx.author().id, name).await? {
+        Some(_) => send(ctx, PlaylistEmbed::Created(name)).await,
+        None => send(ctx, PlaylistEmbed::NameTaken(name)).await,
+    }
+}
+
+/// Delete one of your playlists.
+#[poise::command(prefix_command, slash_command)]
+pub async fn delete(
+    ctx: Context<'_>,
+    #[description = "Name of your playlist"] name: String,
+) -> Result<(), MusicBotError> {
+    let Some(saved) = owned(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    playlist_service::delete(&ctx.data().database_pool, saved.id).await?;
+    send(ctx, PlaylistEmbed::Deleted(&saved.name)).await
+}
+
+/// Add the current track, or a search / URL, to one of your playlists.
+#[poise::command(prefix_command, slash_command)]
+pub async fn add(
+    ctx: Context<'_>,
+    #[description = "Name of your playlist"] name: String,
+    #[description = "Track or playlist to add (default: the current track)"]
+    #[rest]
+    query: Option<String>,
+) -> Result<(), MusicBotError> {
+    let Some(saved) = owned(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    if saved.track_count >= playlist_service::MAX_PLAYLIST_TRACKS {
+        return send(ctx, PlaylistEmbed::Full(&saved.name)).await;
+    }
+
+    let query = query
+        .map(|q| q.trim().to_string())
+        .filter(|q| !q.is_empty());
+    let tracks: Vec<Track> = match query {
+        None => {
+            let player_arc = player_service::player(ctx).await?;
+            let current: Option<Track> = player_arc.read().await.current_track.clone();
+            match current {
+                Some(track) if attachment_service::is_temporary(&track) => {
+                    return send(ctx, PlaylistEmbed::UploadNotSaved).await;
+                }
+                Some(track) => vec![track],
+                None => {
+                    PlayerEmbed::NoSongPlaying
+                        .to_embed()
+                        .send_context(ctx, true, Some(30))
+                        .await?;
+                    return Ok(());
+                }
+            }
+        }
+        Some(query) => {
+            ctx.defer().await?;
+            match resolve_tracks(ctx, &query).await? {
+                Some(tracks) => tracks,
+                None => return Ok(()),
+            }
+        }
+    };
+
+    let added = playlist_service::add_tracks(&ctx.data().database_pool, &saved, &tracks).await?;
+    send(
+        ctx,
+        PlaylistEmbed::TracksAdded {
+            playlist: &saved.name,
+            added: &tracks[..added],
+            dropped: tracks.len() - added,
+        },
+    )
+    .await
+}
+
+/// Remove an entry from one of your playlists by its number.
+#[poise::command(prefix_command, slash_command)]
+pub async fn remove(
+    ctx: Context<'_>,
+    #[description = "Name of your playlist"] name: String,
+    #[description = "Number of the entry, as shown by `playlist show`"] index: usize,
+) -> Result<(), MusicBotError> {
+    let Some(saved) = owned(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    match playlist_service::remove_track(&ctx.data().database_pool, saved.id, index).await? {
+        Some(title) => {
+            send(
+                ctx,
+                PlaylistEmbed::TrackRemoved { playlist: &saved.name, title: &title },
+            )
+            .await
+        }
+        None => send(ctx, PlaylistEmbed::InvalidIndex(index)).await,
+    }
+}
+
+/// List your playlists and the ones shared with this server.
+#[poise::command(prefix_command, slash_command)]
+pub async fn list(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    list_inner(ctx).await
+}
+
+/// Show the tracks in a playlist.
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(
+    ctx: Context<'_>,
+    #[description = "Name of the playlist"] name: String,
+) -> Result<(), MusicBotError> {
+    let Some(saved) = visible(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    let tracks = playlist_service::load_tracks(&ctx.data().database_pool, saved.id).await?;
+    PlaylistEmbed::Show { playlist: &saved, tracks: &tracks }
+        .to_embed()
+        .send_context(ctx, true, Some(120))
+        .await?;
+    Ok(())
+}
+
+/// Queue a saved playlist.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn play(
+    ctx: Context<'_>,
+    #[description = "Name of the playlist"] name: String,
+) -> Result<(), MusicBotError> {
+    let Some(saved) = visible(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    let mut loaded = saved
+        .load(&ctx.data().database_pool, Requester::user(ctx.author()))
+        .await?;
+    if loaded.tracks.is_empty() {
+        return send(ctx, PlaylistEmbed::Empty(&saved.name)).await;
+    }
+
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+
+    if let Err(error) = cmd_play::announce_playlist(ctx, &player, &mut loaded).await? {
+        drop(player);
+        cmd_play::report_playback_error(ctx, error).await?;
+        return Ok(());
+    }
+
+    if let Err(error) = player.add_playlist_to_queue(ctx, loaded, false).await {
+        drop(player);
+        cmd_play::report_playback_error(ctx, error).await?;
+        return Ok(());
+    }
+    drop(player);
+
+    channel_service::join_user_channel(ctx).await?;
+    Ok(())
+}
+
+/// Share one of your playlists with everyone in this server (`off` to make it private again).
+#[poise::command(prefix_command, slash_command)]
+pub async fn share(
+    ctx: Context<'_>,
+    #[description = "Name of your playlist"] name: String,
+    #[description = "on or off (default: on)"] state: Option<String>,
+) -> Result<(), MusicBotError> {
+    let shared = match state.as_deref().map(str::trim).map(str::to_ascii_lowercase) {
+        None => true,
+        Some(s) => match s.as_str() {
+            "on" | "true" | "1" | "yes" | "y" => true,
+            "off" | "false" | "0" | "no" | "n" => false,
+            _ => {
+                return Err(MusicBotError::InternalError(format!(
+                    "Unknown share state `{s}`. Use `on` or `off`."
+                )));
+            }
+        },
+    };
+
+    let Some(saved) = owned(ctx, &name).await? else {
+        return Ok(());
+    };
+
+    playlist_service::set_shared(&ctx.data().database_pool, saved.id, shared).await?;
+    send(
+        ctx,
+        PlaylistEmbed::SharedState { playlist: &saved.name, shared },
+    )
+    .await
+}
+
+async fn list_inner(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    let playlists = playlist_service::list_visible(&ctx.data().database_pool, guild_id, ctx.author().id).await?;
+
+    if playlists.is_empty() {
+        return send(ctx, PlaylistEmbed::ListEmpty).await;
+    }
+
+    PlaylistEmbed::List {
+        playlists: &playlists,
+        viewer: ctx.author().id,
+    }
+    .to_embed()
+    .send_context(ctx, true, Some(120))
+    .await?;
+    Ok(())
+}
+
+/// The author's own playlist `name`. Replies and returns `None` if they
+/// have none by that name.
+async fn owned(
+    ctx: Context<'_>,
+    name: &str,
+) -> Result<Option<SavedPlaylist>, MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    let name = name.trim();
+    let saved = playlist_service::find_owned(&ctx.data().database_pool, guild_id, ctx.author().id, name).await?;
+    if saved.is_none() {
+        send(ctx, PlaylistEmbed::NotFound(name)).await?;
+    }
+    Ok(saved)
+}
+
+/// Playlist `name` as the author sees it: their own, else a shared one.
+/// Replies and returns `None` if there is none.
+async fn visible(
+    ctx: Context<'_>,
+    name: &str,
+) -> Result<Option<SavedPlaylist>, MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    let name = name.trim();
+    let saved = playlist_service::find_visible(&ctx.data().database_pool, guild_id, ctx.author().id, name).await?;
+    if saved.is_none() {
+        send(ctx, PlaylistEmbed::NotFound(name)).await?;
+    }
+    Ok(saved)
+}
+
+/// Resolve `query` the way `play` does, letting the author pick from search
+/// results. Returns `None` once the outcome has been reported to the user.
+async fn resolve_tracks(
+    ctx: Context<'_>,
+    query: &str,
+) -> Result<Option<Vec<Track>>, MusicBotError> {
+    let tracks: Vec<Track> = match cmd_play::resolve_source(ctx, query).await? {
+        Ok(YouTubeSearchResult::Track(track)) => vec![track],
+        Ok(YouTubeSearchResult::Playlist(playlist)) => playlist.tracks,
+        Ok(YouTubeSearchResult::Tracks(mut tracks)) => {
+            let outcome = picker_service::show_picker(
+                ctx,
+                tracks.len(),
+                "track",
+                PlayerEmbed::Search(&tracks).to_embed(),
+                "Only the person who ran this command can select a track.",
+            )
+            .await?;
+
+            match outcome {
+                PickerOutcome::Selected(track_index) => vec![tracks.swap_remove(track_index)],
+                PickerOutcome::Cancelled => {
+                    PlayerEmbed::SearchCancelled
+                        .to_embed()
+                        .send_context(ctx, true, Some(30))
+                        .await?;
+                    return Ok(None);
+                }
+                PickerOutcome::Expired => return Ok(None),
+            }
+        }
+        Err(SearchError::VideoNotFound(_)) | Err(SearchError::PlaylistNotFound(_)) => {
+            PlayerEmbed::NoResults(query.to_owned())
+                .to_embed()
+                .send_context(ctx, true, Some(30))
+                .await?;
+            return Ok(None);
+        }
+        Err(SearchError::QuotaExceeded) => {
+            PlayerEmbed::QuotaExceeded
+                .to_embed()
+                .send_context(ctx, true, Some(60))
+                .await?;
+            return Ok(None);
+        }
+        Err(error) => return Err(MusicBotError::from(error)),
+    };
+
+    // Playback refuses overlong tracks anyway; don't save them in the first place.
+    let tracks: Vec<Track> = tracks
+        .into_iter()
+        .filter(|t| !t.is_known_too_long())
+        .collect();
+    if tracks.is_empty() {
+        PlayerEmbed::NoResults(query.to_owned())
+            .to_embed()
+            .send_context(ctx, true, Some(30))
+            .await?;
+        return Ok(None);
+    }
+
+    Ok(Some(tracks))
+}
+
+async fn send(
+    ctx: Context<'_>,
+    embed: PlaylistEmbed<'_>,
+) -> Result<(), MusicBotError> {
+    embed.to_embed().send_context(ctx, true, Some(30)).await?;
+    Ok(())
+}
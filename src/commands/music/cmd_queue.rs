@@ -1,17 +1,35 @@
 use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::commands::music::{cmd_download, cmd_play};
 use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::embeds::music::queue_embed::QueueEmbed;
 use crate::player::player::Player;
+use crate::player::track::{Requester, Track, MAX_TRACK_DURATION};
+use crate::service::attachment_service;
+use crate::service::channel_service;
 use crate::service::embed_service::SendEmbed;
 use crate::service::interaction_service::DeferredInteractionStream;
-use serenity::all::{ButtonStyle, CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponseFollowup, EditMessage};
+use crate::service::player_service;
+use crate::service::queue_file_service::{self, QueueFileFormat};
+use crate::sources::local_player;
+use crate::sources::youtube_player::{SearchError, YouTubeSearchResult};
+use crate::utils::time_utils;
+use serenity::all::{Attachment, ButtonStyle, CreateActionRow, CreateAttachment, CreateButton, CreateEmbed, CreateInteractionResponseFollowup, EditMessage};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLockReadGuard;
+use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
 
 const ITEMS_PER_PAGE: usize = 10;
 const PAGINATION_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// Largest file `queue import` accepts.
+const MAX_IMPORT_BYTES: u32 = 256 * 1024;
+/// Most lines one `queue import` resolves; the rest are reported and skipped.
+const MAX_IMPORT_LINES: usize = 200;
+/// How often the import progress embed is refreshed.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
 fn nav_buttons(
     page: usize,
     total_pages: usize,
@@ -42,23 +60,373 @@ fn build_embeds(
     let mut embeds: Vec<CreateEmbed> = Vec::new();
 
     if let Some(track) = player.current_track.as_ref() {
-        embeds.push(PlayerEmbed::NowPlaying(track).to_embed());
+        embeds.push(PlayerEmbed::NowPlaying { track, looping: player.looping }.to_embed());
+    }
+
+    if !player.queue.is_empty() {
+        embeds.push(
+            QueueEmbed::Current {
+                queue: &player.queue,
+                page,
+                looping: player.looping,
+            }
+            .to_embed(),
+        );
     }
 
+    embeds
+}
+
+/// Embeds for the reply to a queue edit: `confirmation`, then the queue page
+/// holding 1-based position `focus`, so the result shows without another
+/// `queue`. Just the confirmation once the queue is empty.
+pub fn edit_result_embeds(
+    player: &Player,
+    confirmation: CreateEmbed,
+    focus: usize,
+) -> Vec<CreateEmbed> {
+    let mut embeds = vec![confirmation];
+
     if !player.queue.is_empty() {
-        embeds.push(QueueEmbed::Current { queue: &player.queue, page }.to_embed());
+        let page = focus.clamp(1, player.queue.len()).div_ceil(ITEMS_PER_PAGE);
+        embeds.push(
+            QueueEmbed::Current {
+                queue: &player.queue,
+                page,
+                looping: player.looping,
+            }
+            .to_embed(),
+        );
     }
 
     embeds
 }
 
 /// List upcoming tracks in the queue.
-#[poise::command(prefix_command, slash_command)]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("show", "export", "import")
+)]
 pub async fn queue(
     ctx: Context<'_>,
     page: Option<usize>,
 ) -> Result<(), MusicBotError> {
-    let player: RwLockReadGuard<Player> = ctx.data().player.read().await;
+    // Default action when called without a subcommand: show the queue.
+    show_inner(ctx, page).await
+}
+
+/// List upcoming tracks in the queue.
+#[poise::command(prefix_command, slash_command)]
+pub async fn show(
+    ctx: Context<'_>,
+    page: Option<usize>,
+) -> Result<(), MusicBotError> {
+    show_inner(ctx, page).await
+}
+
+/// Upload the current track and the queue as an M3U or JSON file.
+#[poise::command(prefix_command, slash_command)]
+pub async fn export(
+    ctx: Context<'_>,
+    #[description = "m3u or json (default: m3u)"] format: Option<String>,
+) -> Result<(), MusicBotError> {
+    let format = match format
+        .as_deref()
+        .map(str::trim)
+        .map(str::to_ascii_lowercase)
+    {
+        None => QueueFileFormat::M3u,
+        Some(f) => match f.as_str() {
+            "m3u" | "m3u8" => QueueFileFormat::M3u,
+            "json" => QueueFileFormat::Json,
+            _ => {
+                return Err(MusicBotError::InternalError(format!(
+                    "Unknown export format `{f}`. Use `m3u` or `json`."
+                )));
+            }
+        },
+    };
+
+    let player_arc = player_service::player(ctx).await?;
+    let player: RwLockReadGuard<Player> = player_arc.read().await;
+    let tracks: Vec<&Track> = player
+        .current_track
+        .iter()
+        .chain(player.queue.iter())
+        .collect();
+
+    let uploads = tracks
+        .iter()
+        .filter(|t| attachment_service::is_temporary(t))
+        .count();
+
+    if tracks.len() == uploads {
+        drop(player);
+        let embed = if uploads == 0 { QueueEmbed::IsEmpty } else { QueueEmbed::UploadsNotExported };
+        embed.to_embed().send_context(ctx, true, Some(30)).await?;
+        return Ok(());
+    }
+
+    let count = tracks.len() - uploads;
+    let contents = queue_file_service::export(&tracks, format);
+    drop(player);
+
+    let filename = format!("queue.{}", format.extension());
+    ctx.send(
+        poise::CreateReply::default()
+            .embed(QueueEmbed::Exported { count, format, uploads }.to_embed())
+            .attachment(CreateAttachment::bytes(contents.into_bytes(), filename))
+            .reply(true),
+    )
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Queue every line of an exported queue, or of a text file of URLs and searches.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel"
+)]
+pub async fn import(
+    ctx: Context<'_>,
+    #[description = "An exported queue, or a text file with one URL or search per line"] file: Option<Attachment>,
+) -> Result<(), MusicBotError> {
+    // Slash users pass the file as an option; prefix users attach it to the
+    // message itself.
+    let attachment: Attachment = match file {
+        Some(att) => att,
+        None => match ctx {
+            poise::Context::Prefix(prefix) => match prefix.msg.attachments.first() {
+                Some(att) => att.clone(),
+                None => {
+                    return import_failure(
+                        ctx,
+                        "Attach an exported queue or a text file to your message.",
+                    )
+                    .await
+                }
+            },
+            _ => return import_failure(ctx, "Attach an exported queue or a text file.").await,
+        },
+    };
+
+    if attachment.size > MAX_IMPORT_BYTES {
+        return import_failure(
+            ctx,
+            &format!("`{}` is too large to be a queue file.", attachment.filename),
+        )
+        .await;
+    }
+
+    ctx.defer().await?;
+
+    let bytes = attachment
+        .download()
+        .await
+        .map_err(|e| MusicBotError::InternalError(format!("Failed to download attachment: {e}")))?;
+    let Ok(text) = String::from_utf8(bytes) else {
+        return import_failure(
+            ctx,
+            &format!("`{}` isn't a text file.", attachment.filename),
+        )
+        .await;
+    };
+
+    let mut lines = match queue_file_service::parse(&attachment.filename, &text) {
+        Ok(lines) => lines,
+        Err(reason) => return import_failure(ctx, &reason).await,
+    };
+    if lines.is_empty() {
+        return import_failure(
+            ctx,
+            &format!("`{}` has no tracks in it.", attachment.filename),
+        )
+        .await;
+    }
+
+    let mut failed: Vec<String> = Vec::new();
+    if lines.len() > MAX_IMPORT_LINES {
+        failed.push(format!(
+            "Only the first {} of {} lines were imported.",
+            MAX_IMPORT_LINES,
+            lines.len()
+        ));
+        lines.truncate(MAX_IMPORT_LINES);
+    }
+
+    let total = lines.len();
+    let progress = |done: usize, added: usize, failed: &[String], finished: bool| poise::CreateReply::default().embed(QueueEmbed::ImportProgress { done, total, added, failed, finished }.to_embed());
+
+    let reply_handle = ctx
+        .send(progress(0, 0, &failed, false).reply(true))
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    let local_files: Vec<PathBuf> = local_player::list_local_files().await.unwrap_or_default();
+    let player_arc = player_service::player(ctx).await?;
+    let requester = Requester::user(ctx.author());
+    let mut added: usize = 0;
+    let mut joined = false;
+    let mut last_update = Instant::now();
+
+    for (i, line) in lines.iter().enumerate() {
+        let shown: String = line.chars().take(60).collect();
+
+        match resolve_import_line(ctx, line, &local_files).await {
+            Ok(resolved) => {
+                let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+                let tracks: Vec<Track> = match resolved {
+                    ImportedLine::Tracks(tracks) => tracks,
+                    // Held to the same playlist limits as `play`.
+                    ImportedLine::Playlist(mut tracks) => {
+                        for track in &mut tracks {
+                            track.added_by = requester.clone();
+                        }
+                        let total = tracks.len();
+                        match player.admit_playlist(&mut tracks) {
+                            Ok(None) => tracks,
+                            Ok(Some(_)) => {
+                                failed.push(format!(
+                                    "{}. `{}` — only {} of {} tracks fit the queue limits",
+                                    i + 1,
+                                    shown,
+                                    tracks.len(),
+                                    total
+                                ));
+                                tracks
+                            }
+                            Err(error) => {
+                                failed.push(format!("{}. `{}` — {}", i + 1, shown, error));
+                                Vec::new()
+                            }
+                        }
+                    }
+                };
+
+                let mut pushed: usize = 0;
+                for mut track in tracks {
+                    track.added_by = requester.clone();
+                    if let Err(error) = player.push_track(track, false) {
+                        failed.push(format!("{}. `{}` — {}", i + 1, shown, error));
+                        break;
+                    }
+                    pushed += 1;
+                }
+                added += pushed;
+
+                // Start playing as soon as something is in rather than after
+                // the whole file resolved, and again whenever the player ran
+                // dry (a refused or short track) while later lines resolved.
+                if pushed > 0 && !player.is_playing {
+                    if !joined {
+                        drop(player);
+                        channel_service::join_user_channel(ctx).await?;
+                        joined = true;
+                        player = player_arc.write().await;
+                    }
+                    // Noted like a bad line; the next one tries again.
+                    if !player.is_playing {
+                        if let Err(error) = player.kick_off_playback(ctx, false).await {
+                            failed.push(format!(
+                                "{}. `{}` — couldn't start playback: {}",
+                                i + 1,
+                                shown,
+                                error
+                            ));
+                        }
+                    }
+                }
+            }
+            Err(reason) => failed.push(format!("{}. `{}` — {}", i + 1, shown, reason)),
+        }
+
+        if last_update.elapsed() >= PROGRESS_INTERVAL {
+            last_update = Instant::now();
+            let _ = reply_handle
+                .edit(ctx, progress(i + 1, added, &failed, false))
+                .await;
+        }
+    }
+
+    reply_handle
+        .edit(ctx, progress(total, added, &failed, true))
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// What one import line resolved to.
+enum ImportedLine {
+    Tracks(Vec<Track>),
+    /// The tracks of a playlist URL, which go through the playlist limits.
+    Playlist(Vec<Track>),
+}
+
+/// Resolve one import line the way `play` resolves its argument, taking the
+/// top search result instead of asking. Paths of files in the local library
+/// (as written by `export`) play that file.
+async fn resolve_import_line(
+    ctx: Context<'_>,
+    line: &str,
+    local_files: &[PathBuf],
+) -> Result<ImportedLine, String> {
+    let path = PathBuf::from(line);
+    if local_files.contains(&path) {
+        return Ok(ImportedLine::Tracks(vec![cmd_download::build_local_track(
+            path,
+            Requester::default(),
+        )]));
+    }
+
+    let (tracks, playlist): (Vec<Track>, bool) = match cmd_play::resolve_for_queue(ctx, line).await {
+        Ok(Ok(YouTubeSearchResult::Track(track))) => (vec![track], false),
+        Ok(Ok(YouTubeSearchResult::Tracks(tracks))) => (tracks.into_iter().take(1).collect(), false),
+        Ok(Ok(YouTubeSearchResult::Playlist(playlist))) => (playlist.tracks, true),
+        Ok(Err(SearchError::VideoNotFound(_))) | Ok(Err(SearchError::PlaylistNotFound(_))) => return Err("no results".to_string()),
+        Ok(Err(SearchError::QuotaExceeded)) => return Err("YouTube quota exceeded".to_string()),
+        Ok(Err(error)) => return Err(error.to_string()),
+        Err(error) => return Err(error.to_string()),
+    };
+
+    let tracks: Vec<Track> = tracks
+        .into_iter()
+        .filter(|t| !t.is_known_too_long())
+        .collect();
+    if tracks.is_empty() {
+        return Err(format!(
+            "longer than {}",
+            time_utils::humanize_duration(MAX_TRACK_DURATION)
+        ));
+    }
+    if playlist {
+        Ok(ImportedLine::Playlist(tracks))
+    } else {
+        Ok(ImportedLine::Tracks(tracks))
+    }
+}
+
+async fn import_failure(
+    ctx: Context<'_>,
+    reason: &str,
+) -> Result<(), MusicBotError> {
+    QueueEmbed::ImportUnreadable(reason.to_string())
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+    Ok(())
+}
+
+async fn show_inner(
+    ctx: Context<'_>,
+    page: Option<usize>,
+) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let player: RwLockReadGuard<Player> = player_arc.read().await;
 
     if player.queue.is_empty() && player.current_track.is_none() {
         drop(player);
@@ -133,7 +501,7 @@ pub async fn queue(
             continue;
         }
 
-        let player = ctx.data().player.read().await;
+        let player = player_arc.read().await;
 
         if player.queue.is_empty() && player.current_track.is_none() {
             drop(player);
@@ -1,32 +1,91 @@
 use crate::bot::{Context, MusicBotError};
 use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks::check_dj;
 use crate::checks::player_checks::check_if_queue_is_not_empty;
+use crate::commands::music::cmd_queue;
 use crate::embeds::music::queue_embed::QueueEmbed;
 use crate::player::player::Player;
 use crate::player::track::PlaybackError;
-use crate::service::embed_service::SendEmbed;
+use crate::service::embed_service::{self, SendEmbed};
+use crate::service::player_service;
+use serenity::all::{CreateEmbed, UserId};
 use tokio::sync::RwLockWriteGuard;
 
-/// Remove a track from the queue by 1-based index.
+/// What `remove` was asked to take out of the queue.
+enum RemoveTarget {
+    Index(usize),
+    Range(usize, usize),
+    AddedBy(UserId),
+}
+
+fn parse_target(raw: &str) -> Option<RemoveTarget> {
+    let raw = raw.trim();
+
+    if let Some(user_id) = serenity::utils::parse_user_mention(raw) {
+        return Some(RemoveTarget::AddedBy(user_id));
+    }
+
+    if let Some((start, end)) = raw.split_once('-') {
+        let start: usize = start.trim().parse().ok()?;
+        let end: usize = end.trim().parse().ok()?;
+        return Some(RemoveTarget::Range(start, end));
+    }
+
+    raw.parse().ok().map(RemoveTarget::Index)
+}
+
+/// Remove a track by 1-based index, a range like `3-9`, or everything a @user queued.
 #[poise::command(
     prefix_command,
     slash_command,
     check = "check_author_in_same_voice_channel",
+    check = "check_dj",
     check = "check_if_queue_is_not_empty"
 )]
 pub async fn remove(
     ctx: Context<'_>,
-    index: usize,
+    #[description = "Position, range (3-9) or @user"]
+    #[rest]
+    target: String,
 ) -> Result<(), MusicBotError> {
-    let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+    let target = parse_target(&target).ok_or_else(|| {
+        MusicBotError::InternalError(format!(
+            "Unknown queue position `{}`. Use a number, a range like `3-9`, or a @mention.",
+            target.trim()
+        ))
+    })?;
+
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+
+    let result: Result<(CreateEmbed, usize), PlaybackError> = match target {
+        RemoveTarget::Index(index) => player
+            .remove_from_queue(index)
+            .await
+            .map(|track| (QueueEmbed::TrackRemoved(&track).to_embed(), index)),
+        RemoveTarget::Range(start, end) => player
+            .remove_range_from_queue(start, end)
+            .await
+            .map(|removed| {
+                let embed = QueueEmbed::TracksRemoved { count: removed.len(), added_by: None }.to_embed();
+                (embed, start.min(end))
+            }),
+        RemoveTarget::AddedBy(user_id) => {
+            let removed = player.remove_added_by(user_id).await;
+            let embed = QueueEmbed::TracksRemoved {
+                count: removed.len(),
+                added_by: Some(user_id),
+            }
+            .to_embed();
+            Ok((embed, 1))
+        }
+    };
 
-    match player.remove_from_queue(index).await {
-        Ok(track) => {
+    match result {
+        Ok((confirmation, focus)) => {
+            let embeds = cmd_queue::edit_result_embeds(&player, confirmation, focus);
             drop(player);
-            QueueEmbed::TrackRemoved(&track)
-                .to_embed()
-                .send_context(ctx, true, Some(30))
-                .await?;
+            embed_service::send_context_embeds(ctx, embeds, true, Some(30)).await?;
         }
         Err(PlaybackError::InvalidQueueIndex(i)) => {
             drop(player);
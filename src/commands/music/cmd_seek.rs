@@ -0,0 +1,234 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks::check_dj;
+use crate::checks::player_checks::check_if_player_is_playing;
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::player::{InputTimeline, Player};
+use crate::player::track::Track;
+use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
+use crate::utils::time_utils;
+use std::time::Duration;
+use tokio::sync::RwLockReadGuard;
+
+/// How long a streamed input gets to reach the requested position before we
+/// give up on it. File inputs answer immediately.
+const STREAM_SEEK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Chapters further in than this restart on `chapter prev`, rather than
+/// going back to the one before.
+const CHAPTER_RESTART_THRESHOLD: Duration = Duration::from_secs(3);
+
+pub enum SeekTarget {
+    Absolute(Duration),
+    Forward(Duration),
+    Backward(Duration),
+    NextChapter,
+    /// The start of the chapter playing, or of the one before when it
+    /// only just started.
+    PreviousChapter,
+}
+
+/// Jump to a position in the current track (e.g. `1:30`, `95`, `1m35s`).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj",
+    check = "check_if_player_is_playing"
+)]
+pub async fn seek(
+    ctx: Context<'_>,
+    #[description = "Position to jump to (mm:ss)"] position: String,
+) -> Result<(), MusicBotError> {
+    match time_utils::parse_timestamp(&position) {
+        Some(target) => seek_current(ctx, SeekTarget::Absolute(target)).await,
+        None => invalid_timestamp(ctx, position).await,
+    }
+}
+
+/// Skip ahead in the current track by a number of seconds (default 10).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj",
+    check = "check_if_player_is_playing",
+    aliases("ff")
+)]
+pub async fn forward(
+    ctx: Context<'_>,
+    #[description = "Seconds to skip ahead"] amount: Option<String>,
+) -> Result<(), MusicBotError> {
+    match parse_amount(amount.as_deref()) {
+        Some(amount) => seek_current(ctx, SeekTarget::Forward(amount)).await,
+        None => invalid_timestamp(ctx, amount.unwrap_or_default()).await,
+    }
+}
+
+/// Jump back in the current track by a number of seconds (default 10).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj",
+    check = "check_if_player_is_playing",
+    aliases("rw")
+)]
+pub async fn rewind(
+    ctx: Context<'_>,
+    #[description = "Seconds to jump back"] amount: Option<String>,
+) -> Result<(), MusicBotError> {
+    match parse_amount(amount.as_deref()) {
+        Some(amount) => seek_current(ctx, SeekTarget::Backward(amount)).await,
+        None => invalid_timestamp(ctx, amount.unwrap_or_default()).await,
+    }
+}
+
+fn parse_amount(amount: Option<&str>) -> Option<Duration> {
+    match amount {
+        None => Some(Duration::from_secs(10)),
+        Some(text) => time_utils::parse_timestamp(text),
+    }
+}
+
+async fn invalid_timestamp(
+    ctx: Context<'_>,
+    text: String,
+) -> Result<(), MusicBotError> {
+    PlayerEmbed::InvalidTimestamp(text)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+    Ok(())
+}
+
+/// Seek the current track to `target` on its own timeline and report
+/// where it ended up.
+pub async fn seek_current(
+    ctx: Context<'_>,
+    target: SeekTarget,
+) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let player: RwLockReadGuard<Player> = player_arc.read().await;
+
+    let (Some(handle), Some(track)) = (player.track_handle.clone(), player.current_track.clone()) else {
+        drop(player);
+        PlayerEmbed::NoSongPlaying
+            .to_embed()
+            .send_context(ctx, true, Some(30))
+            .await?;
+        return Ok(());
+    };
+    let seekable = player.current_input_seekable;
+    // Targets are given on the track's own timeline, which a render's speed,
+    // a stream's SponsorBlock skips and a range's start all shift.
+    let timeline: InputTimeline = player.current_timeline.clone();
+
+    // Don't hold the player across the seek — a stream can take a while, and
+    // the End event handler needs the lock if the track finishes meanwhile.
+    drop(player);
+
+    let target = match target {
+        SeekTarget::Absolute(position) => position,
+        relative => {
+            let current = handle
+                .get_info()
+                .await
+                .map(|state| timeline.track_position(state.position))
+                .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+            match relative {
+                SeekTarget::Forward(amount) => current + amount,
+                SeekTarget::Backward(amount) => current.saturating_sub(amount),
+                chapter_target => {
+                    let Some(position) = chapter_position(&track, current, chapter_target) else {
+                        let embed = if track.chapters().is_empty() {
+                            PlayerEmbed::NoChapters(&track)
+                        } else {
+                            PlayerEmbed::LastChapter(&track)
+                        };
+                        embed.to_embed().send_context(ctx, true, Some(30)).await?;
+                        return Ok(());
+                    };
+                    position
+                }
+            }
+        }
+    };
+
+    if let Some(duration) = track.duration() {
+        if target >= duration {
+            PlayerEmbed::SeekOutOfRange { position: target, duration }
+                .to_embed()
+                .send_context(ctx, true, Some(30))
+                .await?;
+            return Ok(());
+        }
+    }
+
+    tracing::info!(
+        "Seeking '{}' to {} (file input: {})",
+        track.metadata.title,
+        time_utils::format_mmss(target),
+        seekable
+    );
+
+    let input_target = timeline.input_position(target);
+    let result = if seekable {
+        handle.seek_async(input_target).await.ok()
+    } else {
+        match tokio::time::timeout(STREAM_SEEK_TIMEOUT, handle.seek_async(input_target)).await {
+            Ok(result) => result.ok(),
+            Err(_) => None,
+        }
+    }
+    .map(|position| timeline.track_position(position));
+
+    match result {
+        Some(position) => {
+            PlayerEmbed::Seeked { track: &track, position }
+                .to_embed()
+                .send_context(ctx, true, Some(30))
+                .await?;
+        }
+        None => {
+            tracing::warn!("Seek failed for '{}'", track.metadata.title);
+            PlayerEmbed::SeekUnavailable
+                .to_embed()
+                .send_context(ctx, true, Some(30))
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Where a chapter jump from `current` lands, `None` when the track has no
+/// chapter to go to.
+fn chapter_position(
+    track: &Track,
+    current: Duration,
+    target: SeekTarget,
+) -> Option<Duration> {
+    let starts: Vec<Duration> = track
+        .chapters()
+        .iter()
+        .map(|chapter| track.chapter_start(chapter))
+        .collect();
+
+    match target {
+        SeekTarget::NextChapter => starts.into_iter().find(|start| *start > current),
+        _ => {
+            let playing = starts
+                .iter()
+                .rposition(|start| *start <= current)
+                .unwrap_or(0);
+            let start = *starts.get(playing)?;
+            if current.saturating_sub(start) < CHAPTER_RESTART_THRESHOLD && playing > 0 {
+                Some(starts[playing - 1])
+            } else {
+                Some(start)
+            }
+        }
+    }
+}
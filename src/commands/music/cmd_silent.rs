@@ -3,6 +3,7 @@ use crate::checks::channel_checks::check_author_in_same_voice_channel;
 use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::player::player::Player;
 use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
 use tokio::sync::RwLockWriteGuard;
 
 /// Toggle session-only silent mode — suppresses NowPlaying (resets on restart).
@@ -16,7 +17,8 @@ pub async fn silent(
     ctx: Context<'_>,
     state: Option<String>,
 ) -> Result<(), MusicBotError> {
-    let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
 
     let desired = match state.as_deref().map(str::trim).map(str::to_ascii_lowercase) {
         None => !player.silent,
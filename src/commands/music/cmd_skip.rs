@@ -1,12 +1,20 @@
 use crate::bot::{Context, MusicBotError};
 use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks;
 use crate::checks::player_checks::check_if_player_is_playing;
+use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::embeds::music::queue_embed::QueueEmbed;
 use crate::player::player::Player;
 use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
+use crate::service::vote_skip_service;
+use serenity::all::RoleId;
 use tokio::sync::RwLockWriteGuard;
 
 /// Skip the current track. With an empty queue this stops playback instead of erroring.
+///
+/// Only the DJ or whoever queued the track skips outright; anyone else casts a vote-skip,
+/// which is always for the current track alone.
 #[poise::command(
     prefix_command,
     slash_command,
@@ -17,7 +25,43 @@ pub async fn skip(
     ctx: Context<'_>,
     amount: Option<usize>,
 ) -> Result<(), MusicBotError> {
-    let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    let player_arc = player_service::player(ctx).await?;
+
+    let roles: Vec<RoleId> = ctx
+        .author_member()
+        .await
+        .map(|member| member.roles.clone())
+        .unwrap_or_default();
+    let instant = dj_checks::may_skip_instantly(
+        ctx.serenity_context(),
+        &ctx.data().database_pool,
+        &player_arc,
+        guild_id,
+        ctx.author().id,
+        &roles,
+    )
+    .await?;
+
+    if !instant {
+        // A vote only ever skips the current track.
+        if amount.is_some_and(|amount| amount > 1) {
+            PlayerEmbed::VoteSkipsOneTrack
+                .to_embed()
+                .send_context(ctx, true, Some(30))
+                .await?;
+            return Ok(());
+        }
+
+        let outcome = vote_skip_service::cast_vote(ctx.serenity_context(), &player_arc, guild_id, ctx.author()).await?;
+        PlayerEmbed::VoteSkip(&outcome)
+            .to_embed()
+            .send_context(ctx, true, Some(30))
+            .await?;
+        return Ok(());
+    }
+
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
 
     let amount: usize = player.skip(amount.unwrap_or(1)).await?;
 
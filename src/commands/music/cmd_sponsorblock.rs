@@ -0,0 +1,47 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks::check_dj;
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::player::Player;
+use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
+use tokio::sync::RwLockWriteGuard;
+
+/// Toggle SponsorBlock — leave sponsor segments and non-music parts out of YouTube tracks.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj",
+    aliases("sb")
+)]
+pub async fn sponsorblock(
+    ctx: Context<'_>,
+    state: Option<String>,
+) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+
+    let desired = match state.as_deref().map(str::trim).map(str::to_ascii_lowercase) {
+        None => !player.sponsorblock,
+        Some(s) => match s.as_str() {
+            "on" | "true" | "1" | "yes" | "y" => true,
+            "off" | "false" | "0" | "no" | "n" => false,
+            _ => {
+                return Err(MusicBotError::InternalError(format!(
+                    "Unknown SponsorBlock state `{s}`. Use `on` or `off`."
+                )));
+            }
+        },
+    };
+
+    player.set_sponsorblock(desired).await?;
+    drop(player);
+
+    PlayerEmbed::SponsorBlockState(desired)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
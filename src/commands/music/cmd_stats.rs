@@ -0,0 +1,40 @@
+use crate::bot::{Context, MusicBotError};
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::service::embed_service::SendEmbed;
+use crate::service::history_service::{self, StatsPeriod};
+
+/// Show listening stats: top tracks, top requesters, most skipped and time listened.
+#[poise::command(prefix_command, slash_command)]
+pub async fn stats(
+    ctx: Context<'_>,
+    #[description = "week, month or all (default: week)"] period: Option<String>,
+) -> Result<(), MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+
+    let period = match period
+        .as_deref()
+        .map(str::trim)
+        .map(str::to_ascii_lowercase)
+    {
+        None => StatsPeriod::Week,
+        Some(p) => match p.as_str() {
+            "week" | "w" | "7d" => StatsPeriod::Week,
+            "month" | "m" | "30d" => StatsPeriod::Month,
+            "all" | "alltime" | "ever" => StatsPeriod::AllTime,
+            _ => {
+                return Err(MusicBotError::InternalError(format!(
+                    "Unknown period `{p}`. Use `week`, `month` or `all`."
+                )));
+            }
+        },
+    };
+
+    let stats = history_service::stats(&ctx.data().database_pool, guild_id, period).await?;
+
+    PlayerEmbed::Stats { stats: &stats, period }
+        .to_embed()
+        .send_context(ctx, true, Some(120))
+        .await?;
+
+    Ok(())
+}
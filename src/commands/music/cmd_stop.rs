@@ -1,9 +1,11 @@
 use crate::bot::{Context, MusicBotError};
 use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks::check_dj;
 use crate::checks::player_checks::check_if_player_is_playing;
 use crate::embeds::music::player_embed::PlayerEmbed;
-use crate::player::player::Player;
+use crate::player::player::{self, Player};
 use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use tokio::sync::RwLockWriteGuard;
@@ -13,10 +15,12 @@ use tokio::sync::RwLockWriteGuard;
     prefix_command,
     slash_command,
     check = "check_author_in_same_voice_channel",
+    check = "check_dj",
     check = "check_if_player_is_playing"
 )]
 pub async fn stop(ctx: Context<'_>) -> Result<(), MusicBotError> {
-    let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
 
     // Reset the flag so the freshly spawned inactivity timer isn't immediately
     // cancelled (push_track sets it to true whenever a new track is queued).
@@ -24,7 +28,9 @@ pub async fn stop(ctx: Context<'_>) -> Result<(), MusicBotError> {
     let cancel = Arc::clone(&player.inactivity_cancel);
 
     player.stop_playback().await?;
-    crate::player::player::set_idle(ctx.serenity_context());
+    if let Some(guild_id) = ctx.guild_id() {
+        player::set_idle(ctx.serenity_context(), guild_id);
+    }
     drop(player);
 
     PlayerEmbed::Stopped
@@ -42,33 +48,13 @@ pub async fn stop(ctx: Context<'_>) -> Result<(), MusicBotError> {
     let Some(guild_channel) = ctx.guild_channel().await else {
         return Ok(());
     };
-    let serenity_ctx = ctx.serenity_context().clone();
-    let player_arc = ctx.data().player.clone();
-
-    tokio::spawn(async move {
-        tokio::time::sleep(tokio::time::Duration::from_secs(5 * 60)).await;
-
-        if cancel.load(Ordering::Relaxed) {
-            tracing::debug!("Inactivity timer cancelled after stop — new track was queued");
-            return;
-        }
-        if player_arc.read().await.is_playing {
-            return;
-        }
-
-        tracing::info!("Leaving voice channel after 5 minutes of inactivity following stop");
-
-        let _ = PlayerEmbed::InactivityLeave
-            .to_embed()
-            .send_channel(serenity_ctx.http.clone(), &guild_channel, Some(60), None)
-            .await;
-
-        let _ = player_arc.write().await.stop_playback().await;
-
-        if let Some(manager) = songbird::get(&serenity_ctx).await {
-            let _ = manager.remove(guild_id).await;
-        }
-    });
+    player::spawn_inactivity_leave(
+        ctx.serenity_context().clone(),
+        player_arc,
+        guild_id,
+        guild_channel,
+        cancel,
+    );
 
     Ok(())
 }
@@ -0,0 +1,60 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks::check_dj;
+use crate::checks::player_checks::check_if_queue_is_not_empty;
+use crate::commands::music::cmd_queue;
+use crate::embeds::music::queue_embed::QueueEmbed;
+use crate::player::player::Player;
+use crate::player::track::PlaybackError;
+use crate::service::embed_service::{self, SendEmbed};
+use crate::service::player_service;
+use tokio::sync::RwLockWriteGuard;
+
+/// Swap two queued tracks.
+///
+/// Not available while fair queue is on, since requesters' turns decide the order then.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_dj",
+    check = "check_if_queue_is_not_empty"
+)]
+pub async fn swap(
+    ctx: Context<'_>,
+    #[description = "Position of the first track"] a: usize,
+    #[description = "Position of the second track"] b: usize,
+) -> Result<(), MusicBotError> {
+    let player_arc = player_service::player(ctx).await?;
+    let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
+
+    match player.swap_in_queue(a, b).await {
+        Ok(()) => {
+            let confirmation = QueueEmbed::TracksSwapped {
+                first: &player.queue[b - 1],
+                second: &player.queue[a - 1],
+            }
+            .to_embed();
+            let embeds = cmd_queue::edit_result_embeds(&player, confirmation, a.min(b));
+            drop(player);
+            embed_service::send_context_embeds(ctx, embeds, true, Some(30)).await?;
+        }
+        Err(PlaybackError::InvalidQueueIndex(i)) => {
+            drop(player);
+            QueueEmbed::InvalidIndex(i)
+                .to_embed()
+                .send_context(ctx, true, Some(30))
+                .await?;
+        }
+        Err(PlaybackError::FairQueueActive) => {
+            drop(player);
+            QueueEmbed::FairQueueActive
+                .to_embed()
+                .send_context(ctx, true, Some(30))
+                .await?;
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    Ok(())
+}
@@ -1,8 +1,10 @@
 use crate::bot::{Context, MusicBotError};
 use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::dj_checks;
 use crate::embeds::music::player_embed::PlayerEmbed;
 use crate::player::player::Player;
 use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
 use tokio::sync::{RwLockReadGuard, RwLockWriteGuard};
 
 /// Default cap when no `!` suffix is supplied. With a trailing `!` (e.g.
@@ -32,10 +34,15 @@ pub async fn volume(
             .parse()
             .map_err(|_| MusicBotError::InternalError(format!("Invalid volume value: {raw}")))?;
 
+        if extended && !dj_checks::require_dj(ctx, "volume").await? {
+            return Ok(());
+        }
+
         let max = if extended { EXTENDED_MAX_VOLUME } else { DEFAULT_MAX_VOLUME };
         let clamped = parsed.clamp(1.0, max);
 
-        let mut player: RwLockWriteGuard<Player> = ctx.data().player.write().await;
+        let player_arc = player_service::player(ctx).await?;
+        let mut player: RwLockWriteGuard<Player> = player_arc.write().await;
         player.set_volume(clamped).await?;
 
         PlayerEmbed::VolumeChanged(clamped)
@@ -43,7 +50,8 @@ pub async fn volume(
             .send_context(ctx, true, Some(30))
             .await?;
     } else {
-        let player: RwLockReadGuard<Player> = ctx.data().player.read().await;
+        let player_arc = player_service::player(ctx).await?;
+        let player: RwLockReadGuard<Player> = player_arc.read().await;
 
         PlayerEmbed::Volume(player.volume * 100.0)
             .to_embed()
@@ -0,0 +1,79 @@
+use crate::bot::{Context, MusicBotError};
+use crate::checks::channel_checks::check_author_in_same_voice_channel;
+use crate::checks::permission_checks;
+use crate::checks::player_checks::check_if_player_is_playing;
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::service::embed_service::SendEmbed;
+use crate::service::player_service;
+use crate::service::vote_skip_service;
+
+/// Vote to skip the current track (or configure the vote threshold).
+#[poise::command(
+    prefix_command,
+    slash_command,
+    subcommands("vote", "threshold"),
+    aliases("vs")
+)]
+pub async fn voteskip(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    // Default action when called without a subcommand: cast a vote, under
+    // the same checks as `vote`. They run here rather than as attributes
+    // because poise applies a parent's checks to its subcommands too, and
+    // `threshold` shouldn't need anything playing.
+    if !check_author_in_same_voice_channel(ctx).await? || !check_if_player_is_playing(ctx).await? {
+        return Ok(());
+    }
+    vote_inner(ctx).await
+}
+
+/// Vote to skip the current track.
+#[poise::command(
+    prefix_command,
+    slash_command,
+    check = "check_author_in_same_voice_channel",
+    check = "check_if_player_is_playing"
+)]
+pub async fn vote(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    vote_inner(ctx).await
+}
+
+/// Show or set the share of listeners (1-100%) needed to skip a track.
+#[poise::command(prefix_command, slash_command)]
+pub async fn threshold(
+    ctx: Context<'_>,
+    #[description = "Percentage of listeners that must vote (1-100)"] percent: Option<u8>,
+) -> Result<(), MusicBotError> {
+    // Anyone can look; changing it needs Manage Server.
+    if percent.is_some() && !permission_checks::require_manage_guild(ctx).await? {
+        return Ok(());
+    }
+
+    let player_arc = player_service::player(ctx).await?;
+    let mut player = player_arc.write().await;
+
+    if let Some(percent) = percent {
+        player.set_vote_skip_percent(percent).await?;
+    }
+    let percent = player.vote_skip_percent;
+    drop(player);
+
+    PlayerEmbed::VoteSkipThreshold(percent)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
+
+async fn vote_inner(ctx: Context<'_>) -> Result<(), MusicBotError> {
+    let guild_id = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    let player_arc = player_service::player(ctx).await?;
+
+    let outcome = vote_skip_service::cast_vote(ctx.serenity_context(), &player_arc, guild_id, ctx.author()).await?;
+
+    PlayerEmbed::VoteSkip(&outcome)
+        .to_embed()
+        .send_context(ctx, true, Some(30))
+        .await?;
+
+    Ok(())
+}
@@ -1,10 +1,13 @@
 use crate::bot::MusicBotError;
-use serenity::all::{ChannelId, Color, CreateEmbed};
+use serenity::all::{ChannelId, Color, CreateEmbed, RoleId};
 
 pub enum BotEmbed<'a> {
     CurrentUserNotInVoiceChannel,
     CurrentUserNotInSharedChannel(&'a ChannelId),
     TargetUserNotInVoiceChannel,
+    DjRoleRequired(&'a RoleId),
+    ManageServerRequired,
+    BackNotAllowed,
     YouShallNotKickMe,
     Error(MusicBotError),
 }
@@ -29,6 +32,21 @@ impl<'a> BotEmbed<'a> {
                 .color(Color::DARK_RED)
                 .title("🚫  Target user not in voice channel")
                 .description("The target user needs to be in a voice channel to use this command."),
+            BotEmbed::DjRoleRequired(role_id) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🎧  DJ only")
+                .description(format!(
+                    "This command needs the <@&{}> role — unless you're alone with the bot or queued the current track.",
+                    role_id
+                )),
+            BotEmbed::ManageServerRequired => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🔒  Manage Server only")
+                .description("Changing this setting needs the **Manage Server** permission."),
+            BotEmbed::BackNotAllowed => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🎧  DJ only")
+                .description("Only the DJ or the person who queued this track can go back. Use **Vote skip** to move on instead."),
             BotEmbed::YouShallNotKickMe => CreateEmbed::new()
                 .color(Color::DARK_RED)
                 .title("🤬 Hey you, fucker!")
@@ -1,2 +1,3 @@
 pub mod player_embed;
+pub mod playlist_embed;
 pub mod queue_embed;
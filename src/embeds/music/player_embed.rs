@@ -1,7 +1,13 @@
+use crate::player::player::{LoopMode, Looping};
 use crate::player::track::{Track, TrackSource};
-use serenity::all::{Color, CreateEmbed, CreateEmbedFooter};
+use crate::service::filter_service::{AudioFilters, Equalizer, EQ_BANDS, MAX_EQ_GAIN};
+use crate::service::history_service::{GuildStats, StatsPeriod, TrackCount};
+use crate::service::vote_skip_service::VoteOutcome;
+use crate::utils::time_utils;
+use serenity::all::{ChannelId, Color, CreateEmbed, CreateEmbedFooter, RoleId};
 use std::collections::VecDeque;
 use std::path::PathBuf;
+use std::time::Duration;
 
 /// Description for embed bodies. Local tracks shouldn't render as a link
 /// (the `file://` URL isn't useful and Discord may strip it), so plain bold
@@ -20,8 +26,81 @@ fn track_description(track: &Track) -> String {
     }
 }
 
+/// Width of the control panel's progress bar, in characters.
+const PROGRESS_BAR_WIDTH: usize = 20;
+
+/// `▬▬▬▬🔘▬▬▬` with the knob at `position`, or a flat bar when the length is
+/// unknown.
+fn progress_bar(
+    position: Duration,
+    duration: Option<Duration>,
+) -> String {
+    let Some(duration) = duration.filter(|d| !d.is_zero()) else {
+        return "▬".repeat(PROGRESS_BAR_WIDTH);
+    };
+    let ratio = (position.as_secs_f64() / duration.as_secs_f64()).clamp(0.0, 1.0);
+    let knob = ((PROGRESS_BAR_WIDTH - 1) as f64 * ratio).round() as usize;
+    format!(
+        "{}🔘{}",
+        "▬".repeat(knob),
+        "▬".repeat(PROGRESS_BAR_WIDTH - 1 - knob)
+    )
+}
+
+/// `📖 3/12 · Title` for the chapter playing at `position`, `None` when
+/// the track has no chapters or none has started yet.
+fn chapter_line(
+    track: &Track,
+    position: Duration,
+) -> Option<String> {
+    let index = track.chapter_at(position)?;
+    let chapters = track.chapters();
+    let title = if chapters[index].title.is_empty() { "Untitled" } else { chapters[index].title.as_str() };
+    Some(format!("📖 {}/{} · *{}*", index + 1, chapters.len(), title))
+}
+
+/// Longest chapter list shown, to stay under Discord's description limit.
+const MAX_LISTED_CHAPTERS: usize = 25;
+
+/// One row per equalizer band: a bar growing left for a cut and right for a
+/// boost, one character per dB.
+fn eq_curve(equalizer: &Equalizer) -> String {
+    let width = MAX_EQ_GAIN as usize;
+    EQ_BANDS
+        .iter()
+        .zip(equalizer.gains)
+        .map(|(hz, gain)| {
+            let filled = (gain.abs().round() as usize).min(width);
+            let (cut, boost) = if gain < 0.0 { (filled, 0) } else { (0, filled) };
+            let label = if *hz >= 1000 { format!("{}k", hz / 1000) } else { hz.to_string() };
+            format!(
+                "{:>3} Hz {}{}│{}{} {:+.1} dB",
+                label,
+                " ".repeat(width - cut),
+                "█".repeat(cut),
+                "█".repeat(boost),
+                " ".repeat(width - boost),
+                gain
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 pub enum PlayerEmbed<'a> {
-    NowPlaying(&'a Track),
+    NowPlaying {
+        track: &'a Track,
+        looping: Looping,
+    },
+    /// The persistent player message, refreshed while the track plays.
+    ControlPanel {
+        track: &'a Track,
+        position: Duration,
+        paused: bool,
+        looping: Looping,
+        volume: f32,
+        queued: usize,
+    },
     Queuing(&'a str),
     NoSongPlaying,
     IsStopped,
@@ -32,16 +111,57 @@ pub enum PlayerEmbed<'a> {
     VolumeChanged(f32),
     SilentState(bool),
     NormalizeState(bool),
+    LoopState(Looping),
+    AutoplayState(bool),
+    SponsorBlockState(bool),
+    CrossfadeState(Duration),
+    FilterState(AudioFilters),
+    EqualizerState(Equalizer),
+    Stats {
+        stats: &'a GuildStats,
+        period: StatsPeriod,
+    },
     Skipped(usize),
+    VoteSkip(&'a VoteOutcome),
+    /// A non-DJ asked to skip several tracks, which a vote can't do.
+    VoteSkipsOneTrack,
+    VoteSkipThreshold(u8),
+    DjSettings {
+        role_id: Option<RoleId>,
+        gated: &'a [String],
+    },
+    Seeked {
+        track: &'a Track,
+        position: Duration,
+    },
+    SeekOutOfRange {
+        position: Duration,
+        duration: Duration,
+    },
+    SeekUnavailable,
+    InvalidTimestamp(String),
+    ChapterList {
+        track: &'a Track,
+        /// Index of the chapter playing.
+        current: Option<usize>,
+    },
+    NoChapters(&'a Track),
+    LastChapter(&'a Track),
     Shuffled,
     Search(&'a [Track]),
     SearchExpired,
     SearchCancelled,
     NoResults(String),
+    InvalidRange(String),
     MissingQuery,
     QuotaExceeded,
-    TrackTooLong { title: String, cap: std::time::Duration },
-    LivestreamNotAllowed { title: String },
+    TrackTooLong {
+        title: String,
+        cap: std::time::Duration,
+    },
+    LivestreamNotAllowed {
+        title: String,
+    },
     PlaybackErrorEmbed(String),
     InactivityLeave,
     History(&'a VecDeque<Track>),
@@ -53,10 +173,22 @@ pub enum PlayerEmbed<'a> {
     LocalEmpty,
     LocalNoMatch(&'a str),
     LocalRemoved(&'a str),
-    LocalRenamed { old: &'a str, new: &'a str },
+    LocalRenamed {
+        old: &'a str,
+        new: &'a str,
+    },
     LocalAmbiguous(&'a [PathBuf]),
     LocalPickToPlay(&'a [PathBuf]),
     LocalPickToRemove(&'a [PathBuf]),
+    RestoreOffer {
+        current: Option<&'a Track>,
+        position: Duration,
+        queued: usize,
+        voice_channel: ChannelId,
+    },
+    SessionRestored(usize),
+    SessionDiscarded,
+    SessionRestoreFailed(String),
 }
 
 impl<'a> PlayerEmbed<'a> {
@@ -66,7 +198,7 @@ impl<'a> PlayerEmbed<'a> {
                 .color(Color::DARK_BLUE)
                 .title("⏳  Queuing…")
                 .description(format!("Resolving `{}`…", source)),
-            PlayerEmbed::NowPlaying(track) => {
+            PlayerEmbed::NowPlaying { track, looping } => {
                 let song = track_description(track);
                 let author = if track.metadata.channel.is_empty() { "—".to_string() } else { track.metadata.channel.clone() };
                 let source = format!("{} {}", track.source.emoji(), track.source.label());
@@ -77,14 +209,75 @@ impl<'a> PlayerEmbed<'a> {
                     .field("Song", song, false)
                     .field("Author", author, true)
                     .field("Source", source, true);
-                if !track.added_by.is_empty() {
-                    embed = embed.footer(CreateEmbedFooter::new(format!(
-                        "Added by {}",
-                        track.added_by
-                    )));
+                let footer: Vec<String> = [(!track.added_by.is_empty()).then(|| format!("Added by {}", track.added_by)), looping.describe()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                if !footer.is_empty() {
+                    embed = embed.footer(CreateEmbedFooter::new(footer.join("  ·  ")));
+                }
+                if let Some(thumbnail) = &track.metadata.thumbnail {
+                    embed = embed.thumbnail(thumbnail);
                 }
                 embed
             }
+            PlayerEmbed::ControlPanel {
+                track,
+                position,
+                paused,
+                looping,
+                volume,
+                queued,
+            } => {
+                let author = if track.metadata.channel.is_empty() { "—".to_string() } else { track.metadata.channel.clone() };
+                let source = format!("{} {}", track.source.emoji(), track.source.label());
+                let requester = match track.added_by.id {
+                    Some(id) => format!("<@{}>", id),
+                    None if track.added_by.is_empty() => "—".to_string(),
+                    None => track.added_by.name.clone(),
+                };
+                let elapsed = match track.duration() {
+                    Some(duration) => format!(
+                        "`{}` / `{}`",
+                        time_utils::format_mmss(*position),
+                        time_utils::format_mmss(duration)
+                    ),
+                    None if matches!(track.source, TrackSource::Radio) => format!("`{}` · 🔴 Live", time_utils::format_mmss(*position)),
+                    None => format!("`{}`", time_utils::format_mmss(*position)),
+                };
+
+                let footer: Vec<String> = [Some(format!("🔊 {:.0}%", volume * 100.0)), Some(format!("{} queued", queued)), looping.describe()]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+
+                let embed = CreateEmbed::new()
+                    .color(if *paused { Color::ORANGE } else { Color::DARK_BLUE })
+                    .title(if *paused { "⏸️  Paused" } else { "🎵  Now playing" })
+                    .description(match chapter_line(track, *position) {
+                        Some(chapter) => format!(
+                            "{}\n{}\n\n{}  {}",
+                            track_description(track),
+                            chapter,
+                            progress_bar(*position, track.duration()),
+                            elapsed
+                        ),
+                        None => format!(
+                            "{}\n\n{}  {}",
+                            track_description(track),
+                            progress_bar(*position, track.duration()),
+                            elapsed
+                        ),
+                    })
+                    .field("Author", author, true)
+                    .field("Source", source, true)
+                    .field("Requested by", requester, true)
+                    .footer(CreateEmbedFooter::new(footer.join("  ·  ")));
+                match &track.metadata.thumbnail {
+                    Some(thumbnail) => embed.thumbnail(thumbnail),
+                    None => embed,
+                }
+            }
             PlayerEmbed::NoSongPlaying => CreateEmbed::new()
                 .color(Color::DARK_RED)
                 .title("🚫  No song playing")
@@ -130,6 +323,147 @@ impl<'a> PlayerEmbed<'a> {
                     .title(title)
                     .description(body)
             }
+            PlayerEmbed::AutoplayState(on) => {
+                let (title, body) = if *on {
+                    (
+                        "📻  Autoplay on",
+                        "When the queue runs out, tracks related to the last one are queued automatically.",
+                    )
+                } else {
+                    (
+                        "📻  Autoplay off",
+                        "Playback stops when the queue runs out.",
+                    )
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title(title)
+                    .description(body)
+            }
+            PlayerEmbed::SponsorBlockState(on) => {
+                let (title, body) = if *on {
+                    (
+                        "✂️  SponsorBlock on",
+                        "Sponsor segments and non-music parts marked on SponsorBlock are left out of YouTube tracks from the next one on.",
+                    )
+                } else {
+                    ("✂️  SponsorBlock off", "Upcoming tracks play in full.")
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title(title)
+                    .description(body)
+            }
+            PlayerEmbed::Stats { stats, period } => {
+                let title = format!("📊  Listening stats — {}", period.label());
+                if stats.plays == 0 {
+                    return CreateEmbed::new()
+                        .color(Color::DARK_BLUE)
+                        .title(title)
+                        .description("Nothing has been played in this period yet.");
+                }
+
+                let ranking = |lines: Vec<String>| {
+                    if lines.is_empty() {
+                        "—".to_string()
+                    } else {
+                        lines.join("\n")
+                    }
+                };
+                let tracks = |counts: &[TrackCount], unit: &str| {
+                    ranking(
+                        counts
+                            .iter()
+                            .enumerate()
+                            .map(|(i, t)| format!("{}. **{}** — {} {}", i + 1, t.title, t.count, unit))
+                            .collect(),
+                    )
+                };
+                let requesters = ranking(
+                    stats
+                        .top_requesters
+                        .iter()
+                        .enumerate()
+                        .map(|(i, r)| format!("{}. <@{}> — {} track(s)", i + 1, r.user_id, r.count))
+                        .collect(),
+                );
+
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title(title)
+                    .description(format!(
+                        "**{}** track(s) played, **{}** of listening.",
+                        stats.plays,
+                        time_utils::humanize_duration(stats.listened)
+                    ))
+                    .field("Top tracks", tracks(&stats.top_tracks, "play(s)"), false)
+                    .field("Top requesters", requesters, false)
+                    .field(
+                        "Most skipped",
+                        tracks(&stats.most_skipped, "skip(s)"),
+                        false,
+                    )
+            }
+            PlayerEmbed::FilterState(filters) => {
+                let body = if filters.is_active() {
+                    format!("Playing with **{}**.", filters)
+                } else {
+                    "No filters — tracks play as they are.".to_string()
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("🎛️  Filters")
+                    .description(body)
+                    .footer(CreateEmbedFooter::new(
+                        "Presets: nightcore, vaporwave, bassboost, 8d, karaoke · filter speed / pitch / off",
+                    ))
+            }
+            PlayerEmbed::EqualizerState(equalizer) => {
+                let body = if equalizer.is_flat() {
+                    "The equalizer is **flat** — tracks play as they are.".to_string()
+                } else {
+                    format!("Playing with the **{}** curve.", equalizer)
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("🎚️  Equalizer")
+                    .description(format!("{}\n```\n{}\n```", body, eq_curve(equalizer)))
+                    .footer(CreateEmbedFooter::new(
+                        "Presets: flat, bass, vocal, treble · eq band <band> <dB> / reset",
+                    ))
+            }
+            PlayerEmbed::CrossfadeState(crossfade) => {
+                let body = if crossfade.is_zero() {
+                    "Crossfade is **off** — each track starts when the previous one ends.".to_string()
+                } else {
+                    format!(
+                        "The last **{}s** of each track fade into the start of the next one.",
+                        crossfade.as_secs()
+                    )
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("🎚️  Crossfade")
+                    .description(body)
+            }
+            PlayerEmbed::LoopState(looping) => {
+                let body = match (looping.mode, looping.remaining) {
+                    (LoopMode::Off, _) => "Looping is **off** — tracks play once.".to_string(),
+                    (LoopMode::Track, None) => "The current track will **repeat** until looping is turned off.".to_string(),
+                    (LoopMode::Track, Some(count)) => format!("The current track will repeat **{}** more time(s).", count),
+                    (LoopMode::Queue, None) => "Finished tracks go back to the end of the queue until looping is turned off.".to_string(),
+                    (LoopMode::Queue, Some(count)) => format!("The queue will play through **{}** more time(s).", count),
+                };
+                let title = match looping.mode {
+                    LoopMode::Off => "➡️  Loop off",
+                    LoopMode::Track => "🔂  Looping track",
+                    LoopMode::Queue => "🔁  Looping queue",
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title(title)
+                    .description(body)
+            }
             PlayerEmbed::SilentState(on) => {
                 let (title, body) = if *on {
                     (
@@ -151,6 +485,160 @@ impl<'a> PlayerEmbed<'a> {
                 .color(Color::DARK_BLUE)
                 .title("⏭️  Skipped")
                 .description(format!("Skipped {} track(s).", amount)),
+            PlayerEmbed::VoteSkip(outcome) => match outcome {
+                VoteOutcome::NothingPlaying => PlayerEmbed::NoSongPlaying.to_embed(),
+                VoteOutcome::NotListening => CreateEmbed::new()
+                    .color(Color::DARK_RED)
+                    .title("🎧  Not listening")
+                    .description("Only people in the bot's voice channel can vote to skip."),
+                VoteOutcome::Counted { votes, needed } => CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("🗳️  Vote counted")
+                    .description(format!(
+                        "**{}/{}** votes to skip the current track.",
+                        votes, needed
+                    )),
+                VoteOutcome::AlreadyVoted { votes, needed } => CreateEmbed::new()
+                    .color(Color::DARK_GOLD)
+                    .title("🗳️  Already voted")
+                    .description(format!(
+                        "You already voted — **{}/{}** votes so far.",
+                        votes, needed
+                    )),
+                VoteOutcome::Passed { votes, needed } => CreateEmbed::new()
+                    .color(Color::DARK_GREEN)
+                    .title("⏭️  Vote passed")
+                    .description(format!("Skipped after **{}/{}** votes.", votes, needed)),
+                VoteOutcome::RequesterSkipped => CreateEmbed::new()
+                    .color(Color::DARK_GREEN)
+                    .title("⏭️  Skipped")
+                    .description("Skipped by the person who queued it."),
+            },
+            PlayerEmbed::VoteSkipsOneTrack => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🗳️  One track at a time")
+                .description("Only the DJ or the person who queued this track can skip several at once. Use `skip` without an amount to vote on the current track."),
+            PlayerEmbed::VoteSkipThreshold(percent) => CreateEmbed::new()
+                .color(Color::DARK_BLUE)
+                .title("🗳️  Vote-skip threshold")
+                .description(format!(
+                    "A track is skipped once **{}%** of the listeners vote for it.",
+                    percent
+                )),
+            PlayerEmbed::DjSettings { role_id, gated } => {
+                let role = match role_id {
+                    Some(role_id) => format!("<@&{}>", role_id),
+                    None => "None — every command is open to everyone".to_string(),
+                };
+                let commands = if gated.is_empty() {
+                    "None".to_string()
+                } else {
+                    gated
+                        .iter()
+                        .map(|c| format!("`{}`", c))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("🎧  DJ settings")
+                    .field("DJ role", role, false)
+                    .field("DJ-only commands", commands, false)
+                    .footer(CreateEmbedFooter::new(
+                        "Members without the role can still use these when alone with the bot or on their own track.",
+                    ))
+            }
+            PlayerEmbed::Seeked { track, position } => {
+                let at = match track.duration() {
+                    Some(duration) => format!(
+                        "{} / {}",
+                        time_utils::format_mmss(*position),
+                        time_utils::format_mmss(duration)
+                    ),
+                    None => time_utils::format_mmss(*position),
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("⏩  Seeked")
+                    .description(match chapter_line(track, *position) {
+                        Some(chapter) => format!(
+                            "{} — now at **{}**\n{}",
+                            track_description(track),
+                            at,
+                            chapter
+                        ),
+                        None => format!("{} — now at **{}**", track_description(track), at),
+                    })
+            }
+            PlayerEmbed::SeekOutOfRange { position, duration } => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Out of range")
+                .description(format!(
+                    "Can't seek to **{}** — the track is only **{}** long.",
+                    time_utils::format_mmss(*position),
+                    time_utils::format_mmss(*duration)
+                )),
+            PlayerEmbed::SeekUnavailable => CreateEmbed::new()
+                .color(Color::DARK_GOLD)
+                .title("⏳  Can't seek this stream")
+                .description("This track is still being streamed and couldn't jump there. Once it has been cached, seeking it next time is instant."),
+            PlayerEmbed::InvalidTimestamp(text) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Invalid time")
+                .description(format!(
+                    "Couldn't read `{}` as a time. Use `mm:ss`, `h:mm:ss`, seconds (`95`) or units (`1m35s`).",
+                    text
+                )),
+            PlayerEmbed::ChapterList { track, current } => {
+                let chapters = track.chapters();
+                let mut lines: Vec<String> = chapters
+                    .iter()
+                    .enumerate()
+                    .take(MAX_LISTED_CHAPTERS)
+                    .map(|(index, chapter)| {
+                        let title = if chapter.title.is_empty() { "Untitled" } else { chapter.title.as_str() };
+                        let line = format!(
+                            "`{:>2}.` `{}` {}",
+                            index + 1,
+                            time_utils::format_mmss(track.chapter_start(chapter)),
+                            title
+                        );
+                        if *current == Some(index) {
+                            format!("**▶ {}**", line)
+                        } else {
+                            line
+                        }
+                    })
+                    .collect();
+                if chapters.len() > MAX_LISTED_CHAPTERS {
+                    lines.push(format!(
+                        "…and {} more",
+                        chapters.len() - MAX_LISTED_CHAPTERS
+                    ));
+                }
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("📖  Chapters")
+                    .description(format!(
+                        "{}\n\n{}",
+                        track_description(track),
+                        lines.join("\n")
+                    ))
+            }
+            PlayerEmbed::NoChapters(track) => CreateEmbed::new()
+                .color(Color::DARK_GOLD)
+                .title("📖  No chapters")
+                .description(format!(
+                    "{} has no chapters. They're looked up when a track starts, so give it a few seconds if it just began.",
+                    track_description(track)
+                )),
+            PlayerEmbed::LastChapter(track) => CreateEmbed::new()
+                .color(Color::DARK_GOLD)
+                .title("📖  Last chapter")
+                .description(format!(
+                    "{} is already in its last chapter.",
+                    track_description(track)
+                )),
             PlayerEmbed::Shuffled => CreateEmbed::new()
                 .color(Color::DARK_BLUE)
                 .title("🔀  Shuffle")
@@ -183,6 +671,10 @@ impl<'a> PlayerEmbed<'a> {
                 .color(Color::DARK_GOLD)
                 .title("🔎  No results")
                 .description(format!("No tracks found for: **{}**", query)),
+            PlayerEmbed::InvalidRange(reason) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Invalid range")
+                .description(reason.clone()),
             PlayerEmbed::MissingQuery => CreateEmbed::new()
                 .color(Color::DARK_RED)
                 .title("🚫  Missing search query")
@@ -288,6 +780,47 @@ impl<'a> PlayerEmbed<'a> {
                 "Multiple matches — choose one to delete:",
                 files,
             ),
+            PlayerEmbed::RestoreOffer {
+                current,
+                position,
+                queued,
+                voice_channel,
+            } => {
+                let mut embed = CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("♻️  Restore last session?")
+                    .description(format!(
+                        "I was restarted while playing in <#{}>. Join that channel and press **Restore** to pick up where we left off.",
+                        voice_channel
+                    ));
+                if let Some(track) = current {
+                    embed = embed.field(
+                        "Now playing",
+                        format!(
+                            "{} at {}",
+                            track_description(track),
+                            time_utils::format_mmss(*position)
+                        ),
+                        false,
+                    );
+                }
+                embed.field("Queue", format!("{} track(s)", queued), true)
+            }
+            PlayerEmbed::SessionRestored(count) => CreateEmbed::new()
+                .color(Color::DARK_GREEN)
+                .title("♻️  Session restored")
+                .description(format!(
+                    "Restored **{}** track(s) from the last session.",
+                    count
+                )),
+            PlayerEmbed::SessionDiscarded => CreateEmbed::new()
+                .color(Color::DARK_GREY)
+                .title("🗑️  Session discarded")
+                .description("The saved queue from the last session was discarded."),
+            PlayerEmbed::SessionRestoreFailed(reason) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Could not restore session")
+                .description(reason.clone()),
         }
     }
 }
@@ -0,0 +1,196 @@
+use crate::player::track::{Track, TrackSource};
+use crate::service::playlist_service::{SavedPlaylist, MAX_NAME_LENGTH, MAX_PLAYLIST_TRACKS};
+use crate::utils::time_utils;
+use serenity::all::{Color, CreateEmbed, CreateEmbedFooter, UserId};
+
+/// How many entries `playlist show` lists before summarising the rest.
+const SHOWN_ENTRIES: usize = 20;
+
+fn entry_line(
+    index: usize,
+    track: &Track,
+) -> String {
+    let title = match &track.source {
+        TrackSource::Local(_) => format!("**{}**", track.metadata.title),
+        _ if track.metadata.track_url.is_empty() => format!("**{}**", track.metadata.title),
+        _ => format!(
+            "**[{}]({})**",
+            track.metadata.title, track.metadata.track_url
+        ),
+    };
+    match track.duration() {
+        Some(duration) => format!(
+            "{}. {} `{}`",
+            index,
+            title,
+            time_utils::format_mmss(duration)
+        ),
+        None => format!("{}. {}", index, title),
+    }
+}
+
+pub enum PlaylistEmbed<'a> {
+    Created(&'a str),
+    Deleted(&'a str),
+    NameTaken(&'a str),
+    InvalidName,
+    NotFound(&'a str),
+    Empty(&'a str),
+    Full(&'a str),
+    UploadNotSaved,
+    TracksAdded { playlist: &'a str, added: &'a [Track], dropped: usize },
+    TrackRemoved { playlist: &'a str, title: &'a str },
+    InvalidIndex(usize),
+    SharedState { playlist: &'a str, shared: bool },
+    List { playlists: &'a [SavedPlaylist], viewer: UserId },
+    ListEmpty,
+    Show { playlist: &'a SavedPlaylist, tracks: &'a [Track] },
+}
+
+impl<'a> PlaylistEmbed<'a> {
+    pub fn to_embed(&self) -> CreateEmbed {
+        match self {
+            PlaylistEmbed::Created(name) => CreateEmbed::new()
+                .color(Color::DARK_GREEN)
+                .title("💾  Playlist created")
+                .description(format!(
+                    "Created **{}**. Add tracks with `!playlist add {} <query>`.",
+                    name, name
+                )),
+            PlaylistEmbed::Deleted(name) => CreateEmbed::new()
+                .color(Color::DARK_GREEN)
+                .title("🗑️  Playlist deleted")
+                .description(format!("Deleted **{}**.", name)),
+            PlaylistEmbed::NameTaken(name) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Name taken")
+                .description(format!("You already have a playlist called **{}**.", name)),
+            PlaylistEmbed::InvalidName => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Invalid name")
+                .description(format!(
+                    "Playlist names must be a single word of at most {} characters.",
+                    MAX_NAME_LENGTH
+                )),
+            PlaylistEmbed::NotFound(name) => CreateEmbed::new()
+                .color(Color::DARK_GOLD)
+                .title("🔎  No such playlist")
+                .description(format!(
+                    "No playlist called **{}**. Use `!playlist list` to see yours and the shared ones.",
+                    name
+                )),
+            PlaylistEmbed::Empty(name) => CreateEmbed::new()
+                .color(Color::DARK_GOLD)
+                .title("💾  Empty playlist")
+                .description(format!("**{}** has no tracks yet.", name)),
+            PlaylistEmbed::Full(name) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Playlist full")
+                .description(format!(
+                    "**{}** already holds {} tracks, the most a playlist can have.",
+                    name, MAX_PLAYLIST_TRACKS
+                )),
+            PlaylistEmbed::UploadNotSaved => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Can't save an upload")
+                .description("This track was played from a Discord attachment, which is deleted once it's played. Save it with `local upload` first to keep it."),
+            PlaylistEmbed::TracksAdded { playlist, added, dropped } => {
+                let mut description = match added {
+                    [track] => format!("Added **{}** to **{}**.", track.metadata.title, playlist),
+                    _ => format!("Added {} tracks to **{}**.", added.len(), playlist),
+                };
+                if *dropped > 0 {
+                    description.push_str(&format!(
+                        "\n{} more didn't fit — a playlist holds at most {} tracks.",
+                        dropped, MAX_PLAYLIST_TRACKS
+                    ));
+                }
+                CreateEmbed::new()
+                    .color(Color::DARK_GREEN)
+                    .title("💾  Saved to playlist")
+                    .description(description)
+            }
+            PlaylistEmbed::TrackRemoved { playlist, title } => CreateEmbed::new()
+                .color(Color::DARK_GREEN)
+                .title("🗑️  Removed from playlist")
+                .description(format!("Removed **{}** from **{}**.", title, playlist)),
+            PlaylistEmbed::InvalidIndex(index) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Invalid index")
+                .description(format!("The playlist has no entry {}.", index)),
+            PlaylistEmbed::SharedState { playlist, shared } => CreateEmbed::new()
+                .color(Color::DARK_BLUE)
+                .title("💾  Playlist sharing")
+                .description(if *shared {
+                    format!(
+                        "**{}** is now shared with everyone in this server.",
+                        playlist
+                    )
+                } else {
+                    format!("**{}** is now private.", playlist)
+                }),
+            PlaylistEmbed::List { playlists, viewer } => {
+                let lines: Vec<String> = playlists
+                    .iter()
+                    .map(|p| {
+                        let owner = if p.owner_id == *viewer {
+                            if p.shared {
+                                "yours, shared".to_string()
+                            } else {
+                                "yours".to_string()
+                            }
+                        } else {
+                            format!("by <@{}>", p.owner_id)
+                        };
+                        format!("**{}** — {} track(s) · {}", p.name, p.track_count, owner)
+                    })
+                    .collect();
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("💾  Playlists")
+                    .description(lines.join("\n"))
+                    .footer(CreateEmbedFooter::new(
+                        "Play one with !playlist play <name>",
+                    ))
+            }
+            PlaylistEmbed::ListEmpty => CreateEmbed::new()
+                .color(Color::DARK_GOLD)
+                .title("💾  No playlists")
+                .description("Nobody has saved a playlist here yet. Start one with `!playlist create <name>`."),
+            PlaylistEmbed::Show { playlist, tracks } => {
+                let mut lines: Vec<String> = tracks
+                    .iter()
+                    .take(SHOWN_ENTRIES)
+                    .enumerate()
+                    .map(|(i, track)| entry_line(i + 1, track))
+                    .collect();
+                if tracks.len() > SHOWN_ENTRIES {
+                    lines.push(format!("…and {} more", tracks.len() - SHOWN_ENTRIES));
+                }
+                if lines.is_empty() {
+                    lines.push("No tracks yet.".to_string());
+                }
+
+                let total = tracks.iter().filter_map(|t| t.duration()).sum();
+                let mut footer = format!(
+                    "{} track(s), {}",
+                    tracks.len(),
+                    time_utils::humanize_duration(total)
+                );
+                if playlist.shared {
+                    footer.push_str("  ·  shared");
+                }
+
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title(format!("💾  {}", playlist.name))
+                    .description(format!(
+                        "By <@{}>\n\n{}",
+                        playlist.owner_id,
+                        lines.join("\n")
+                    ))
+                    .footer(CreateEmbedFooter::new(footer))
+            }
+        }
+    }
+}
@@ -1,6 +1,9 @@
+use crate::player::player::{Looping, QueueLimit, QueueLimits};
 use crate::player::track::{Playlist, Track, TrackSource};
-use crate::utils::string_utils;
-use serenity::all::{Color, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter};
+use crate::service::queue_file_service::QueueFileFormat;
+use crate::utils::{string_utils, time_utils};
+use serenity::all::{Color, CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, UserId};
+use std::time::Duration;
 
 /// "Location" line shown under each track in queue embeds. Local files don't
 /// have a useful URL; Spotify tracks without a permalink fall back to a label
@@ -17,12 +20,54 @@ fn track_location(track: &Track) -> String {
 
 pub enum QueueEmbed<'a> {
     IsEmpty,
-    Current { queue: &'a [Track], page: usize },
+    Current {
+        queue: &'a [Track],
+        page: usize,
+        looping: Looping,
+    },
     TrackAdded(&'a Track),
     PlaylistAdded(&'a Playlist),
     Skipped(usize),
     TrackRemoved(&'a Track),
+    TracksRemoved {
+        count: usize,
+        added_by: Option<UserId>,
+    },
+    TrackMoved {
+        track: &'a Track,
+        position: usize,
+    },
+    TracksSwapped {
+        first: &'a Track,
+        second: &'a Track,
+    },
+    Deduplicated(usize),
+    FairQueueState(bool),
+    UserTrackLimit(u32),
+    QueueDurationLimit(Duration),
+    PlaylistTrimmed {
+        kept: usize,
+        dropped: usize,
+        limit: QueueLimit,
+    },
+    Limits(&'a QueueLimits),
+    Exported {
+        count: usize,
+        format: QueueFileFormat,
+        /// One-off attachments left out of the file.
+        uploads: usize,
+    },
+    UploadsNotExported,
+    ImportUnreadable(String),
+    ImportProgress {
+        done: usize,
+        total: usize,
+        added: usize,
+        failed: &'a [String],
+        finished: bool,
+    },
     InvalidIndex(usize),
+    FairQueueActive,
     Cleared(usize),
 }
 
@@ -33,15 +78,16 @@ impl<'a> QueueEmbed<'a> {
                 .color(Color::DARK_RED)
                 .title("🚫  Empty queue")
                 .description("The queue is empty."),
-            QueueEmbed::Current { queue, page } => {
+            QueueEmbed::Current { queue, page, looping } => {
+                let mut footer = format!("Queue length: {}", queue.len());
+                if let Some(state) = looping.describe() {
+                    footer.push_str(&format!("  ·  {}", state));
+                }
                 let mut embed: CreateEmbed = CreateEmbed::new()
                     .color(Color::DARK_BLUE)
                     .title("📜  Queue")
                     .description("Upcoming tracks:")
-                    .footer(CreateEmbedFooter::new(format!(
-                        "Queue length: {}",
-                        queue.len()
-                    )));
+                    .footer(CreateEmbedFooter::new(footer));
 
                 let page: usize = *page.max(&1);
                 let mut start: usize = (page - 1) * 10;
@@ -87,12 +133,15 @@ impl<'a> QueueEmbed<'a> {
                 embed
             }
             QueueEmbed::PlaylistAdded(playlist) => {
-                let embed: CreateEmbed = CreateEmbed::new()
+                let mut embed: CreateEmbed = CreateEmbed::new()
                     .color(Color::DARK_GREEN)
                     .author(CreateEmbedAuthor::new("🎵  Playlist added to queue"))
                     .title(format!("**{}**", playlist.title))
-                    .url(playlist.playlist_url.clone())
                     .description(playlist.description.clone());
+                // Saved playlists have no URL to link to.
+                if !playlist.playlist_url.is_empty() {
+                    embed = embed.url(playlist.playlist_url.clone());
+                }
 
                 embed.footer(CreateEmbedFooter::new(format!(
                     "Playlist length: {}",
@@ -119,10 +168,175 @@ impl<'a> QueueEmbed<'a> {
                     .title("🗑️  Track removed")
                     .description(body)
             }
+            QueueEmbed::TracksRemoved { count, added_by } => {
+                let body = match added_by {
+                    Some(added_by) => format!("Removed **{}** track(s) added by <@{}>.", count, added_by),
+                    None => format!("Removed **{}** track(s) from the queue.", count),
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_GREEN)
+                    .title("🗑️  Tracks removed")
+                    .description(body)
+            }
+            QueueEmbed::TrackMoved { track, position } => CreateEmbed::new()
+                .color(Color::DARK_GREEN)
+                .title("↕️  Track moved")
+                .description(format!(
+                    "**{}** is now at position **{}**.",
+                    track.metadata.title, position
+                )),
+            QueueEmbed::TracksSwapped { first, second } => CreateEmbed::new()
+                .color(Color::DARK_GREEN)
+                .title("🔀  Tracks swapped")
+                .description(format!(
+                    "**{}** and **{}** traded places.",
+                    first.metadata.title, second.metadata.title
+                )),
+            QueueEmbed::Deduplicated(0) => CreateEmbed::new()
+                .color(Color::DARK_BLUE)
+                .title("🧹  No duplicates")
+                .description("Every track in the queue is already unique."),
+            QueueEmbed::Deduplicated(amount) => CreateEmbed::new()
+                .color(Color::DARK_GREEN)
+                .title("🧹  Duplicates removed")
+                .description(format!(
+                    "Removed **{}** duplicate track(s) from the queue.",
+                    amount
+                )),
+            QueueEmbed::FairQueueState(on) => {
+                let (title, body) = if *on {
+                    (
+                        "⚖️  Fair queue on",
+                        "Requesters take turns — one track each per round, in the order they joined the queue.",
+                    )
+                } else {
+                    (
+                        "⚖️  Fair queue off",
+                        "Tracks play in the order they were added.",
+                    )
+                };
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title(title)
+                    .description(body)
+            }
+            QueueEmbed::UserTrackLimit(max) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("✋  That's your share for now")
+                .description(format!(
+                    "You already have **{}** track(s) waiting — the most one person can queue at once. Add more once some of yours have played.",
+                    max
+                )),
+            QueueEmbed::QueueDurationLimit(max) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("⏳  The queue is full")
+                .description(format!(
+                    "This server caps the queue at **{}** of music. Try again once it has played down a bit.",
+                    time_utils::humanize_duration(*max)
+                )),
+            QueueEmbed::PlaylistTrimmed { kept, dropped, limit } => {
+                let reason = match limit {
+                    QueueLimit::PlaylistSize(max) => format!("playlist imports are capped at **{}** tracks", max),
+                    QueueLimit::TracksPerUser(max) => format!("each person can have **{}** track(s) queued", max),
+                    QueueLimit::QueueDuration(max) => format!(
+                        "the queue is capped at **{}** of music",
+                        time_utils::humanize_duration(*max)
+                    ),
+                };
+                CreateEmbed::new()
+                    .color(Color::ORANGE)
+                    .title("✂️  Playlist trimmed")
+                    .description(format!(
+                        "Queued the first **{}** track(s) and left out **{}** — {}.",
+                        kept, dropped, reason
+                    ))
+            }
+            QueueEmbed::Limits(limits) => {
+                let show = |limit: Option<String>| limit.unwrap_or_else(|| "No limit".to_string());
+                CreateEmbed::new()
+                    .color(Color::DARK_BLUE)
+                    .title("📏  Queue limits")
+                    .field(
+                        "Tracks per person",
+                        show(limits.max_tracks_per_user.map(|n| n.to_string())),
+                        true,
+                    )
+                    .field(
+                        "Total queue length",
+                        show(limits.max_queue_duration.map(time_utils::humanize_duration)),
+                        true,
+                    )
+                    .field(
+                        "Playlist import size",
+                        show(limits.max_playlist_size.map(|n| n.to_string())),
+                        true,
+                    )
+            }
+            QueueEmbed::Exported { count, format, uploads } => {
+                let mut description = format!(
+                    "Exported **{}** track(s) as `{}`. Load it again with `queue import`.",
+                    count,
+                    format.extension()
+                );
+                if *uploads > 0 {
+                    description.push_str(&format!(
+                        "\n{} uploaded attachment(s) left out — they're deleted once played.",
+                        uploads
+                    ));
+                }
+                CreateEmbed::new()
+                    .color(Color::DARK_GREEN)
+                    .title("📤  Queue exported")
+                    .description(description)
+            }
+            QueueEmbed::UploadsNotExported => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Nothing to export")
+                .description("Only uploaded attachments are queued, and those are deleted once played."),
+            QueueEmbed::ImportUnreadable(reason) => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("🚫  Can't import")
+                .description(reason.clone()),
+            QueueEmbed::ImportProgress { done, total, added, failed, finished } => {
+                let (color, title) = match (*finished, failed.is_empty()) {
+                    (false, _) => (Color::DARK_BLUE, "📥  Importing queue…"),
+                    (true, true) => (Color::DARK_GREEN, "📥  Queue imported"),
+                    (true, false) => (Color::DARK_GOLD, "📥  Queue imported with errors"),
+                };
+                let mut embed = CreateEmbed::new()
+                    .color(color)
+                    .title(title)
+                    .description(format!(
+                        "Resolved **{}/{}** line(s), added **{}** track(s).",
+                        done, total, added
+                    ));
+
+                if !failed.is_empty() {
+                    // Field values cap at 1024 characters; list what fits.
+                    let mut listed = String::new();
+                    for (i, line) in failed.iter().enumerate() {
+                        let more = format!("\n…and {} more", failed.len() - i);
+                        if listed.len() + line.len() + more.len() + 1 > 1024 {
+                            listed.push_str(&more);
+                            break;
+                        }
+                        if !listed.is_empty() {
+                            listed.push('\n');
+                        }
+                        listed.push_str(line);
+                    }
+                    embed = embed.field(format!("Failed ({})", failed.len()), listed, false);
+                }
+                embed
+            }
             QueueEmbed::InvalidIndex(index) => CreateEmbed::new()
                 .color(Color::DARK_RED)
                 .title("🚫  Invalid index")
                 .description(format!("No track at position **{}** in the queue.", index)),
+            QueueEmbed::FairQueueActive => CreateEmbed::new()
+                .color(Color::DARK_RED)
+                .title("⚖️  Fair queue is on")
+                .description("Requesters take turns while fair queue is on, so a manual order wouldn't last. Turn it off with `fairqueue off` to arrange the queue by hand."),
             QueueEmbed::Cleared(amount) => CreateEmbed::new()
                 .color(Color::DARK_GREEN)
                 .title("🧹  Queue cleared")
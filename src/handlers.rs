@@ -1,4 +1,9 @@
+pub mod crossfade_handler;
 pub mod error_handler;
+pub mod guild_handler;
 pub mod message_handler;
+pub mod prefetch_handler;
 pub mod queue_handler;
+pub mod range_end_handler;
+pub mod sponsorblock_handler;
 pub mod voice_handler;
@@ -0,0 +1,85 @@
+use crate::player::playback::Playback;
+use crate::player::player::FadingOut;
+use async_trait::async_trait;
+use lombok::AllArgsConstructor;
+use songbird::{Event, EventContext, EventHandler};
+use std::time::Duration;
+
+/// Overlaps shorter than this aren't worth it; the End event just starts the
+/// next track as usual.
+const MIN_CROSSFADE: Duration = Duration::from_secs(1);
+
+/// Polls the playing track and, once it's within the guild's crossfade
+/// length of its end, starts the next queued track under it. Autoplay and
+/// idling are left to `QueueHandler`, so an empty queue never crossfades.
+#[derive(AllArgsConstructor)]
+pub struct CrossfadeHandler {
+    playback: Playback,
+}
+
+#[async_trait]
+impl EventHandler for CrossfadeHandler {
+    async fn act(
+        &self,
+        e: &EventContext<'_>,
+    ) -> Option<Event> {
+        let EventContext::Track([(state, handle), ..]) = e else {
+            return None;
+        };
+
+        let mut player = self.playback.player.write().await;
+
+        // Skipped, stopped or already faded out — nothing left to watch.
+        if !player.is_playing || player.track_handle.as_ref().map(|h| h.uuid()) != Some(handle.uuid()) {
+            return Some(Event::Cancel);
+        }
+
+        if player.crossfade.is_zero() || player.is_paused || player.queue.is_empty() {
+            return None;
+        }
+
+        let Some(duration) = player.current_track.as_ref().and_then(|t| t.duration()) else {
+            return Some(Event::Cancel);
+        };
+
+        // The input's end is further out than the track's length when a
+        // filtered render stretches it or a stream still has SponsorBlock
+        // segments to skip, and closer when a render shrinks it.
+        let remaining = player
+            .input_position(duration)
+            .saturating_sub(state.position);
+        if remaining > player.crossfade {
+            return None;
+        }
+        if remaining < MIN_CROSSFADE {
+            return Some(Event::Cancel);
+        }
+
+        tracing::info!(
+            "Crossfading into the next track over {:.1}s",
+            remaining.as_secs_f32()
+        );
+
+        player
+            .record_ended(state.play_time + remaining, false)
+            .await;
+
+        if let Some(finished) = player.current_track.clone() {
+            player.requeue_finished(finished);
+        }
+
+        let outgoing = FadingOut {
+            handle: (*handle).clone(),
+            gain: player.current_gain,
+        };
+        let (_, announcements) = self
+            .playback
+            .crossfade_next(&mut player, outgoing, remaining)
+            .await;
+        drop(player);
+
+        announcements.send_channel(&self.playback).await;
+
+        Some(Event::Cancel)
+    }
+}
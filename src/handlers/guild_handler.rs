@@ -0,0 +1,35 @@
+use crate::bot::{MusicBotData, MusicBotError};
+use crate::service::{player_service, session_service};
+use serenity::all::FullEvent;
+use serenity::prelude::Context as SerenityContext;
+
+/// Handle `GuildCreate` events: make sure the guild has a player (and its
+/// `guilds` row), offer to restore a session saved before the last restart,
+/// and register commands in guilds the bot was just added to. Guilds present
+/// at startup were already registered during setup.
+pub async fn handle(
+    ctx: &SerenityContext,
+    event: &FullEvent,
+    framework: poise::FrameworkContext<'_, MusicBotData, MusicBotError>,
+    data: &MusicBotData,
+) -> Result<(), MusicBotError> {
+    let FullEvent::GuildCreate { guild, is_new } = event else {
+        return Ok(());
+    };
+
+    let player = player_service::player_for_guild(data, guild.id).await?;
+    session_service::offer_restore(ctx, data, guild.id, player).await?;
+
+    if *is_new == Some(true) {
+        tracing::info!(
+            "Joined new guild {} ({}); registering commands",
+            guild.name,
+            guild.id
+        );
+        if let Err(e) = poise::builtins::register_in_guild(ctx, &framework.options().commands, guild.id).await {
+            tracing::error!("Failed to register commands in guild {}: {:?}", guild.id, e);
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::player::player::Player;
+use async_trait::async_trait;
+use lombok::AllArgsConstructor;
+use songbird::{Event, EventContext, EventHandler};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Polled while a track plays so the prefetch follows the head of the queue
+/// through adds, removals, reorders and clears without every queue edit
+/// having to remember to retarget it.
+#[derive(AllArgsConstructor)]
+pub struct PrefetchHandler {
+    player: Arc<RwLock<Player>>,
+}
+
+#[async_trait]
+impl EventHandler for PrefetchHandler {
+    async fn act(
+        &self,
+        e: &EventContext<'_>,
+    ) -> Option<Event> {
+        let EventContext::Track([(_, handle), ..]) = e else {
+            return None;
+        };
+
+        let mut player = self.player.write().await;
+
+        // The next track's own handler takes over once it starts.
+        if player.track_handle.as_ref().map(|h| h.uuid()) != Some(handle.uuid()) {
+            return Some(Event::Cancel);
+        }
+
+        player.refresh_prefetch();
+        None
+    }
+}
@@ -1,139 +1,121 @@
-use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::playback::Playback;
 use crate::player::player::{self, Player};
 // Odebral jsem PlaybackError, v tomto kontextu nebyl správně použit
-use crate::service::embed_service::SendEmbed;
+use crate::service::autoplay_service;
+use crate::service::panel_service;
 use async_trait::async_trait;
 use lombok::AllArgsConstructor;
-use poise::serenity_prelude;
-use serenity::all::{GuildChannel, GuildId};
-use songbird::{Call, Event, EventContext, EventHandler};
+use songbird::{Event, EventContext, EventHandler};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use tokio::sync::RwLockWriteGuard;
 
 #[derive(AllArgsConstructor, Clone)]
 pub struct QueueHandler {
-    serenity_ctx: serenity_prelude::Context,
-    manager: Arc<tokio::sync::Mutex<Call>>,
-    req_client: reqwest::Client,
-    player: Arc<tokio::sync::RwLock<Player>>,
-    guild_channel: GuildChannel,
-    guild_id: GuildId,
+    playback: Playback,
 }
 
 #[async_trait]
 impl EventHandler for QueueHandler {
     async fn act(
         &self,
-        _e: &EventContext<'_>,
+        e: &EventContext<'_>,
     ) -> Option<Event> {
-        let mut player = self.player.write().await;
+        let mut player = self.playback.player.write().await;
 
         if !player.is_playing {
             return None;
         }
 
-        tracing::info!("Track ended; advancing queue");
-
-        if player.queue.is_empty() {
-            tracing::info!("No more tracks to play. Stopping playback.");
-            player::set_idle(&self.serenity_ctx);
-
-            player.track_handle = None;
-            player.current_track = None;
-            player.is_playing = false;
-
-            player.inactivity_cancel.store(false, Ordering::Relaxed);
-
-            let cancel = Arc::clone(&player.inactivity_cancel);
-            let serenity_ctx = self.serenity_ctx.clone();
-            let player_arc = self.player.clone();
-            let guild_id = self.guild_id;
-            let guild_channel = self.guild_channel.clone();
+        // A track replaced by `playnow` fires its End event after the new
+        // one has already started — that's not ours to advance.
+        let current_uuid = player.track_handle.as_ref().map(|h| h.uuid());
+        if let (EventContext::Track(ended), Some(current)) = (e, current_uuid) {
+            if ended.iter().all(|(_, handle)| handle.uuid() != current) {
+                return None;
+            }
+        }
 
-            drop(player);
+        tracing::info!("Track ended; advancing queue");
 
-            tokio::spawn(async move {
-                tokio::time::sleep(tokio::time::Duration::from_secs(5 * 60)).await;
+        if let EventContext::Track([(state, _), ..]) = e {
+            player.record_ended(state.play_time, false).await;
+        }
 
-                if cancel.load(Ordering::Relaxed) {
-                    tracing::debug!("Inactivity timer cancelled - new track was queued");
-                    return;
-                }
+        // Skips clear `current_track` before the End event lands, so only a
+        // track that played to completion is looped here.
+        if let Some(finished) = player.current_track.clone() {
+            player.requeue_finished(finished);
+        }
 
-                if player_arc.read().await.is_playing {
-                    return;
+        if player.queue.is_empty() && player.autoplay {
+            // A skipped track is no longer current, but it's still the
+            // freshest thing in history.
+            let seed = player
+                .current_track
+                .clone()
+                .or_else(|| player.history.back().cloned());
+            if let Some(seed) = seed {
+                let exclude = autoplay_service::exclusions(&player);
+
+                // yt-dlp takes a few seconds; don't block commands meanwhile.
+                drop(player);
+                let related = autoplay_service::related_tracks(&seed, &exclude).await;
+                player = self.playback.player.write().await;
+
+                // Stopped, or another track was started, while we looked.
+                if !player.is_playing || player.track_handle.as_ref().map(|h| h.uuid()) != current_uuid {
+                    return None;
                 }
 
-                tracing::info!("Leaving voice channel after 5 minutes of inactivity");
-
-                let _ = PlayerEmbed::InactivityLeave
-                    .to_embed()
-                    .send_channel(serenity_ctx.http.clone(), &guild_channel, Some(60), None)
-                    .await;
-
-                let _ = player_arc.write().await.stop_playback().await;
-
-                if let Some(manager) = songbird::get(&serenity_ctx).await {
-                    let _ = manager.remove(guild_id).await;
+                if player.queue.is_empty() {
+                    tracing::info!(
+                        "Autoplay queued {} track(s) related to '{}'",
+                        related.len(),
+                        seed.metadata.title
+                    );
+                    player.queue.extend(related);
                 }
-            });
-
-            return None;
-        }
-
-        let next_track = player.queue.remove(0);
-
-        tracing::info!("Playing next track: {}", next_track.metadata.title);
-
-        if !player.silent {
-            if let Err(e) = PlayerEmbed::NowPlaying(&next_track)
-                .to_embed()
-                .send_channel(
-                    self.serenity_ctx.http.clone(),
-                    &self.guild_channel,
-                    Some(30),
-                    None,
-                )
-                .await
-            {
-                tracing::error!("Error sending now playing embed: {e:?}");
             }
         }
 
-        let (input, source_path) = next_track.resolve_input(&self.req_client).await;
-
-        let track_handle = self.manager.lock().await.play(input.into());
-
-        player.current_gain = 1.0;
-        player.current_source_path = source_path.clone();
-        let _ = track_handle.set_volume(player.volume);
-
-        if let Some(path) = source_path {
-            if player.should_normalize() {
-                player::schedule_normalization_apply(
-                    self.player.clone(),
-                    track_handle.clone(),
-                    path,
-                    next_track.id.clone(),
-                );
+        if !player.queue.is_empty() {
+            let (started, announcements) = self.playback.play_next(&mut player).await;
+            announcements.send_channel(&self.playback).await;
+            if started.is_some() {
+                return None;
             }
-        } else {
-            player::spawn_cache_and_apply(
-                next_track.clone(),
-                self.player.clone(),
-                track_handle.clone(),
-            );
         }
 
-        let _ = track_handle.add_event(Event::Track(songbird::TrackEvent::End), self.clone());
-
-        player::set_now_playing(&self.serenity_ctx, &next_track);
-
-        player.push_to_history(next_track.clone());
-        player.track_handle = Some(track_handle);
-        player.current_track = Some(next_track);
-        player.is_playing = true;
+        self.go_idle(player);
 
         None
     }
 }
+
+impl QueueHandler {
+    /// Nothing left to play: clear the now-playing state and leave the voice
+    /// channel if nothing gets queued within five minutes.
+    fn go_idle(
+        &self,
+        mut player: RwLockWriteGuard<'_, Player>,
+    ) {
+        tracing::info!("No more tracks to play. Stopping playback.");
+        player::set_idle(&self.playback.serenity_ctx, self.playback.guild_id);
+
+        player.track_handle = None;
+        player.current_track = None;
+        player.is_playing = false;
+
+        player.inactivity_cancel.store(false, Ordering::Relaxed);
+        panel_service::close(self.playback.guild_id);
+
+        player::spawn_inactivity_leave(
+            self.playback.serenity_ctx.clone(),
+            self.playback.player.clone(),
+            self.playback.guild_id,
+            self.playback.text_channel.clone(),
+            Arc::clone(&player.inactivity_cancel),
+        );
+    }
+}
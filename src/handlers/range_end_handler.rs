@@ -0,0 +1,45 @@
+use crate::player::player::Player;
+use async_trait::async_trait;
+use lombok::AllArgsConstructor;
+use songbird::{Event, EventContext, EventHandler};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Polled while a track with a range end plays, and stops it once it gets
+/// there. Stopping fires the End event, so `QueueHandler` carries on as if
+/// the track had run out by itself.
+#[derive(AllArgsConstructor)]
+pub struct RangeEndHandler {
+    player: Arc<RwLock<Player>>,
+}
+
+#[async_trait]
+impl EventHandler for RangeEndHandler {
+    async fn act(
+        &self,
+        e: &EventContext<'_>,
+    ) -> Option<Event> {
+        let EventContext::Track([(state, handle), ..]) = e else {
+            return None;
+        };
+
+        let player = self.player.read().await;
+
+        // Skipped, stopped or restarted — the new input has its own handler.
+        if player.track_handle.as_ref().map(|h| h.uuid()) != Some(handle.uuid()) {
+            return Some(Event::Cancel);
+        }
+
+        let Some(duration) = player.current_track.as_ref().and_then(|t| t.duration()) else {
+            return Some(Event::Cancel);
+        };
+        if player.track_position(state.position) < duration {
+            return None;
+        }
+        drop(player);
+
+        tracing::info!("Track reached the end of its range");
+        let _ = handle.stop();
+        Some(Event::Cancel)
+    }
+}
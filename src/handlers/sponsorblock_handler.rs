@@ -0,0 +1,65 @@
+use crate::player::player::Player;
+use async_trait::async_trait;
+use songbird::{Event, EventContext, EventHandler};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Polled while a stream with SponsorBlock segments plays, seeking past
+/// each segment as playback enters it. Cut downloads don't need this.
+pub struct SponsorBlockHandler {
+    player: Arc<RwLock<Player>>,
+    /// Where the last seek went, while it may still be under way, so the
+    /// same segment isn't skipped twice before the stream gets there.
+    seeking_to: Mutex<Option<Duration>>,
+}
+
+impl SponsorBlockHandler {
+    pub fn new(player: Arc<RwLock<Player>>) -> Self {
+        Self { player, seeking_to: Mutex::new(None) }
+    }
+}
+
+#[async_trait]
+impl EventHandler for SponsorBlockHandler {
+    async fn act(
+        &self,
+        e: &EventContext<'_>,
+    ) -> Option<Event> {
+        let EventContext::Track([(state, handle), ..]) = e else {
+            return None;
+        };
+
+        let player = self.player.read().await;
+
+        // Skipped, stopped or restarted — the new input has its own handler.
+        if player.track_handle.as_ref().map(|h| h.uuid()) != Some(handle.uuid()) {
+            return Some(Event::Cancel);
+        }
+
+        let segment = player
+            .current_timeline
+            .skips
+            .iter()
+            .find(|s| s.contains(state.position))
+            .copied();
+        drop(player);
+
+        let mut seeking_to = self.seeking_to.lock().unwrap_or_else(|e| e.into_inner());
+        match segment {
+            Some(segment) if *seeking_to != Some(segment.end) => {
+                tracing::info!(
+                    "Skipping SponsorBlock segment {:.1}s-{:.1}s",
+                    segment.start.as_secs_f32(),
+                    segment.end.as_secs_f32()
+                );
+                *seeking_to = Some(segment.end);
+                let _ = handle.seek(segment.end);
+            }
+            Some(_) => {}
+            None => *seeking_to = None,
+        }
+
+        None
+    }
+}
@@ -1,5 +1,6 @@
 use crate::bot::{MusicBotData, MusicBotError};
 use crate::player::player;
+use crate::service::{channel_service, player_service};
 use serenity::all::{ChannelId, FullEvent};
 use serenity::prelude::Context as SerenityContext;
 
@@ -48,26 +49,22 @@ pub async fn handle(
         }
     }
 
-    let bot_id = ctx.cache.current_user().id;
-
-    let bot_channel: Option<ChannelId> = ctx
-        .cache
-        .guild(guild_id)
-        .as_ref()
-        .and_then(|g| g.voice_states.get(&bot_id))
-        .and_then(|vs| vs.channel_id);
+    let bot_channel: Option<ChannelId> = channel_service::bot_voice_channel(ctx, guild_id);
 
     // Bot is no longer in voice (kicked, dragged out, force-disconnected).
     // A paused track still holds queue state, so always wipe both.
     if bot_channel.is_none() {
-        let mut player = data.player.write().await;
+        let Some(player_arc) = player_service::existing_player(data, guild_id).await else {
+            return Ok(());
+        };
+        let mut player = player_arc.write().await;
         let needs_cleanup = player.is_playing || player.is_paused || !player.queue.is_empty();
 
         if needs_cleanup {
             tracing::info!("Bot is no longer in a voice channel. Cleaning up playback state.");
             let _ = player.stop_playback().await;
             drop(player);
-            player::set_idle(ctx);
+            player::set_idle(ctx, guild_id);
 
             if let Some(manager) = songbird::get(ctx).await {
                 let _ = manager.remove(guild_id).await;
@@ -78,23 +75,15 @@ pub async fn handle(
 
     let bot_channel = bot_channel.unwrap();
 
-    let humans = ctx
-        .cache
-        .guild(guild_id)
-        .as_ref()
-        .map(|g| {
-            g.voice_states
-                .values()
-                .filter(|vs| vs.channel_id == Some(bot_channel) && vs.user_id != bot_id)
-                .count()
-        })
-        .unwrap_or(0);
+    let humans = channel_service::listeners(ctx, guild_id, bot_channel).len();
 
     if humans == 0 {
         tracing::info!("Bot is alone in voice channel. Leaving.");
 
-        let _ = data.player.write().await.stop_playback().await;
-        player::set_idle(ctx);
+        if let Some(player_arc) = player_service::existing_player(data, guild_id).await {
+            let _ = player_arc.write().await.stop_playback().await;
+        }
+        player::set_idle(ctx, guild_id);
 
         if let Some(manager) = songbird::get(ctx).await {
             let _ = manager.remove(guild_id).await;
@@ -1,3 +1,4 @@
+pub mod playback;
 #[allow(clippy::module_inception)]
 pub mod player;
 pub mod track;
@@ -0,0 +1,600 @@
+//! The single place a track gets started. Commands (through
+//! `Player::next_track`), the songbird End event (`QueueHandler`) and session
+//! restore all go through `Playback::play_next`, so duration probing,
+//! livestream rejection, the length cap, normalization and caching behave
+//! the same no matter what started the track.
+
+use crate::bot::{Context, MusicBotError};
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::handlers::crossfade_handler::CrossfadeHandler;
+use crate::handlers::prefetch_handler::PrefetchHandler;
+use crate::handlers::queue_handler::QueueHandler;
+use crate::handlers::range_end_handler::RangeEndHandler;
+use crate::handlers::sponsorblock_handler::SponsorBlockHandler;
+use crate::player::player::{self, FadingOut, InputTimeline, Player};
+use crate::player::track::{ResolvedInput, Track, TrackSource, MAX_TRACK_DURATION};
+use crate::service::embed_service::SendEmbed;
+use crate::service::filter_service::{self, AudioFilters, Equalizer};
+use crate::service::{cache_service, normalize_service, panel_service, player_service, sponsorblock_service};
+use crate::sources::radio_player::IcyTitles;
+use poise::serenity_prelude;
+use serenity::all::{CreateEmbed, GuildChannel, GuildId};
+use songbird::tracks::TrackHandle;
+use songbird::{Call, Event, TrackEvent};
+use std::f32::consts::FRAC_PI_2;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, RwLock};
+
+/// How often a crossfade ramp updates the two volumes.
+const RAMP_STEP: Duration = Duration::from_millis(50);
+
+/// How often a playing track checks whether it's time to crossfade.
+const CROSSFADE_POLL: Duration = Duration::from_millis(500);
+
+/// How often a playing track checks that the prefetch still targets the
+/// head of the queue.
+const PREFETCH_POLL: Duration = Duration::from_secs(2);
+
+/// How often a stream checks whether it has reached a SponsorBlock segment.
+const SPONSORBLOCK_POLL: Duration = Duration::from_millis(500);
+
+/// How often a track with a range end checks whether it has reached it.
+const RANGE_END_POLL: Duration = Duration::from_millis(250);
+
+/// How often a radio stream's title reader checks that the stream is still
+/// the one playing.
+const STREAM_TITLE_POLL: Duration = Duration::from_secs(2);
+
+/// How `Playback::start` brings a track in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StartMode {
+    Normal,
+    /// Starts silent; a crossfade ramp brings it up.
+    FadeIn,
+    /// The current track again with a fresh input (new filters). Nothing is
+    /// announced or recorded as a new play.
+    Restart,
+}
+
+/// Everything needed to start tracks in one guild without a command context.
+#[derive(Clone)]
+pub struct Playback {
+    pub serenity_ctx: serenity_prelude::Context,
+    pub manager: Arc<Mutex<Call>>,
+    pub req_client: reqwest::Client,
+    pub player: Arc<RwLock<Player>>,
+    /// Where the End event posts Now Playing and rejection notices.
+    pub text_channel: GuildChannel,
+    pub guild_id: GuildId,
+}
+
+/// What starting a track has to tell the channel. The pipeline only builds
+/// the embeds; callers deliver them — a command replies through its context
+/// so slash interactions get answered, the End event posts to the channel.
+#[derive(Default)]
+pub struct Announcements {
+    /// One notice per queued track that was refused (livestream, too long).
+    pub rejected: Vec<CreateEmbed>,
+    /// Control panel for the started track, unless silent mode is on.
+    pub now_playing: Option<CreateEmbed>,
+}
+
+impl Announcements {
+    pub async fn send_context(
+        self,
+        ctx: Context<'_>,
+        playback: &Playback,
+    ) -> Result<(), MusicBotError> {
+        for embed in self.rejected {
+            embed.send_context(ctx, false, Some(30)).await?;
+        }
+        if let Some(embed) = self.now_playing {
+            panel_service::show(playback, embed, Some(ctx)).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn send_channel(
+        self,
+        playback: &Playback,
+    ) {
+        let http = playback.serenity_ctx.http.clone();
+        for embed in self.rejected {
+            if let Err(e) = embed
+                .send_channel(http.clone(), &playback.text_channel, Some(30), None)
+                .await
+            {
+                tracing::error!("Error sending playback notice: {e:?}");
+            }
+        }
+        if let Some(embed) = self.now_playing {
+            if let Err(e) = panel_service::show(playback, embed, None).await {
+                tracing::error!("Error showing the control panel: {e:?}");
+            }
+        }
+    }
+}
+
+impl Playback {
+    pub fn new(
+        serenity_ctx: serenity_prelude::Context,
+        manager: Arc<Mutex<Call>>,
+        req_client: reqwest::Client,
+        player: Arc<RwLock<Player>>,
+        text_channel: GuildChannel,
+        guild_id: GuildId,
+    ) -> Self {
+        Self {
+            serenity_ctx,
+            manager,
+            req_client,
+            player,
+            text_channel,
+            guild_id,
+        }
+    }
+
+    /// Build the pipeline for the guild and channel a command was run in.
+    pub async fn from_context(ctx: Context<'_>) -> Result<Self, MusicBotError> {
+        let guild_id: GuildId = ctx.guild_id().ok_or_else(|| {
+            tracing::error!("Could not locate voice channel: guild ID is none");
+            MusicBotError::InternalError("Could not locate voice channel. Guild ID is none".to_owned())
+        })?;
+
+        let manager: Arc<Mutex<Call>> = songbird::get(ctx.serenity_context())
+            .await
+            .ok_or_else(|| MusicBotError::InternalError("Songbird manager not registered".to_owned()))?
+            .get_or_insert(guild_id);
+
+        let text_channel: GuildChannel = ctx
+            .guild_channel()
+            .await
+            .ok_or_else(|| MusicBotError::InternalError("Could not resolve the command's text channel".to_owned()))?;
+
+        let player = player_service::player_for_guild(ctx.data(), guild_id).await?;
+
+        Ok(Self::new(
+            ctx.serenity_context().clone(),
+            manager,
+            ctx.data().request_client.clone(),
+            player,
+            text_channel,
+            guild_id,
+        ))
+    }
+
+    /// Pop tracks off the front of `player`'s queue until one passes the
+    /// checks, and start it. Returns the new track's handle, or `None` when
+    /// the queue ran out first; the caller decides what idle means.
+    pub async fn play_next(
+        &self,
+        player: &mut Player,
+    ) -> (Option<TrackHandle>, Announcements) {
+        self.advance(player, StartMode::Normal).await
+    }
+
+    /// Like `play_next`, but the new track starts silent while `outgoing`
+    /// keeps playing, and the two are ramped against each other over `length`.
+    /// When nothing in the queue is playable `outgoing` just plays on.
+    pub async fn crossfade_next(
+        &self,
+        player: &mut Player,
+        outgoing: FadingOut,
+        length: Duration,
+    ) -> (Option<TrackHandle>, Announcements) {
+        let (started, announcements) = self.advance(player, StartMode::FadeIn).await;
+
+        if let Some(incoming) = &started {
+            if let Some(previous) = player.fading_out.replace(outgoing) {
+                let _ = previous.handle.stop();
+            }
+            spawn_ramp(self.player.clone(), incoming.clone(), length);
+        }
+
+        (started, announcements)
+    }
+
+    /// Start the current track over with a freshly built input, so changed
+    /// filters apply right away, and carry on from the same point in it.
+    /// A no-op when nothing is playing.
+    pub async fn restart(
+        &self,
+        player: &mut Player,
+    ) -> Option<TrackHandle> {
+        let (Some(track), Some(previous)) = (player.current_track.clone(), player.track_handle.clone()) else {
+            return None;
+        };
+
+        // Translate to the track's own timeline before switching inputs;
+        // the new one may run at another rate.
+        let position = previous
+            .get_info()
+            .await
+            .map(|state| player.track_position(state.position))
+            .unwrap_or_default();
+        let was_paused = player.is_paused;
+
+        // The old handle's End event is ignored once it's no longer current.
+        let _ = previous.stop();
+        if let Some(fading) = player.fading_out.take() {
+            let _ = fading.handle.stop();
+        }
+
+        let mut announcements = Announcements::default();
+        let handle = self
+            .start(player, track, StartMode::Restart, &mut announcements)
+            .await;
+
+        if !position.is_zero() && player.current_input_seekable {
+            let _ = handle.seek(player.input_position(position));
+        }
+        if was_paused {
+            let _ = handle.pause();
+        }
+
+        Some(handle)
+    }
+
+    /// Bring the current track in line with changed filters or equalizer.
+    /// When the new input can pick up where the old one was — its render is
+    /// already there, or the track plays from a file — it's switched to at
+    /// once. A stream can't seek, so it plays on and `spawn_render` moves it
+    /// over once the render is made. A no-op when nothing is playing.
+    pub async fn apply_effects(
+        &self,
+        player: &mut Player,
+    ) {
+        let (Some(track), Some(_)) = (player.current_track.clone(), player.track_handle.as_ref()) else {
+            return;
+        };
+
+        let active = filter_service::is_active(&player.filters, &player.equalizer);
+        let rendered = active
+            && filter_service::find_render(&track, &player.filters, &player.equalizer)
+                .await
+                .is_some();
+
+        if rendered || player.current_input_seekable {
+            self.restart(player).await;
+        } else if active && !matches!(track.source, TrackSource::Radio) {
+            self.spawn_render(track, player.filters, player.equalizer);
+        }
+    }
+
+    /// Render `track` with the effects it should be playing with, then move
+    /// playback over to the render where it left off. The track plays
+    /// unfiltered meanwhile: a render downloads and runs ffmpeg over the
+    /// whole file, far too long to keep the player locked for.
+    fn spawn_render(
+        &self,
+        track: Track,
+        filters: AudioFilters,
+        equalizer: Equalizer,
+    ) {
+        let playback = self.clone();
+        tokio::spawn(async move {
+            if filter_service::render_track(&track, &filters, &equalizer)
+                .await
+                .is_none()
+            {
+                return;
+            }
+
+            let mut player = playback.player.write().await;
+            // A track or effects change since then renders for itself.
+            let still_wanted = player
+                .current_track
+                .as_ref()
+                .is_some_and(|t| t.id == track.id)
+                && player.filters == filters
+                && player.equalizer == equalizer;
+            if still_wanted {
+                tracing::info!(
+                    "Switching '{}' to its filtered render",
+                    track.metadata.title
+                );
+                playback.restart(&mut player).await;
+            }
+        });
+    }
+
+    async fn advance(
+        &self,
+        player: &mut Player,
+        mode: StartMode,
+    ) -> (Option<TrackHandle>, Announcements) {
+        let mut announcements = Announcements::default();
+
+        while !player.queue.is_empty() {
+            let mut track = player.queue.remove(0);
+            // Before vetting, so the length cap goes by what's left.
+            if player.sponsorblock {
+                sponsorblock_service::annotate(&self.req_client, &mut track).await;
+            }
+            match vet(track).await {
+                Ok(track) => {
+                    let handle = self.start(player, track, mode, &mut announcements).await;
+                    return (Some(handle), announcements);
+                }
+                Err(notice) => announcements.rejected.push(notice),
+            }
+        }
+
+        (None, announcements)
+    }
+
+    async fn start(
+        &self,
+        player: &mut Player,
+        next_track: Track,
+        mode: StartMode,
+        announcements: &mut Announcements,
+    ) -> TrackHandle {
+        tracing::info!("Playing next track: {}", next_track.metadata.title);
+
+        if !player.silent && mode != StartMode::Restart {
+            let embed = PlayerEmbed::ControlPanel {
+                track: &next_track,
+                position: Duration::ZERO,
+                paused: false,
+                looping: player.looping,
+                volume: player.volume,
+                queued: player.queue.len(),
+            };
+            announcements.now_playing = Some(embed.to_embed());
+        }
+
+        let ResolvedInput { input, source_path, rate, filtered } = next_track
+            .resolve_input(&self.req_client, &player.filters, &player.equalizer)
+            .await;
+
+        // A prefetched track was measured ahead of time, so it can start at
+        // its normalized level instead of being corrected a few seconds in.
+        let known_gain = match &source_path {
+            Some(path) if player.should_normalize() => normalize_service::known_measurement(path)
+                .await
+                .map(|m| m.multiplier),
+            _ => None,
+        };
+
+        // A crossfaded track starts silent; its ramp brings it up.
+        let volume = if mode == StartMode::FadeIn { 0.0 } else { player.volume * known_gain.unwrap_or(1.0) };
+        let track_handle: TrackHandle = self
+            .manager
+            .lock()
+            .await
+            .play(songbird::tracks::Track::from(input).volume(volume));
+
+        player.current_gain = known_gain.unwrap_or(1.0);
+        player.current_source_path = source_path.clone();
+        player.current_input_seekable = source_path.is_some();
+        player.current_timeline = InputTimeline {
+            rate,
+            // Files were downloaded with the segments already cut out.
+            skips: match source_path {
+                Some(_) => Vec::new(),
+                None => next_track.metadata.skip_segments.clone(),
+            },
+            offset: sponsorblock_service::trimmed_position(&next_track.metadata.skip_segments, next_track.start()),
+        };
+        if !player.current_timeline.offset.is_zero() {
+            let _ = track_handle.seek(player.input_position(Duration::ZERO));
+        }
+
+        // Cache hit / local file → measure now. Cache miss → fetch in the
+        // background; spawn_cache_and_apply will record the path and apply
+        // the gain mid-track when ffmpeg returns.
+        match source_path {
+            Some(path) => {
+                if player.should_normalize() && known_gain.is_none() {
+                    player::schedule_normalization_apply(
+                        self.player.clone(),
+                        track_handle.clone(),
+                        path,
+                        next_track.id.clone(),
+                    );
+                }
+            }
+            None => {
+                player::spawn_cache_and_apply(
+                    next_track.clone(),
+                    self.player.clone(),
+                    track_handle.clone(),
+                );
+            }
+        }
+        if !filtered && filter_service::is_active(&player.filters, &player.equalizer) && !matches!(next_track.source, TrackSource::Radio) {
+            self.spawn_render(next_track.clone(), player.filters, player.equalizer);
+        }
+        if next_track.metadata.chapters.is_none() && mode != StartMode::Restart {
+            player::spawn_chapter_lookup(next_track.clone(), self.player.clone());
+        }
+
+        let _ = track_handle.add_event(
+            Event::Track(TrackEvent::End),
+            QueueHandler::new(self.clone()),
+        );
+        let _ = track_handle.add_event(
+            Event::Periodic(CROSSFADE_POLL, None),
+            CrossfadeHandler::new(self.clone()),
+        );
+        let _ = track_handle.add_event(
+            Event::Periodic(PREFETCH_POLL, None),
+            PrefetchHandler::new(self.player.clone()),
+        );
+        if !player.current_timeline.skips.is_empty() {
+            let _ = track_handle.add_event(
+                Event::Periodic(SPONSORBLOCK_POLL, None),
+                SponsorBlockHandler::new(self.player.clone()),
+            );
+        }
+        if matches!(next_track.source, TrackSource::Radio) {
+            spawn_stream_titles(
+                self.clone(),
+                track_handle.clone(),
+                next_track.metadata.track_url.clone(),
+            );
+        }
+        if next_track.range.is_some_and(|range| range.end.is_some()) {
+            let _ = track_handle.add_event(
+                Event::Periodic(RANGE_END_POLL, None),
+                RangeEndHandler::new(self.player.clone()),
+            );
+        }
+
+        if mode != StartMode::Restart {
+            player::set_now_playing(&self.serenity_ctx, self.guild_id, &next_track);
+            player.record_started(&next_track).await;
+            player.push_to_history(next_track.clone());
+            player.skip_votes.clear();
+        }
+        player.track_handle = Some(track_handle.clone());
+        player.current_track = Some(next_track);
+        player.text_channel_id = Some(self.text_channel.id);
+        player.is_playing = true;
+        player.refresh_prefetch();
+
+        track_handle
+    }
+}
+
+/// Check `track` against the playback rules, probing its duration with
+/// yt-dlp first when the source didn't report one. Returns the notice to
+/// post when the track is refused.
+async fn vet(mut track: Track) -> Result<Track, CreateEmbed> {
+    // Radio never ends by design; there's no length to probe or cap.
+    if matches!(track.source, TrackSource::Radio) {
+        return Ok(track);
+    }
+
+    if track.metadata.duration.is_none() {
+        if let Some(probe) = cache_service::probe_track(&track).await {
+            if probe.is_live {
+                tracing::info!(
+                    "Skipping '{}' — livestreams are not allowed",
+                    track.metadata.title
+                );
+                return Err(PlayerEmbed::LivestreamNotAllowed { title: track.metadata.title.clone() }.to_embed());
+            }
+            track.metadata.duration = probe.duration;
+            track.metadata.chapters = Some(probe.chapters);
+        }
+    }
+
+    if track.is_known_too_long() {
+        tracing::info!(
+            "Skipping '{}' — duration exceeds {}s cap",
+            track.metadata.title,
+            MAX_TRACK_DURATION.as_secs()
+        );
+        return Err(PlayerEmbed::TrackTooLong {
+            title: track.metadata.title.clone(),
+            cap: MAX_TRACK_DURATION,
+        }
+        .to_embed());
+    }
+
+    Ok(track)
+}
+
+/// Follow the song titles a radio station announces while `handle` plays
+/// it. Each new one becomes the current track's title (the station stays on
+/// as its author) and goes to the bot's activity and the control panel;
+/// between songs the station's name comes back. Stops once the stream ends
+/// or stops being the current track.
+fn spawn_stream_titles(
+    playback: Playback,
+    handle: TrackHandle,
+    url: String,
+) {
+    tokio::spawn(async move {
+        let Some(mut titles) = IcyTitles::connect(&playback.req_client, &url).await else {
+            tracing::debug!("No ICY metadata on {}", url);
+            return;
+        };
+
+        loop {
+            let replaced = async {
+                loop {
+                    tokio::time::sleep(STREAM_TITLE_POLL).await;
+                    let player = playback.player.read().await;
+                    if player.track_handle.as_ref().map(|h| h.uuid()) != Some(handle.uuid()) {
+                        return;
+                    }
+                }
+            };
+            let title = tokio::select! {
+                title = titles.next_title() => title,
+                _ = replaced => return,
+            };
+            let Some(title) = title else {
+                return;
+            };
+
+            let mut player = playback.player.write().await;
+            if player.track_handle.as_ref().map(|h| h.uuid()) != Some(handle.uuid()) {
+                return;
+            }
+            let Some(track) = player.current_track.as_mut() else {
+                return;
+            };
+            let title = if title.is_empty() { track.metadata.channel.clone() } else { title };
+            if track.metadata.title == title {
+                continue;
+            }
+
+            tracing::info!("Now on {}: {}", track.metadata.channel, title);
+            track.metadata.title = title;
+            player::set_now_playing(&playback.serenity_ctx, playback.guild_id, track);
+            let player = player.downgrade();
+            panel_service::refresh(playback.guild_id, &player).await;
+        }
+    });
+}
+
+/// Equal-power crossfade from the player's `fading_out` track to `incoming`
+/// over `length`. Each step re-reads `volume` and both gains, so volume
+/// changes and a normalization measurement landing mid-fade are honoured.
+/// Gives up as soon as `incoming` stops being the current track; whatever
+/// replaced it already cut the outgoing one.
+fn spawn_ramp(
+    player_arc: Arc<RwLock<Player>>,
+    incoming: TrackHandle,
+    length: Duration,
+) {
+    tokio::spawn(async move {
+        let mut elapsed = Duration::ZERO;
+
+        loop {
+            tokio::time::sleep(RAMP_STEP).await;
+
+            let player = player_arc.read().await;
+            if player.track_handle.as_ref().map(|h| h.uuid()) != Some(incoming.uuid()) {
+                return;
+            }
+
+            if !player.is_paused {
+                elapsed += RAMP_STEP;
+            }
+            let progress = (elapsed.as_secs_f32() / length.as_secs_f32()).min(1.0);
+            let angle = progress * FRAC_PI_2;
+
+            let _ = incoming.set_volume(player.volume * player.current_gain * angle.sin());
+            if let Some(fading) = &player.fading_out {
+                let _ = fading
+                    .handle
+                    .set_volume(player.volume * fading.gain * angle.cos());
+            }
+
+            if progress >= 1.0 {
+                drop(player);
+                let mut player = player_arc.write().await;
+                if let Some(fading) = player.fading_out.take() {
+                    let _ = fading.handle.stop();
+                }
+                return;
+            }
+        }
+    });
+}
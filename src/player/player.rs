@@ -1,20 +1,237 @@
 use crate::bot::{Context, Database};
 use crate::embeds::music::player_embed::PlayerEmbed;
-use crate::handlers::queue_handler::QueueHandler;
-use crate::player::track::{PlaybackError, Playlist, Track, MAX_TRACK_DURATION};
+use crate::player::playback::Playback;
+use crate::player::track::{PlaybackError, Playlist, Track, TrackSource};
+use crate::service::attachment_service;
 use crate::service::cache_service;
 use crate::service::embed_service::SendEmbed;
+use crate::service::filter_service::{self, AudioFilters, Equalizer};
+use crate::service::history_service;
 use crate::service::normalize_service;
+use crate::service::panel_service;
+use crate::service::sponsorblock_service::{self, SkipSegment};
 use poise::serenity_prelude;
 use rand::seq::SliceRandom;
-use serenity::all::{ActivityData, GuildId};
+use serenity::all::{ActivityData, ChannelId, GuildChannel, GuildId, UserId};
 use songbird::tracks::TrackHandle;
-use songbird::{Call, Event, TrackEvent};
-use std::collections::VecDeque;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use tokio::sync::{Mutex, MutexGuard};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Vote-skip threshold for guilds that never set one.
+pub const DEFAULT_VOTE_SKIP_PERCENT: u8 = 50;
+
+/// Longest crossfade a guild can configure.
+pub const MAX_CROSSFADE: Duration = Duration::from_secs(12);
+
+/// What happens to a track once it finishes playing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LoopMode {
+    #[default]
+    Off,
+    /// Replay the current track.
+    Track,
+    /// Re-append every finished track to the end of the queue.
+    Queue,
+}
+
+/// Active loop mode plus how many more repeats are left. `remaining: None`
+/// loops until switched off; otherwise the mode drops back to `Off` once the
+/// last repeat has been queued.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Looping {
+    pub mode: LoopMode,
+    pub remaining: Option<u32>,
+}
+
+impl Looping {
+    /// Short status line for embeds, `None` when looping is off.
+    pub fn describe(&self) -> Option<String> {
+        let label = match self.mode {
+            LoopMode::Off => return None,
+            LoopMode::Track => "🔂 Looping track",
+            LoopMode::Queue => "🔁 Looping queue",
+        };
+        Some(match self.remaining {
+            Some(remaining) => format!("{} ({} more)", label, remaining),
+            None => label.to_string(),
+        })
+    }
+}
+
+/// Per-guild caps on what can be queued. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QueueLimits {
+    pub max_tracks_per_user: Option<u32>,
+    pub max_queue_duration: Option<Duration>,
+    pub max_playlist_size: Option<u32>,
+}
+
+impl QueueLimits {
+    /// Check one track against the per-user and total-duration limits,
+    /// given what's already in `queue`. Tracks without a Discord requester
+    /// (autoplay) don't count against anyone's share.
+    pub fn admit(
+        &self,
+        queue: &[Track],
+        track: &Track,
+    ) -> Result<(), PlaybackError> {
+        if let (Some(max), Some(user_id)) = (self.max_tracks_per_user, track.added_by.id) {
+            if queued_by(queue, user_id) >= max as usize {
+                return Err(PlaybackError::UserTrackLimit(max));
+            }
+        }
+
+        if let Some(max) = self.max_queue_duration {
+            if queued_duration(queue) + track.duration().unwrap_or_default() > max {
+                return Err(PlaybackError::QueueDurationLimit(max));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Trim a playlist import to what the limits allow on top of `queue`,
+    /// returning the limit that cut it short (if any). Only errors when not
+    /// even one track fits. Trimming an already trimmed list changes
+    /// nothing, so commands can call this up front to announce the result
+    /// before queueing it.
+    pub fn trim_playlist(
+        &self,
+        queue: &[Track],
+        tracks: &mut Vec<Track>,
+    ) -> Result<Option<QueueLimit>, PlaybackError> {
+        let mut trimmed_by: Option<QueueLimit> = None;
+
+        if let Some(max) = self.max_playlist_size {
+            if tracks.len() > max as usize {
+                tracks.truncate(max as usize);
+                trimmed_by = Some(QueueLimit::PlaylistSize(max));
+            }
+        }
+
+        let requester = tracks.first().and_then(|t| t.added_by.id);
+        if let (Some(max), Some(user_id)) = (self.max_tracks_per_user, requester) {
+            let room = (max as usize).saturating_sub(queued_by(queue, user_id));
+            if room == 0 && !tracks.is_empty() {
+                return Err(PlaybackError::UserTrackLimit(max));
+            }
+            if tracks.len() > room {
+                tracks.truncate(room);
+                trimmed_by = Some(QueueLimit::TracksPerUser(max));
+            }
+        }
+
+        if let Some(max) = self.max_queue_duration {
+            let mut total = queued_duration(queue);
+            let fits = tracks
+                .iter()
+                .take_while(|t| {
+                    total += t.duration().unwrap_or_default();
+                    total <= max
+                })
+                .count();
+            if fits == 0 && !tracks.is_empty() {
+                return Err(PlaybackError::QueueDurationLimit(max));
+            }
+            if fits < tracks.len() {
+                tracks.truncate(fits);
+                trimmed_by = Some(QueueLimit::QueueDuration(max));
+            }
+        }
+
+        Ok(trimmed_by)
+    }
+}
+
+/// How many tracks in `queue` `user_id` added.
+fn queued_by(
+    queue: &[Track],
+    user_id: UserId,
+) -> usize {
+    queue.iter().filter(|t| t.added_by.is(user_id)).count()
+}
+
+/// Total known length of `queue`. Tracks whose duration hasn't been probed
+/// yet count as zero.
+fn queued_duration(queue: &[Track]) -> Duration {
+    queue.iter().filter_map(Track::duration).sum()
+}
+
+/// The limit that cut an imported playlist short.
+#[derive(Debug, Clone, Copy)]
+pub enum QueueLimit {
+    PlaylistSize(u32),
+    TracksPerUser(u32),
+    QueueDuration(Duration),
+}
+
+/// The previous track while it fades out under the one that replaced it.
+pub struct FadingOut {
+    pub handle: TrackHandle,
+    /// Normalization gain the track was playing at, so its ramp keeps
+    /// `volume × gain` as the starting level.
+    pub gain: f32,
+}
+
+/// How positions reported by the current input map onto the track's own
+/// timeline, which leaves out skipped SponsorBlock segments and starts at
+/// the start of the track's range.
+#[derive(Debug, Clone)]
+pub struct InputTimeline {
+    /// Playback rate of the input: the filter speed when it was rendered
+    /// with filters, else `1.0`. Positions reported by its handle are in
+    /// rendered time.
+    pub rate: f32,
+    /// SponsorBlock segments the input still contains: a stream plays the
+    /// full video and seeks over them, while a cut download or a render of
+    /// one already left them out.
+    pub skips: Vec<SkipSegment>,
+    /// Where the track starts, once the rate and skips are accounted for.
+    pub offset: Duration,
+}
+
+impl Default for InputTimeline {
+    fn default() -> Self {
+        Self {
+            rate: 1.0,
+            skips: Vec::new(),
+            offset: Duration::ZERO,
+        }
+    }
+}
+
+impl InputTimeline {
+    /// Where the track is when its input is at `input_position`.
+    pub fn track_position(
+        &self,
+        input_position: Duration,
+    ) -> Duration {
+        sponsorblock_service::trimmed_position(&self.skips, input_position.mul_f32(self.rate)).saturating_sub(self.offset)
+    }
+
+    /// Inverse of `track_position`: the input position to seek to for
+    /// `position` in the track.
+    pub fn input_position(
+        &self,
+        position: Duration,
+    ) -> Duration {
+        sponsorblock_service::full_position(&self.skips, position + self.offset).div_f32(self.rate)
+    }
+}
+
+/// Background download of the track at the head of the queue.
+struct Prefetch {
+    track_id: String,
+    /// Filters and equalizer the prefetch renders with; a change retargets
+    /// it.
+    filters: AudioFilters,
+    equalizer: Equalizer,
+    task: JoinHandle<()>,
+}
 
 pub struct Player {
     pub is_playing: bool,
@@ -33,8 +250,15 @@ pub struct Player {
     /// file). Used by `!normalize` to re-measure and apply gain mid-track.
     /// `None` for streamed inputs that have no analyzable file yet.
     pub current_source_path: Option<PathBuf>,
+    /// Whether the current track was started from a file (cache hit or
+    /// local track) and so seeks instantly. Streamed inputs stay `false`
+    /// even after the background cache lands, since the stream keeps playing.
+    pub current_input_seekable: bool,
+    /// How the current input's positions map onto the track's timeline.
+    pub current_timeline: InputTimeline,
     pub inactivity_cancel: Arc<AtomicBool>,
-    /// Session-only "shh" mode — when on, the NowPlaying embed is suppressed.
+    /// Session-only "shh" mode — when on, no control panel is posted for new
+    /// tracks.
     /// Resets to `false` on bot restart.
     pub silent: bool,
     /// Session-only loudness normalization toggle. Off by default and reset
@@ -42,6 +266,51 @@ pub struct Player {
     /// session. When on, it applies to every source (YouTube, Spotify, and
     /// local files) for every track that has a measurable file path.
     pub normalize: bool,
+    /// Session-only autoplay toggle. When on and the queue runs dry, tracks
+    /// related to the last one are queued instead of going idle.
+    pub autoplay: bool,
+    /// Session-only audio effects, set with `!filter`. Applied to every
+    /// track started while set, by re-rendering its file.
+    pub filters: AudioFilters,
+    /// Band gains applied to every track started while set, on top of the
+    /// filters. Persisted per guild.
+    pub equalizer: Equalizer,
+    /// Text channel the last track was started from. Saved with the session
+    /// so a restart knows where to offer restoring it.
+    pub text_channel_id: Option<ChannelId>,
+    /// Set once the startup "restore last session?" offer has been handled,
+    /// so a gateway reconnect (which replays `GuildCreate`) doesn't repeat it.
+    pub restore_offered: bool,
+    /// Percentage of listeners that must vote to skip the current track.
+    /// Persisted per guild.
+    pub vote_skip_percent: u8,
+    /// Users who voted to skip the current track. Cleared whenever a new
+    /// track starts.
+    pub skip_votes: HashSet<UserId>,
+    /// How long the end of a track overlaps the start of the next one.
+    /// `Duration::ZERO` starts tracks back to back. Persisted per guild.
+    pub crossfade: Duration,
+    /// Caps on queue size per requester, total queue length and playlist
+    /// imports. Persisted per guild.
+    pub limits: QueueLimits,
+    /// Whether the queue is kept interleaved round-robin across requesters.
+    /// Persisted per guild.
+    pub fair_queue: bool,
+    /// Whether tracks are looked up on SponsorBlock and have their sponsor
+    /// and non-music segments left out. Persisted per guild.
+    pub sponsorblock: bool,
+    /// Outgoing track of a crossfade in progress.
+    pub fading_out: Option<FadingOut>,
+    /// Session-only loop mode, set with `!loop`.
+    pub looping: Looping,
+    /// Id of the track that closes one pass through a looped queue. Counted
+    /// repeats in queue mode are decremented whenever it gets re-appended.
+    loop_anchor: Option<String>,
+    /// `play_history` row of the current track, closed when it ends.
+    history_entry: Option<i64>,
+    /// Cache download (and loudness measurement) of the queue head, so the
+    /// next track starts from a local file at its normalized level.
+    prefetch: Option<Prefetch>,
     guild_id: GuildId,
     database: Arc<Database>,
 }
@@ -53,17 +322,46 @@ impl Player {
     ) -> Self {
         let guild_id_map: i64 = guild_id.get() as i64;
 
-        let volume = sqlx::query!("SELECT * FROM guilds WHERE guild_id = $1", guild_id_map)
+        let settings = sqlx::query!("SELECT * FROM guilds WHERE guild_id = $1", guild_id_map)
             .fetch_one(&*database)
             .await
             .map_err(|e| {
-                tracing::error!("Failed to fetch volume from database: {:?}", e);
+                tracing::error!("Failed to fetch guild settings from database: {:?}", e);
                 crate::bot::MusicBotError::InternalError(e.to_string())
             });
 
-        let volume: f32 = match volume {
-            Ok(volume) => volume.volume.unwrap_or(0.5) as f32,
-            Err(_) => 0.5,
+        let (volume, vote_skip_percent, crossfade, fair_queue, limits, equalizer, sponsorblock): (f32, u8, Duration, bool, QueueLimits, Equalizer, bool) = match settings {
+            Ok(settings) => (
+                settings.volume.unwrap_or(0.5) as f32,
+                settings
+                    .vote_skip_percent
+                    .map_or(DEFAULT_VOTE_SKIP_PERCENT, |p| p.clamp(1, 100) as u8),
+                Duration::from_secs(
+                    settings
+                        .crossfade_secs
+                        .unwrap_or(0)
+                        .clamp(0, MAX_CROSSFADE.as_secs() as i64) as u64,
+                ),
+                settings.fair_queue.unwrap_or(0) != 0,
+                QueueLimits {
+                    max_tracks_per_user: settings.max_tracks_per_user.map(|n| n.max(1) as u32),
+                    max_queue_duration: settings
+                        .max_queue_duration_secs
+                        .map(|s| Duration::from_secs(s.max(1) as u64)),
+                    max_playlist_size: settings.max_playlist_size.map(|n| n.max(1) as u32),
+                },
+                Equalizer::from_db(settings.eq_gains.as_deref()),
+                settings.sponsorblock.unwrap_or(0) != 0,
+            ),
+            Err(_) => (
+                0.5,
+                DEFAULT_VOTE_SKIP_PERCENT,
+                Duration::ZERO,
+                false,
+                QueueLimits::default(),
+                Equalizer::default(),
+                false,
+            ),
         };
 
         Player {
@@ -76,14 +374,36 @@ impl Player {
             volume,
             current_gain: 1.0,
             current_source_path: None,
+            current_input_seekable: false,
+            current_timeline: InputTimeline::default(),
             inactivity_cancel: Arc::new(AtomicBool::new(false)),
             silent: false,
             normalize: false,
+            autoplay: false,
+            filters: AudioFilters::default(),
+            equalizer,
+            text_channel_id: None,
+            restore_offered: false,
+            vote_skip_percent,
+            skip_votes: HashSet::new(),
+            crossfade,
+            fair_queue,
+            sponsorblock,
+            limits,
+            fading_out: None,
+            looping: Looping::default(),
+            loop_anchor: None,
+            history_entry: None,
+            prefetch: None,
             guild_id,
             database,
         }
     }
 
+    pub fn database(&self) -> Arc<Database> {
+        Arc::clone(&self.database)
+    }
+
     /// Whether loudness normalization should apply this session.
     pub fn should_normalize(&self) -> bool {
         self.normalize
@@ -95,16 +415,125 @@ impl Player {
     ) {
         self.history.push_back(track);
         if self.history.len() > 10 {
-            self.history.pop_front();
+            if let Some(dropped) = self.history.pop_front() {
+                self.release(&[dropped]);
+            }
+        }
+    }
+
+    /// Delete the files of one-off attachments among `dropped` that the
+    /// player no longer refers to: not playing, queued or in the history.
+    /// Every path that drops tracks calls this.
+    fn release(
+        &self,
+        dropped: &[Track],
+    ) {
+        for track in dropped {
+            let in_use = self
+                .current_track
+                .iter()
+                .chain(&self.queue)
+                .chain(&self.history)
+                .any(|t| t.id == track.id);
+            if !in_use {
+                attachment_service::discard(track);
+            }
+        }
+    }
+
+    /// Open a `play_history` row for `track`, which just started. History is
+    /// best-effort: a database error is logged, never surfaced to playback.
+    pub async fn record_started(
+        &mut self,
+        track: &Track,
+    ) {
+        self.history_entry = match history_service::record_start(&self.database, self.guild_id, track).await {
+            Ok(id) => Some(id),
+            Err(e) => {
+                tracing::warn!("Failed to record play history: {:?}", e);
+                None
+            }
+        };
+    }
+
+    /// Close the current track's `play_history` row. Whichever of the End
+    /// event, a skip or a crossfade gets there first records the outcome;
+    /// later calls are no-ops. `listened` is the time actually played, not
+    /// the input position, which seeks and range starts move around.
+    pub async fn record_ended(
+        &mut self,
+        listened: Duration,
+        skipped: bool,
+    ) {
+        let Some(entry_id) = self.history_entry.take() else {
+            return;
+        };
+        if let Err(e) = history_service::record_end(&self.database, entry_id, listened, skipped).await {
+            tracing::warn!("Failed to record play history: {:?}", e);
+        }
+    }
+
+    /// Switch loop mode. `count` limits the number of repeats (of the track,
+    /// or of whole passes through the queue); `None` loops indefinitely.
+    pub fn set_looping(
+        &mut self,
+        mode: LoopMode,
+        count: Option<u32>,
+    ) {
+        tracing::info!("Setting loop mode to {:?} (count={:?})", mode, count);
+
+        self.loop_anchor = match mode {
+            LoopMode::Queue => self
+                .queue
+                .last()
+                .or(self.current_track.as_ref())
+                .map(|t| t.id.clone()),
+            _ => None,
+        };
+        self.looping = Looping { mode, remaining: count };
+    }
+
+    /// Feed a track that just finished back into the queue according to the
+    /// loop mode: to the front in track mode, to the back in queue mode.
+    /// A no-op when looping is off.
+    pub fn requeue_finished(
+        &mut self,
+        track: Track,
+    ) {
+        match self.looping.mode {
+            LoopMode::Off => return,
+            LoopMode::Track => self.queue.insert(0, track),
+            LoopMode::Queue => {
+                // Looping was switched on before anything was queued; the
+                // first pass ends with whatever is last right now.
+                let anchor = self
+                    .loop_anchor
+                    .get_or_insert_with(|| self.queue.last().unwrap_or(&track).id.clone());
+                let ends_pass = *anchor == track.id;
+                self.queue.push(track);
+                if !ends_pass {
+                    return;
+                }
+            }
+        }
+
+        if let Some(remaining) = self.looping.remaining.as_mut() {
+            *remaining = remaining.saturating_sub(1);
+            if *remaining == 0 {
+                tracing::info!("Loop count exhausted; looping off");
+                self.set_looping(LoopMode::Off, None);
+            }
         }
     }
 
     pub async fn add_playlist_to_queue(
         &mut self,
         ctx: Context<'_>,
-        playlist: Playlist,
+        mut playlist: Playlist,
         top: bool,
     ) -> Result<(), PlaybackError> {
+        self.admit_playlist(&mut playlist.tracks)?;
+
         tracing::info!(
             "Adding playlist to queue (top={}), tracks: {}",
             top,
@@ -116,12 +545,57 @@ impl Player {
             self.queue.splice(0..0, playlist.tracks);
         } else {
             self.queue.extend(playlist.tracks);
+            self.interleave_queue();
         }
         tracing::debug!("Queue length: {}", self.queue.len());
 
         self.kick_off_playback(ctx, top).await
     }
 
+    /// Check one track against the queue limits.
+    pub fn admit_track(
+        &self,
+        track: &Track,
+    ) -> Result<(), PlaybackError> {
+        self.limits.admit(&self.queue, track)
+    }
+
+    /// Trim a playlist import to what the queue limits allow; see
+    /// `QueueLimits::trim_playlist`.
+    pub fn admit_playlist(
+        &self,
+        tracks: &mut Vec<Track>,
+    ) -> Result<Option<QueueLimit>, PlaybackError> {
+        self.limits.trim_playlist(&self.queue, tracks)
+    }
+
+    /// Set and persist the guild's queue limits.
+    pub async fn set_queue_limits(
+        &mut self,
+        limits: QueueLimits,
+    ) -> Result<(), PlaybackError> {
+        tracing::info!("Setting queue limits to {:?}", limits);
+
+        let guild_id_map: i64 = self.guild_id.get() as i64;
+        let per_user: Option<i64> = limits.max_tracks_per_user.map(i64::from);
+        let duration_secs: Option<i64> = limits.max_queue_duration.map(|d| d.as_secs() as i64);
+        let playlist_size: Option<i64> = limits.max_playlist_size.map(i64::from);
+
+        sqlx::query!(
+            "UPDATE guilds SET max_tracks_per_user = $1, max_queue_duration_secs = $2, max_playlist_size = $3 WHERE guild_id = $4",
+            per_user,
+            duration_secs,
+            playlist_size,
+            guild_id_map
+        )
+        .execute(&*self.database)
+        .await
+        .map_err(|e| PlaybackError::InternalError(e.to_string()))?;
+
+        self.limits = limits;
+        Ok(())
+    }
+
     /// Stop the current track (if any) and immediately start `track`, inserting
     /// it at the front of the queue so it plays next.
     pub async fn force_play_track(
@@ -129,6 +603,7 @@ impl Player {
         ctx: Context<'_>,
         track: Track,
     ) -> Result<(), PlaybackError> {
+        self.admit_track(&track)?;
         self.inactivity_cancel.store(true, Ordering::SeqCst);
         self.queue.insert(0, track);
         self.next_track(ctx).await?;
@@ -140,8 +615,9 @@ impl Player {
     pub async fn force_play_playlist(
         &mut self,
         ctx: Context<'_>,
-        playlist: Playlist,
+        mut playlist: Playlist,
     ) -> Result<(), PlaybackError> {
+        self.admit_playlist(&mut playlist.tracks)?;
         self.inactivity_cancel.store(true, Ordering::SeqCst);
         self.queue.splice(0..0, playlist.tracks);
         self.next_track(ctx).await?;
@@ -154,19 +630,22 @@ impl Player {
         track: Track,
         top: bool,
     ) -> Result<(), PlaybackError> {
-        self.push_track(track, top);
+        self.push_track(track, top)?;
         self.kick_off_playback(ctx, top).await
     }
 
-    /// Infallible queue push — separated from `kick_off_playback` so callers
-    /// that need to render a "queued" confirmation between the push and the
-    /// playback start (so the message lands before NowPlaying) can interleave
-    /// them while holding the player lock.
+    /// Queue push, checked against the queue limits — separated from
+    /// `kick_off_playback` so callers that need to render a "queued"
+    /// confirmation between the push and the playback start (so the message
+    /// lands before NowPlaying) can interleave them while holding the player
+    /// lock.
     pub fn push_track(
         &mut self,
         track: Track,
         top: bool,
-    ) {
+    ) -> Result<(), PlaybackError> {
+        self.admit_track(&track)?;
+
         tracing::info!(
             "Adding track to queue (top={}): {}",
             top,
@@ -178,8 +657,10 @@ impl Player {
             self.queue.insert(0, track);
         } else {
             self.queue.push(track);
+            self.interleave_queue();
         }
         tracing::debug!("Queue length: {}", self.queue.len());
+        Ok(())
     }
 
     /// Decide what to do after appending to the queue:
@@ -216,11 +697,28 @@ impl Player {
             return Err(PlaybackError::PlaybackNotActive);
         }
 
+        // Skipping doesn't take a track out of a looped queue — it goes to the
+        // back like any finished track would.
+        if self.looping.mode == LoopMode::Queue {
+            if let Some(current) = self.current_track.clone() {
+                self.requeue_finished(current);
+            }
+        }
+
         if amount > self.queue.len() {
             tracing::debug!("Amount to skip is greater than queue length. Skipping all tracks");
             amount = amount.min(self.queue.len());
         }
 
+        // With autoplay on, skipping the last track ends it like any other
+        // track would, and the queue handler refills the queue.
+        if self.queue.is_empty() && self.is_playing && self.autoplay {
+            self.stop_track().await?;
+            self.is_playing = true;
+
+            return Ok(1);
+        }
+
         if self.queue.is_empty() && self.is_playing {
             tracing::info!("No tracks in queue. Stopping playback");
             self.stop_playback().await?;
@@ -230,7 +728,14 @@ impl Player {
 
         if !self.queue.is_empty() {
             if amount > 1 {
-                self.queue.drain(0..amount - 1);
+                let skipped: Vec<Track> = self.queue.drain(0..amount - 1).collect();
+                if self.looping.mode == LoopMode::Queue {
+                    for track in skipped {
+                        self.requeue_finished(track);
+                    }
+                } else {
+                    self.release(&skipped);
+                }
             }
 
             self.stop_track().await?;
@@ -240,6 +745,41 @@ impl Player {
         Ok(amount)
     }
 
+    /// Go back to the track played before the current one. The current track
+    /// goes back to the front of the queue, right after it, and the queue
+    /// handler starts the earlier one when the current one ends.
+    pub async fn back(&mut self) -> Result<(), PlaybackError> {
+        tracing::info!("Going back to the previous track");
+
+        if !self.is_playing {
+            return Err(PlaybackError::PlaybackNotActive);
+        }
+
+        // The current track is the newest history entry.
+        let current = self.current_track.clone();
+        if let (Some(current), Some(last)) = (&current, self.history.back()) {
+            if last.id == current.id {
+                self.history.pop_back();
+            }
+        }
+        let Some(previous) = self.history.pop_back() else {
+            if let Some(current) = current {
+                self.push_to_history(current);
+            }
+            return Err(PlaybackError::NoPreviousTrack);
+        };
+
+        if let Some(current) = current {
+            self.queue.insert(0, current);
+        }
+        self.queue.insert(0, previous);
+
+        self.stop_track().await?;
+        self.is_playing = true;
+
+        Ok(())
+    }
+
     pub async fn start_playback(
         &mut self,
         ctx: Context<'_>,
@@ -258,153 +798,39 @@ impl Player {
         Ok(())
     }
 
+    /// Stop the current track (if any) and start the next playable one from
+    /// the queue, announcing it in reply to `ctx`. Stops playback when the
+    /// queue has nothing playable left.
     pub async fn next_track(
         &mut self,
         ctx: Context<'_>,
     ) -> Result<Option<&Track>, PlaybackError> {
         tracing::info!("Requesting next track to play");
 
-        let guild_id: GuildId = ctx.guild_id().ok_or_else(|| {
-            tracing::error!("Could not locate voice channel: guild ID is none");
-            PlaybackError::InternalError("Could not locate voice channel. Guild ID is none".to_owned())
-        })?;
-
-        let manager: Arc<Mutex<Call>> = songbird::get(ctx.serenity_context())
-            .await
-            .ok_or_else(|| {
-                tracing::error!("Could not locate voice channel: guild ID is none");
-                PlaybackError::InternalError("Could not locate voice channel. Guild ID is none".to_owned())
-            })?
-            .get_or_insert(guild_id);
+        let playback = Playback::from_context(ctx).await?;
 
         if self.is_playing {
             self.stop_track().await?;
         }
 
-        // Pop tracks until we land on one within the length cap. Tracks with a
-        // known duration from their source (Spotify, lazy YouTube playlist)
-        // are already filtered at queue-add; this loop catches the YouTube
-        // Data API path where duration isn't known until yt-dlp probes it.
-        let next = loop {
-            let candidate = if self.queue.is_empty() { None } else { Some(self.queue.remove(0)) };
-            let Some(mut track) = candidate else {
-                break None;
-            };
-
-            if track.duration().is_none() {
-                if let Some(probe) = cache_service::probe_track(&track).await {
-                    if probe.is_live {
-                        tracing::info!(
-                            "Skipping '{}' — livestreams are not allowed",
-                            track.metadata.title
-                        );
-                        PlayerEmbed::LivestreamNotAllowed { title: track.metadata.title.clone() }
-                            .to_embed()
-                            .send_context(ctx, false, Some(30))
-                            .await?;
-                        continue;
-                    }
-                    track.metadata.duration = probe.duration;
-                }
-            }
-
-            if track.is_known_too_long() {
-                tracing::info!(
-                    "Skipping '{}' — duration exceeds {}s cap",
-                    track.metadata.title,
-                    MAX_TRACK_DURATION.as_secs()
-                );
-                PlayerEmbed::TrackTooLong {
-                    title: track.metadata.title.clone(),
-                    cap: MAX_TRACK_DURATION,
-                }
-                .to_embed()
-                .send_context(ctx, false, Some(30))
-                .await?;
-                continue;
-            }
-
-            break Some(track);
-        };
-
-        match next {
-            Some(next_track) => {
-                tracing::info!("Found: {}", next_track.metadata.title);
+        let (started, announcements) = playback.play_next(self).await;
 
-                if !self.silent {
-                    PlayerEmbed::NowPlaying(&next_track)
-                        .to_embed()
-                        .send_context(ctx, false, Some(30))
-                        .await?;
-                }
-
-                let (input, source_path) = next_track.resolve_input(&ctx.data().request_client).await;
-
-                let mut guard: MutexGuard<Call> = manager.lock().await;
-                let track_handle: TrackHandle = guard.play(input.into());
-
-                self.current_gain = 1.0;
-                self.current_source_path = source_path.clone();
-                let _ = track_handle.set_volume(self.volume);
-
-                // Cache hit / local file → measure now. Cache miss → fetch
-                // in the background; spawn_cache_and_apply will record the
-                // path and apply the gain mid-track when ffmpeg returns.
-                match source_path {
-                    Some(path) => {
-                        if self.should_normalize() {
-                            schedule_normalization_apply(
-                                ctx.data().player.clone(),
-                                track_handle.clone(),
-                                path,
-                                next_track.id.clone(),
-                            );
-                        }
-                    }
-                    None => {
-                        spawn_cache_and_apply(
-                            next_track.clone(),
-                            ctx.data().player.clone(),
-                            track_handle.clone(),
-                        );
-                    }
-                }
+        if started.is_none() {
+            tracing::info!("No more tracks to play. Stopping playback");
+            set_idle(ctx.serenity_context(), self.guild_id);
+            self.stop_playback().await?;
+        }
 
-                let _ = track_handle.add_event(
-                    Event::Track(TrackEvent::End),
-                    QueueHandler::new(
-                        ctx.serenity_context().clone(),
-                        manager.clone(),
-                        ctx.data().request_client.clone(),
-                        ctx.data().player.clone(),
-                        ctx.guild_channel().await.unwrap(),
-                        guild_id,
-                    ),
-                );
-
-                set_now_playing(ctx.serenity_context(), &next_track);
-
-                self.push_to_history(next_track.clone());
-                self.current_track = Some(next_track);
-                self.track_handle = Some(track_handle);
-                self.is_playing = true;
-
-                Ok(self.current_track.as_ref())
-            }
+        announcements.send_context(ctx, &playback).await?;
 
-            None => {
-                tracing::info!("No more tracks to play. Stopping playback");
-                set_idle(ctx.serenity_context());
-                self.stop_playback().await?;
-                Ok(None)
-            }
-        }
+        Ok(self.current_track.as_ref())
     }
 
     pub async fn clear_queue(&mut self) -> usize {
         let cleared = self.queue.len();
         tracing::info!("Clearing queue ({} tracks)", cleared);
-        self.queue.clear();
+        let dropped = std::mem::take(&mut self.queue);
+        self.release(&dropped);
         cleared
     }
 
@@ -418,15 +844,228 @@ impl Player {
             return Err(PlaybackError::InvalidQueueIndex(index));
         }
 
-        Ok(self.queue.remove(index - 1))
+        let track = self.queue.remove(index - 1);
+        self.release(std::slice::from_ref(&track));
+        Ok(track)
+    }
+
+    /// In fair-queue mode, reorder the queue so requesters take turns: each
+    /// one's tracks keep their own order, and rounds go by who appears first
+    /// in the queue. Whoever queued the playing track is moved to the back of
+    /// the rotation so they don't get two turns in a row. Re-running it on an
+    /// interleaved queue changes nothing, so it's safe after every append.
+    pub fn interleave_queue(&mut self) {
+        if !self.fair_queue || self.queue.len() < 2 {
+            return;
+        }
+
+        let playing = self.current_track.as_ref().map(|t| t.added_by.id);
+        self.queue = interleave(std::mem::take(&mut self.queue), playing);
+    }
+
+    /// Turn fair-queue mode on or off and persist it. Turning it on
+    /// interleaves the queue straight away.
+    pub async fn set_fair_queue(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), PlaybackError> {
+        tracing::info!("Setting fair queue to {}", enabled);
+
+        let guild_id_map: i64 = self.guild_id.get() as i64;
+
+        sqlx::query!(
+            "UPDATE guilds SET fair_queue = $1 WHERE guild_id = $2",
+            enabled,
+            guild_id_map
+        )
+        .execute(&*self.database)
+        .await
+        .map_err(|e| PlaybackError::InternalError(e.to_string()))?;
+
+        self.fair_queue = enabled;
+        self.interleave_queue();
+        Ok(())
+    }
+
+    /// Turn SponsorBlock mode on or off and persist it. Tracks already
+    /// queued are looked up when they start; turning it off drops their
+    /// segments so they play (and cache) in full again.
+    pub async fn set_sponsorblock(
+        &mut self,
+        enabled: bool,
+    ) -> Result<(), PlaybackError> {
+        tracing::info!("Setting SponsorBlock to {}", enabled);
+
+        let guild_id_map: i64 = self.guild_id.get() as i64;
+
+        sqlx::query!(
+            "UPDATE guilds SET sponsorblock = $1 WHERE guild_id = $2",
+            enabled,
+            guild_id_map
+        )
+        .execute(&*self.database)
+        .await
+        .map_err(|e| PlaybackError::InternalError(e.to_string()))?;
+
+        self.sponsorblock = enabled;
+        if !enabled {
+            for track in &mut self.queue {
+                track.metadata.skip_segments.clear();
+            }
+        }
+        Ok(())
+    }
+
+    /// Where the current track is, given its input's position.
+    pub fn track_position(
+        &self,
+        input_position: Duration,
+    ) -> Duration {
+        self.current_timeline.track_position(input_position)
+    }
+
+    /// The current input's position for `position` in the track.
+    pub fn input_position(
+        &self,
+        position: Duration,
+    ) -> Duration {
+        self.current_timeline.input_position(position)
+    }
+
+    /// Set the equalizer and persist it. Tracks started from now on are
+    /// rendered with it; the caller restarts the current one if it should
+    /// change too.
+    pub async fn set_equalizer(
+        &mut self,
+        equalizer: Equalizer,
+    ) -> Result<(), PlaybackError> {
+        tracing::info!("Setting equalizer to {}", equalizer);
+
+        let guild_id_map: i64 = self.guild_id.get() as i64;
+        let gains = equalizer.to_db();
+
+        sqlx::query!(
+            "UPDATE guilds SET eq_gains = $1 WHERE guild_id = $2",
+            gains,
+            guild_id_map
+        )
+        .execute(&*self.database)
+        .await
+        .map_err(|e| PlaybackError::InternalError(e.to_string()))?;
+
+        self.equalizer = equalizer;
+        Ok(())
+    }
+
+    /// Remove the tracks at 1-based positions `start..=end`.
+    pub async fn remove_range_from_queue(
+        &mut self,
+        start: usize,
+        end: usize,
+    ) -> Result<Vec<Track>, PlaybackError> {
+        tracing::info!("Removing tracks at queue positions {}-{}", start, end);
+
+        self.check_queue_index(start)?;
+        self.check_queue_index(end)?;
+
+        let removed: Vec<Track> = self
+            .queue
+            .drain(start.min(end) - 1..start.max(end))
+            .collect();
+        self.release(&removed);
+        Ok(removed)
+    }
+
+    /// Remove every queued track added by `user_id`.
+    pub async fn remove_added_by(
+        &mut self,
+        user_id: UserId,
+    ) -> Vec<Track> {
+        tracing::info!("Removing queued tracks added by {}", user_id);
+
+        let (removed, kept) = std::mem::take(&mut self.queue)
+            .into_iter()
+            .partition(|t| t.added_by.is(user_id));
+        self.queue = kept;
+        self.release(&removed);
+        removed
+    }
+
+    /// Move the track at 1-based position `from` so it ends up at `to`.
+    /// Refused in fair-queue mode, where the next append would interleave
+    /// the move away again.
+    pub async fn move_in_queue(
+        &mut self,
+        from: usize,
+        to: usize,
+    ) -> Result<&Track, PlaybackError> {
+        tracing::info!("Moving queued track from {} to {}", from, to);
+
+        if self.fair_queue {
+            return Err(PlaybackError::FairQueueActive);
+        }
+        self.check_queue_index(from)?;
+        self.check_queue_index(to)?;
+
+        let track = self.queue.remove(from - 1);
+        self.queue.insert(to - 1, track);
+        Ok(&self.queue[to - 1])
+    }
+
+    /// Swap the tracks at 1-based positions `a` and `b`. Refused in
+    /// fair-queue mode, like `move_in_queue`.
+    pub async fn swap_in_queue(
+        &mut self,
+        a: usize,
+        b: usize,
+    ) -> Result<(), PlaybackError> {
+        tracing::info!("Swapping queued tracks {} and {}", a, b);
+
+        if self.fair_queue {
+            return Err(PlaybackError::FairQueueActive);
+        }
+        self.check_queue_index(a)?;
+        self.check_queue_index(b)?;
+
+        self.queue.swap(a - 1, b - 1);
+        Ok(())
+    }
+
+    /// Drop later copies of tracks that are already queued, keeping the
+    /// earliest one. Returns how many were removed.
+    pub async fn dedupe_queue(&mut self) -> usize {
+        let before = self.queue.len();
+        let mut seen: HashSet<String> = HashSet::new();
+        let (kept, dropped): (Vec<Track>, Vec<Track>) = std::mem::take(&mut self.queue)
+            .into_iter()
+            .partition(|t| seen.insert(t.id.clone()));
+        self.queue = kept;
+        self.release(&dropped);
+
+        let removed = before - self.queue.len();
+        tracing::info!("Removed {} duplicate(s) from the queue", removed);
+        removed
     }
 
+    fn check_queue_index(
+        &self,
+        index: usize,
+    ) -> Result<(), PlaybackError> {
+        if index == 0 || index > self.queue.len() {
+            return Err(PlaybackError::InvalidQueueIndex(index));
+        }
+        Ok(())
+    }
+
+    /// In fair-queue mode the turns are restored afterwards, so each
+    /// requester's tracks come out shuffled among themselves.
     pub async fn shuffle(&mut self) -> Result<(), PlaybackError> {
         tracing::info!("Shuffling queue");
 
         if self.queue.len() > 1 {
             let mut rng = rand::rng();
             self.queue.shuffle(&mut rng);
+            self.interleave_queue();
         }
 
         Ok(())
@@ -442,10 +1081,6 @@ impl Player {
         volume /= 100.0;
         volume = volume.max(0.0);
 
-        if let Some(track_handle) = &self.track_handle {
-            let _ = track_handle.set_volume(volume * self.current_gain);
-        }
-
         let guild_id_map: i64 = self.guild_id.get() as i64;
 
         sqlx::query!(
@@ -455,12 +1090,64 @@ impl Player {
         )
         .execute(&*self.database)
         .await
-        .expect("TODO: panic message");
+        .map_err(|e| PlaybackError::InternalError(e.to_string()))?;
+
+        // Mid-crossfade the ramp owns both volumes and picks this up itself.
+        if let (Some(track_handle), None) = (&self.track_handle, &self.fading_out) {
+            let _ = track_handle.set_volume(volume * self.current_gain);
+        }
 
         self.volume = volume;
         Ok(())
     }
 
+    /// Set and persist the vote-skip threshold (clamped to 1–100%).
+    pub async fn set_vote_skip_percent(
+        &mut self,
+        percent: u8,
+    ) -> Result<(), PlaybackError> {
+        let percent = percent.clamp(1, 100);
+        tracing::info!("Setting vote-skip threshold to {}%", percent);
+
+        let guild_id_map: i64 = self.guild_id.get() as i64;
+
+        sqlx::query!(
+            "UPDATE guilds SET vote_skip_percent = $1 WHERE guild_id = $2",
+            percent,
+            guild_id_map
+        )
+        .execute(&*self.database)
+        .await
+        .map_err(|e| PlaybackError::InternalError(e.to_string()))?;
+
+        self.vote_skip_percent = percent;
+        Ok(())
+    }
+
+    /// Set and persist the crossfade length (capped at `MAX_CROSSFADE`).
+    pub async fn set_crossfade(
+        &mut self,
+        crossfade: Duration,
+    ) -> Result<(), PlaybackError> {
+        let crossfade = crossfade.min(MAX_CROSSFADE);
+        tracing::info!("Setting crossfade to {}s", crossfade.as_secs());
+
+        let guild_id_map: i64 = self.guild_id.get() as i64;
+        let secs: i64 = crossfade.as_secs() as i64;
+
+        sqlx::query!(
+            "UPDATE guilds SET crossfade_secs = $1 WHERE guild_id = $2",
+            secs,
+            guild_id_map
+        )
+        .execute(&*self.database)
+        .await
+        .map_err(|e| PlaybackError::InternalError(e.to_string()))?;
+
+        self.crossfade = crossfade;
+        Ok(())
+    }
+
     pub async fn pause(&mut self) -> Result<(), PlaybackError> {
         if !self.is_playing {
             return Err(PlaybackError::PlaybackNotActive);
@@ -473,6 +1160,9 @@ impl Player {
                 .pause()
                 .map_err(|e| PlaybackError::InternalError(e.to_string()))?;
         }
+        if let Some(fading) = &self.fading_out {
+            let _ = fading.handle.pause();
+        }
         self.is_paused = true;
         Ok(())
     }
@@ -486,15 +1176,25 @@ impl Player {
                 .play()
                 .map_err(|e| PlaybackError::InternalError(e.to_string()))?;
         }
+        if let Some(fading) = &self.fading_out {
+            let _ = fading.handle.play();
+        }
         self.is_paused = false;
         Ok(())
     }
 
     pub async fn stop_track(&mut self) -> Result<(), PlaybackError> {
         if self.is_playing {
-            if let Some(track_handle) = &self.track_handle {
+            if let Some(track_handle) = self.track_handle.clone() {
                 tracing::info!("Stopping track");
 
+                let listened = track_handle
+                    .get_info()
+                    .await
+                    .map(|state| state.play_time)
+                    .unwrap_or_default();
+                self.record_ended(listened, true).await;
+
                 if let Err(error) = track_handle.stop() {
                     tracing::error!("Error stopping track: {:?}", error);
                     return Err(PlaybackError::InternalError(format!(
@@ -505,19 +1205,91 @@ impl Player {
             }
         }
 
+        // Cutting the track also cuts whatever was still fading out under it.
+        if let Some(fading) = self.fading_out.take() {
+            let _ = fading.handle.stop();
+        }
+
         self.is_playing = false;
         self.is_paused = false;
         self.track_handle = None;
         self.current_track = None;
         self.current_source_path = None;
+        self.current_input_seekable = false;
+        self.current_timeline = InputTimeline::default();
         self.current_gain = 1.0;
+        self.skip_votes.clear();
 
         Ok(())
     }
 
+    /// Point the prefetch at the current head of the queue. A prefetch for a
+    /// track that's no longer next is cancelled, unless that track is the one
+    /// now playing — its download is still wanted for the cache. Called when
+    /// a track starts and polled while it plays, so queue edits of any kind
+    /// retarget it.
+    pub fn refresh_prefetch(&mut self) {
+        let head = self
+            .queue
+            .first()
+            .filter(|t| cache_service::is_cacheable(t) && !t.is_known_long_form());
+
+        if let (Some(prefetch), Some(head)) = (&self.prefetch, head) {
+            if prefetch.track_id == head.id && prefetch.filters == self.filters && prefetch.equalizer == self.equalizer {
+                return;
+            }
+        }
+
+        if let Some(prefetch) = self.prefetch.take() {
+            let now_playing = self
+                .current_track
+                .as_ref()
+                .is_some_and(|t| t.id == prefetch.track_id);
+            if !now_playing && !prefetch.task.is_finished() {
+                tracing::debug!("Cancelling prefetch of {}", prefetch.track_id);
+                prefetch.task.abort();
+            }
+        }
+
+        let Some(track) = head.cloned() else {
+            return;
+        };
+        let measure = self.should_normalize();
+        let filters = self.filters;
+        let equalizer = self.equalizer;
+
+        self.prefetch = Some(Prefetch {
+            track_id: track.id.clone(),
+            filters,
+            equalizer,
+            task: tokio::spawn(async move {
+                match cache_service::cache_track(&track).await {
+                    Ok(path) => {
+                        tracing::info!(
+                            "Prefetched '{}' to {}",
+                            track.metadata.title,
+                            path.display()
+                        );
+                        if measure {
+                            normalize_service::measurement_for(&path).await;
+                        }
+                        if filter_service::is_active(&filters, &equalizer) {
+                            filter_service::render_track(&track, &filters, &equalizer).await;
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to prefetch '{}': {}", track.metadata.title, e),
+                }
+            }),
+        });
+    }
+
     pub async fn stop_playback(&mut self) -> Result<(), PlaybackError> {
+        panel_service::close(self.guild_id);
         self.stop_track().await?;
-        self.queue.clear();
+        let dropped = std::mem::take(&mut self.queue);
+        self.release(&dropped);
+        self.set_looping(LoopMode::Off, None);
+        self.refresh_prefetch();
 
         Ok(())
     }
@@ -551,7 +1323,9 @@ pub fn schedule_normalization_apply(
             .unwrap_or_default();
         player.current_gain = measurement.multiplier;
         let effective = player.volume * measurement.multiplier;
-        let _ = handle.set_volume(effective);
+        if player.fading_out.is_none() {
+            let _ = handle.set_volume(effective);
+        }
         let lufs_str = measurement
             .lufs
             .map(|l| format!("{l:.2} LUFS"))
@@ -616,10 +1390,43 @@ pub fn spawn_cache_and_apply(
     });
 }
 
-/// Set the bot's Discord activity. We bake the "Playing " word into the label
-/// itself because some Discord clients hide the activity-type prefix on bots.
+/// Fetch the chapters of a track that started without them, through the
+/// same yt-dlp probe that finds durations, and store them on the current
+/// track if it's still playing. The control panel shows them on its next
+/// refresh. A no-op for local files and radio streams.
+pub fn spawn_chapter_lookup(
+    track: Track,
+    player_arc: Arc<tokio::sync::RwLock<Player>>,
+) {
+    if matches!(track.source, TrackSource::Local(_) | TrackSource::Radio) {
+        return;
+    }
+    tokio::spawn(async move {
+        let Some(probe) = cache_service::probe_track(&track).await else {
+            return;
+        };
+        let mut player = player_arc.write().await;
+        if let Some(current) = player.current_track.as_mut().filter(|t| t.id == track.id) {
+            current.metadata.chapters = Some(probe.chapters);
+        }
+    });
+}
+
+/// Activity label per guild that's playing something. The bot has one
+/// activity across all guilds, so it only names a track when a single
+/// guild is playing.
+static NOW_PLAYING: OnceLock<Mutex<HashMap<GuildId, String>>> = OnceLock::new();
+
+fn now_playing() -> &'static Mutex<HashMap<GuildId, String>> {
+    NOW_PLAYING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Record `track` as what `guild_id` is playing and update the bot's Discord
+/// activity. We bake the "Playing " word into the label itself because some
+/// Discord clients hide the activity-type prefix on bots.
 pub fn set_now_playing(
     ctx: &serenity_prelude::Context,
+    guild_id: GuildId,
     track: &Track,
 ) {
     let label = format!(
@@ -627,10 +1434,370 @@ pub fn set_now_playing(
         track.metadata.title,
         track.source.label()
     );
-    ctx.set_activity(Some(ActivityData::playing(label)));
+    now_playing()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(guild_id, label);
+    refresh_activity(ctx);
+}
+
+/// `guild_id` stopped playing; the activity falls back to another guild's
+/// track, or the idle status.
+pub fn set_idle(
+    ctx: &serenity_prelude::Context,
+    guild_id: GuildId,
+) {
+    now_playing()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&guild_id);
+    refresh_activity(ctx);
+}
+
+/// Show the track when exactly one guild is playing, a count when several
+/// are, and the friendly default status when none is.
+pub fn refresh_activity(ctx: &serenity_prelude::Context) {
+    let playing = now_playing().lock().unwrap_or_else(|e| e.into_inner());
+    let activity = match playing.len() {
+        0 => ActivityData::listening("!help · waiting for !play"),
+        1 => ActivityData::playing(playing.values().next().cloned().unwrap_or_default()),
+        n => ActivityData::playing(format!("Playing music in {n} servers")),
+    };
+    drop(playing);
+    ctx.set_activity(Some(activity));
+}
+
+/// Leave the voice channel after five minutes of inactivity, unless
+/// something gets queued first (which sets `cancel`) or playback resumes.
+pub fn spawn_inactivity_leave(
+    serenity_ctx: serenity_prelude::Context,
+    player_arc: Arc<tokio::sync::RwLock<Player>>,
+    guild_id: GuildId,
+    text_channel: GuildChannel,
+    cancel: Arc<AtomicBool>,
+) {
+    tokio::spawn(async move {
+        tokio::time::sleep(tokio::time::Duration::from_secs(5 * 60)).await;
+
+        if cancel.load(Ordering::Relaxed) {
+            tracing::debug!("Inactivity timer cancelled - new track was queued");
+            return;
+        }
+
+        if player_arc.read().await.is_playing {
+            return;
+        }
+
+        tracing::info!("Leaving voice channel after 5 minutes of inactivity");
+
+        let _ = PlayerEmbed::InactivityLeave
+            .to_embed()
+            .send_channel(serenity_ctx.http.clone(), &text_channel, Some(60), None)
+            .await;
+
+        let _ = player_arc.write().await.stop_playback().await;
+
+        if let Some(manager) = songbird::get(&serenity_ctx).await {
+            let _ = manager.remove(guild_id).await;
+        }
+    });
+}
+
+/// Requesters take turns through `queue`; see `Player::interleave_queue`.
+/// `playing` is the requester of the playing track, if one is playing.
+fn interleave(
+    queue: Vec<Track>,
+    playing: Option<Option<UserId>>,
+) -> Vec<Track> {
+    let mut requesters: Vec<Option<UserId>> = Vec::new();
+    let mut lanes: Vec<VecDeque<Track>> = Vec::new();
+    for track in queue {
+        let requester = track.added_by.id;
+        match requesters.iter().position(|r| *r == requester) {
+            Some(lane) => lanes[lane].push_back(track),
+            None => {
+                requesters.push(requester);
+                lanes.push(VecDeque::from([track]));
+            }
+        }
+    }
+
+    if lanes.len() > 1 && playing == Some(requesters[0]) {
+        lanes.rotate_left(1);
+    }
+
+    let mut interleaved = Vec::new();
+    while !lanes.is_empty() {
+        lanes.retain_mut(|lane| match lane.pop_front() {
+            Some(track) => {
+                interleaved.push(track);
+                true
+            }
+            None => false,
+        });
+    }
+    interleaved
 }
 
-/// Friendly default status shown whenever the bot isn't playing anything.
-pub fn set_idle(ctx: &serenity_prelude::Context) {
-    ctx.set_activity(Some(ActivityData::listening("!help · waiting for !play")));
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: UserId = UserId::new(1);
+    const BOB: UserId = UserId::new(2);
+
+    fn tracks(
+        n: usize,
+        user_id: UserId,
+        secs: u64,
+    ) -> Vec<Track> {
+        (0..n)
+            .map(|_| Track::stub().requested_by(user_id).lasting(secs))
+            .collect()
+    }
+
+    #[test]
+    fn no_limits_admit_everything() {
+        let limits = QueueLimits::default();
+        let queue = tracks(50, ALICE, 600);
+        assert!(limits
+            .admit(&queue, &Track::stub().requested_by(ALICE).lasting(600))
+            .is_ok());
+
+        let mut playlist = tracks(200, ALICE, 600);
+        assert!(limits
+            .trim_playlist(&queue, &mut playlist)
+            .unwrap()
+            .is_none());
+        assert_eq!(playlist.len(), 200);
+    }
+
+    #[test]
+    fn per_user_limit_counts_only_that_user() {
+        let limits = QueueLimits {
+            max_tracks_per_user: Some(2),
+            ..Default::default()
+        };
+        let mut queue = tracks(2, ALICE, 60);
+        queue.push(Track::stub().lasting(60));
+
+        assert!(matches!(
+            limits.admit(&queue, &Track::stub().requested_by(ALICE).lasting(60)),
+            Err(PlaybackError::UserTrackLimit(2))
+        ));
+        assert!(limits
+            .admit(&queue, &Track::stub().requested_by(BOB).lasting(60))
+            .is_ok());
+        // Autoplay has no requester and never hits the per-user limit.
+        assert!(limits.admit(&queue, &Track::stub().lasting(60)).is_ok());
+    }
+
+    #[test]
+    fn duration_limit_includes_the_new_track() {
+        let limits = QueueLimits {
+            max_queue_duration: Some(Duration::from_secs(300)),
+            ..Default::default()
+        };
+        let queue = tracks(2, ALICE, 100);
+
+        assert!(limits
+            .admit(&queue, &Track::stub().requested_by(BOB).lasting(100))
+            .is_ok());
+        assert!(matches!(
+            limits.admit(&queue, &Track::stub().requested_by(BOB).lasting(101)),
+            Err(PlaybackError::QueueDurationLimit(_))
+        ));
+        // Unprobed tracks count as zero.
+        assert!(limits
+            .admit(&queue, &Track::stub().requested_by(BOB))
+            .is_ok());
+    }
+
+    #[test]
+    fn playlist_trimmed_to_playlist_size() {
+        let limits = QueueLimits {
+            max_playlist_size: Some(3),
+            ..Default::default()
+        };
+        let mut playlist = tracks(5, ALICE, 60);
+        assert!(matches!(
+            limits.trim_playlist(&[], &mut playlist),
+            Ok(Some(QueueLimit::PlaylistSize(3)))
+        ));
+        assert_eq!(playlist.len(), 3);
+    }
+
+    #[test]
+    fn playlist_trimmed_to_room_left_for_user() {
+        let limits = QueueLimits {
+            max_tracks_per_user: Some(4),
+            ..Default::default()
+        };
+        let queue = tracks(3, ALICE, 60);
+
+        let mut playlist = tracks(5, ALICE, 60);
+        assert!(matches!(
+            limits.trim_playlist(&queue, &mut playlist),
+            Ok(Some(QueueLimit::TracksPerUser(4)))
+        ));
+        assert_eq!(playlist.len(), 1);
+
+        let queue = tracks(4, ALICE, 60);
+        let mut playlist = tracks(5, ALICE, 60);
+        assert!(matches!(
+            limits.trim_playlist(&queue, &mut playlist),
+            Err(PlaybackError::UserTrackLimit(4))
+        ));
+    }
+
+    #[test]
+    fn playlist_trimmed_to_remaining_duration() {
+        let limits = QueueLimits {
+            max_queue_duration: Some(Duration::from_secs(300)),
+            ..Default::default()
+        };
+        let queue = tracks(1, BOB, 100);
+
+        let mut playlist = tracks(5, ALICE, 60);
+        assert!(matches!(
+            limits.trim_playlist(&queue, &mut playlist),
+            Ok(Some(QueueLimit::QueueDuration(_)))
+        ));
+        assert_eq!(playlist.len(), 3);
+
+        let mut playlist = tracks(1, ALICE, 201);
+        assert!(matches!(
+            limits.trim_playlist(&queue, &mut playlist),
+            Err(PlaybackError::QueueDurationLimit(_))
+        ));
+    }
+
+    #[test]
+    fn trimming_twice_changes_nothing() {
+        let limits = QueueLimits {
+            max_tracks_per_user: Some(3),
+            max_queue_duration: Some(Duration::from_secs(150)),
+            max_playlist_size: Some(10),
+        };
+        let mut playlist = tracks(20, ALICE, 60);
+        limits.trim_playlist(&[], &mut playlist).unwrap();
+        assert_eq!(playlist.len(), 2);
+
+        assert!(limits.trim_playlist(&[], &mut playlist).unwrap().is_none());
+        assert_eq!(playlist.len(), 2);
+    }
+
+    fn requesters(queue: &[Track]) -> Vec<Option<UserId>> {
+        queue.iter().map(|t| t.added_by.id).collect()
+    }
+
+    #[test]
+    fn interleave_takes_turns_in_order_of_first_appearance() {
+        let mut queue = tracks(3, ALICE, 60);
+        queue.extend(tracks(1, BOB, 60));
+        queue.push(Track::stub().lasting(60));
+        let queue = interleave(queue, None);
+        assert_eq!(
+            requesters(&queue),
+            vec![Some(ALICE), Some(BOB), None, Some(ALICE), Some(ALICE)]
+        );
+    }
+
+    #[test]
+    fn interleave_keeps_each_requesters_order() {
+        let mut queue = tracks(3, ALICE, 60);
+        for (n, track) in queue.iter_mut().enumerate() {
+            track.id = n.to_string();
+        }
+        queue.insert(1, Track::stub().requested_by(BOB).lasting(60));
+        let ids: Vec<String> = interleave(queue, None)
+            .into_iter()
+            .filter(|t| t.added_by.is(ALICE))
+            .map(|t| t.id)
+            .collect();
+        assert_eq!(ids, vec!["0", "1", "2"]);
+    }
+
+    #[test]
+    fn interleave_skips_the_playing_requesters_turn() {
+        let mut queue = tracks(2, ALICE, 60);
+        queue.extend(tracks(2, BOB, 60));
+        let queue = interleave(queue, Some(Some(ALICE)));
+        assert_eq!(
+            requesters(&queue),
+            vec![Some(BOB), Some(ALICE), Some(BOB), Some(ALICE)]
+        );
+    }
+
+    #[test]
+    fn interleave_twice_changes_nothing() {
+        let mut queue = tracks(3, ALICE, 60);
+        queue.extend(tracks(2, BOB, 60));
+        let once = interleave(queue, Some(Some(BOB)));
+        let expected = requesters(&once);
+        assert_eq!(requesters(&interleave(once, Some(Some(BOB)))), expected);
+    }
+
+    fn secs(n: u64) -> Duration {
+        Duration::from_secs(n)
+    }
+
+    fn skip(
+        start: u64,
+        end: u64,
+    ) -> SkipSegment {
+        SkipSegment { start: secs(start), end: secs(end) }
+    }
+
+    #[test]
+    fn default_timeline_is_identity() {
+        let timeline = InputTimeline::default();
+        assert_eq!(timeline.track_position(secs(42)), secs(42));
+        assert_eq!(timeline.input_position(secs(42)), secs(42));
+    }
+
+    #[test]
+    fn timeline_leaves_out_skips() {
+        let timeline = InputTimeline {
+            skips: vec![skip(10, 20), skip(50, 60)],
+            ..Default::default()
+        };
+        assert_eq!(timeline.track_position(secs(5)), secs(5));
+        // Inside a skip counts as its start.
+        assert_eq!(timeline.track_position(secs(15)), secs(10));
+        assert_eq!(timeline.track_position(secs(30)), secs(20));
+        assert_eq!(timeline.track_position(secs(70)), secs(50));
+
+        assert_eq!(timeline.input_position(secs(5)), secs(5));
+        assert_eq!(timeline.input_position(secs(20)), secs(30));
+        assert_eq!(timeline.input_position(secs(50)), secs(70));
+    }
+
+    #[test]
+    fn timeline_applies_rate_and_offset() {
+        let timeline = InputTimeline {
+            rate: 2.0,
+            skips: Vec::new(),
+            offset: secs(30),
+        };
+        // Rendered at double speed: 20s of input is 40s of video, 10s into
+        // a track that starts 30s in.
+        assert_eq!(timeline.track_position(secs(20)), secs(10));
+        assert_eq!(timeline.input_position(secs(10)), secs(20));
+        // Before the start clamps to zero.
+        assert_eq!(timeline.track_position(secs(5)), Duration::ZERO);
+    }
+
+    #[test]
+    fn timeline_round_trips() {
+        let timeline = InputTimeline {
+            rate: 2.0,
+            skips: vec![skip(40, 50), skip(100, 130)],
+            offset: secs(20),
+        };
+        for position in [0, 15, 30, 75, 200] {
+            let input = timeline.input_position(secs(position));
+            assert_eq!(timeline.track_position(input), secs(position));
+        }
+    }
 }
@@ -1,5 +1,10 @@
 use crate::service::cache_service;
-use songbird::input::{File, Input, YoutubeDl};
+use crate::service::filter_service::{self, AudioFilters, Equalizer};
+use crate::service::sponsorblock_service::{self, SkipSegment};
+use crate::utils::time_utils;
+use serenity::all::{User, UserId};
+use songbird::input::{File, HttpRequest, Input, YoutubeDl};
+use std::fmt;
 use std::path::PathBuf;
 use std::time::Duration;
 
@@ -32,8 +37,20 @@ pub enum PlaybackError {
     #[error("Playback is not paused")]
     PlaybackNotPaused,
 
+    #[error("There is no earlier track to go back to")]
+    NoPreviousTrack,
+
     #[error("Invalid queue index: {0}")]
     InvalidQueueIndex(usize),
+
+    #[error("Fair queue decides the order while it's on")]
+    FairQueueActive,
+
+    #[error("You already have {0} track(s) queued, the most one person can have at once")]
+    UserTrackLimit(u32),
+
+    #[error("The queue is capped at {} of music", time_utils::humanize_duration(*.0))]
+    QueueDurationLimit(Duration),
 }
 
 #[derive(Debug, Clone)]
@@ -49,8 +66,62 @@ pub struct Playlist {
 pub struct Track {
     pub id: String,
     pub metadata: TrackMetadata,
-    pub added_by: String,
+    pub added_by: Requester,
     pub source: TrackSource,
+    /// The part of the video to play, when only part of it was asked for.
+    pub range: Option<TrackRange>,
+}
+
+/// Part of a video to play, on the video's own timeline: from a `t=` in its
+/// URL, or `play <url> from 1:30 to 4:00`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrackRange {
+    pub start: Duration,
+    /// `None` plays to the end.
+    pub end: Option<Duration>,
+}
+
+/// Who queued a track. `id` is what requester checks, fair queueing and
+/// `remove @user` go by, since display names change; `name` is only shown.
+/// Tracks the bot queues itself (autoplay) have a name but no id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Requester {
+    pub id: Option<UserId>,
+    pub name: String,
+}
+
+impl Requester {
+    pub fn user(user: &User) -> Self {
+        Self {
+            id: Some(user.id),
+            name: user.name.clone(),
+        }
+    }
+
+    /// A requester that isn't a Discord user, e.g. autoplay.
+    pub fn named(name: &str) -> Self {
+        Self { id: None, name: name.to_string() }
+    }
+
+    pub fn is(
+        &self,
+        user_id: UserId,
+    ) -> bool {
+        self.id == Some(user_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none() && self.name.is_empty()
+    }
+}
+
+impl fmt::Display for Requester {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        f.write_str(&self.name)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -61,14 +132,24 @@ pub enum TrackSource {
     Spotify,
     /// A previously downloaded file on the local filesystem.
     Local(PathBuf),
+    /// Any other site yt-dlp can play (SoundCloud, Bandcamp, Twitch VODs…),
+    /// streamed via yt-dlp from its URL. `extractor` is yt-dlp's name for
+    /// the site, e.g. `Soundcloud`.
+    Generic { extractor: String },
+    /// An Icecast/Shoutcast station or other raw HTTP audio stream, played
+    /// straight from its URL for as long as it keeps sending.
+    Radio,
 }
 
 impl TrackSource {
-    pub fn label(&self) -> &'static str {
+    pub fn label(&self) -> &str {
         match self {
             TrackSource::YouTube => "YouTube",
             TrackSource::Spotify => "Spotify",
             TrackSource::Local(_) => "Local file",
+            TrackSource::Generic { extractor } if !extractor.is_empty() => extractor,
+            TrackSource::Generic { .. } => "Web",
+            TrackSource::Radio => "Radio",
         }
     }
 
@@ -77,32 +158,111 @@ impl TrackSource {
             TrackSource::YouTube => "🎬",
             TrackSource::Spotify => "🟢",
             TrackSource::Local(_) => "📁",
+            TrackSource::Generic { .. } => "🌐",
+            TrackSource::Radio => "📻",
         }
     }
+
+    /// Stable key stored in the database alongside a persisted track. Local
+    /// files keep their path in a separate column; generic tracks carry
+    /// their extractor, as `generic:<extractor>`.
+    pub fn kind(&self) -> String {
+        match self {
+            TrackSource::YouTube => "youtube".to_string(),
+            TrackSource::Spotify => "spotify".to_string(),
+            TrackSource::Local(_) => "local".to_string(),
+            TrackSource::Generic { extractor } => format!("{GENERIC_KIND_PREFIX}{extractor}"),
+            TrackSource::Radio => "radio".to_string(),
+        }
+    }
+
+    /// Inverse of `kind`. `None` for unknown keys or a local track stored
+    /// without a path.
+    pub fn from_kind(
+        kind: &str,
+        local_path: Option<String>,
+    ) -> Option<Self> {
+        if let Some(extractor) = kind.strip_prefix(GENERIC_KIND_PREFIX) {
+            return Some(TrackSource::Generic { extractor: extractor.to_string() });
+        }
+        match kind {
+            "youtube" => Some(TrackSource::YouTube),
+            "spotify" => Some(TrackSource::Spotify),
+            "radio" => Some(TrackSource::Radio),
+            "local" => local_path.map(|p| TrackSource::Local(PathBuf::from(p))),
+            _ => None,
+        }
+    }
+}
+
+const GENERIC_KIND_PREFIX: &str = "generic:";
+
+/// The input a track plays from, as picked by `Track::resolve_input`.
+pub struct ResolvedInput {
+    pub input: Input,
+    /// The on-disk file the input was built from, before any effects — what
+    /// loudness normalization measures. `None` for streamed inputs.
+    pub source_path: Option<PathBuf>,
+    /// How fast the input plays the track; `1.0` unless it was rendered at
+    /// another speed.
+    pub rate: f32,
+    /// Whether the input carries the filters and equalizer. `false` with
+    /// effects on means the render isn't there yet and the track plays
+    /// unfiltered until it is.
+    pub filtered: bool,
 }
 
 impl Track {
     /// Pick the best input for this track:
-    ///   1. If a raw cache exists, play that.
-    ///   2. Else stream through yt-dlp; the caller is expected to kick off
+    ///   1. With filters or an equalizer set, play the render of the file if
+    ///      it's been made. Otherwise the caller renders it in the
+    ///      background (`Playback::spawn_render`) and falls through to the
+    ///      unfiltered input meanwhile.
+    ///   2. If a local file or a raw cache exists, play that.
+    ///   3. Else stream through yt-dlp; the caller is expected to kick off
     ///      a background cache-and-normalize pass via
     ///      `spawn_cache_and_apply` so the gain can be applied mid-track
     ///      as soon as the cache is ready.
     ///
-    /// Returns the chosen `Input` along with the on-disk path it was built
-    /// from (when available). The path is what loudness normalization needs;
-    /// streamed inputs return `None`.
+    /// Radio streams skip all of that and play straight from their URL:
+    /// there's no end to render or cache.
     pub async fn resolve_input(
         &self,
         req_client: &reqwest::Client,
-    ) -> (Input, Option<PathBuf>) {
-        if let TrackSource::Local(path) = &self.source {
-            return (File::new(path.clone()).into(), Some(path.clone()));
+        filters: &AudioFilters,
+        equalizer: &Equalizer,
+    ) -> ResolvedInput {
+        if matches!(self.source, TrackSource::Radio) {
+            return ResolvedInput {
+                input: HttpRequest::new(req_client.clone(), self.metadata.track_url.clone()).into(),
+                source_path: None,
+                rate: 1.0,
+                filtered: false,
+            };
         }
 
-        if let Some(raw) = cache_service::find_cached(self).await {
-            let path = raw.clone();
-            return (File::new(raw).into(), Some(path));
+        if filter_service::is_active(filters, equalizer) {
+            if let Some(rendered) = filter_service::find_render(self, filters, equalizer).await {
+                return ResolvedInput {
+                    input: File::new(rendered.path).into(),
+                    source_path: Some(rendered.source),
+                    rate: filters.speed,
+                    filtered: true,
+                };
+            }
+        }
+
+        let file = match &self.source {
+            TrackSource::Local(path) => Some(path.clone()),
+            _ => cache_service::find_cached(self).await,
+        };
+        if let Some(path) = file {
+            return ResolvedInput {
+                input: File::new(path.clone()).into(),
+                source_path: Some(path),
+                rate: 1.0,
+                filtered: false,
+            };
         }
 
         // Cache miss: stream now. The caller fires off the cache write.
@@ -113,7 +273,12 @@ impl Track {
             .play_url
             .clone()
             .unwrap_or_else(|| self.metadata.track_url.clone());
-        (YoutubeDl::new(req_client.clone(), input_url).into(), None)
+        ResolvedInput {
+            input: YoutubeDl::new(req_client.clone(), input_url).into(),
+            source_path: None,
+            rate: 1.0,
+            filtered: false,
+        }
     }
 }
 
@@ -126,15 +291,78 @@ pub struct TrackMetadata {
     /// Optional override used by `build_input`. For Spotify this is the
     /// `ytsearch1:` query, while `track_url` stays the Spotify permalink.
     pub play_url: Option<String>,
+    /// Cover art or video thumbnail, when the source has one.
+    pub thumbnail: Option<String>,
     /// Reported track length, when the source knew it at resolution time.
     /// `None` means we never asked — sources without cheap duration info
     /// (YouTube Data API search) leave this unset until a yt-dlp probe runs.
     pub duration: Option<Duration>,
+    /// SponsorBlock segments left out of the track, when the guild's
+    /// SponsorBlock mode found any. Never persisted; looked up again when
+    /// the track is queued or started.
+    pub skip_segments: Vec<SkipSegment>,
+    /// The video's chapters, in order. `None` until yt-dlp has been asked;
+    /// an empty list means it has none.
+    pub chapters: Option<Vec<Chapter>>,
+}
+
+/// A titled section of a video, starting `start` into it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chapter {
+    pub title: String,
+    pub start: Duration,
 }
 
 impl Track {
+    /// How long the track plays for: the part of its reported length in
+    /// its range, minus any SponsorBlock segments that get cut or skipped.
+    /// Known without the reported length when the range has an end.
     pub fn duration(&self) -> Option<Duration> {
-        self.metadata.duration
+        let end = match self.range.and_then(|range| range.end) {
+            Some(end) => Some(self.metadata.duration.map_or(end, |d| d.min(end))),
+            None => self.metadata.duration,
+        };
+        end.map(|end| self.track_time(end))
+    }
+
+    /// Where the track starts in the video.
+    pub fn start(&self) -> Duration {
+        self.range.map(|range| range.start).unwrap_or_default()
+    }
+
+    /// Where `position` in the video falls in the track as it plays:
+    /// SponsorBlock segments don't count, and the track begins at its
+    /// range's start.
+    pub fn track_time(
+        &self,
+        position: Duration,
+    ) -> Duration {
+        let skips = &self.metadata.skip_segments;
+        sponsorblock_service::trimmed_position(skips, position).saturating_sub(sponsorblock_service::trimmed_position(skips, self.start()))
+    }
+
+    pub fn chapters(&self) -> &[Chapter] {
+        self.metadata.chapters.as_deref().unwrap_or_default()
+    }
+
+    /// Where `chapter` starts in the track as it plays; chapters before
+    /// the range's start all start at zero.
+    pub fn chapter_start(
+        &self,
+        chapter: &Chapter,
+    ) -> Duration {
+        self.track_time(chapter.start)
+    }
+
+    /// Index of the chapter playing at `position`, `None` before the first
+    /// one starts or when the track has no chapters.
+    pub fn chapter_at(
+        &self,
+        position: Duration,
+    ) -> Option<usize> {
+        self.chapters()
+            .iter()
+            .rposition(|chapter| self.chapter_start(chapter) <= position)
     }
 
     /// True when the track is known to be over the hard length cap. Returns
@@ -145,8 +373,78 @@ impl Track {
     }
 
     /// True when the track is known to be too long for caching. Returns
-    /// false if duration is unknown.
+    /// false if duration is unknown. Goes by the whole video rather than
+    /// `duration()`: a range still downloads all of it.
     pub fn is_known_long_form(&self) -> bool {
-        self.duration().is_some_and(|d| d > STREAM_ONLY_DURATION)
+        self.metadata
+            .duration
+            .is_some_and(|d| d > STREAM_ONLY_DURATION)
+    }
+}
+
+/// Tracks for tests, built up from a bare YouTube track with only what the
+/// test cares about set.
+#[cfg(test)]
+impl Track {
+    pub fn stub() -> Self {
+        Track {
+            id: String::new(),
+            metadata: TrackMetadata {
+                id: String::new(),
+                title: String::new(),
+                channel: String::new(),
+                track_url: String::new(),
+                play_url: None,
+                thumbnail: None,
+                duration: None,
+                skip_segments: Vec::new(),
+                chapters: None,
+            },
+            added_by: Requester::default(),
+            source: TrackSource::YouTube,
+            range: None,
+        }
+    }
+
+    pub fn titled(
+        mut self,
+        channel: &str,
+        title: &str,
+    ) -> Self {
+        self.metadata.channel = channel.to_string();
+        self.metadata.title = title.to_string();
+        self
+    }
+
+    pub fn linked(
+        mut self,
+        url: &str,
+    ) -> Self {
+        self.metadata.track_url = url.to_string();
+        self
+    }
+
+    pub fn sourced(
+        mut self,
+        source: TrackSource,
+    ) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn requested_by(
+        mut self,
+        user_id: UserId,
+    ) -> Self {
+        self.added_by.id = Some(user_id);
+        self
+    }
+
+    pub fn lasting(
+        mut self,
+        secs: u64,
+    ) -> Self {
+        self.metadata.duration = Some(Duration::from_secs(secs));
+        self
     }
 }
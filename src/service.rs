@@ -1,10 +1,22 @@
+pub mod attachment_service;
 pub mod attendance_service;
+pub mod autoplay_service;
 pub mod cache_service;
 pub mod channel_service;
+pub mod dj_service;
 pub mod embed_service;
 pub mod emoticon_service;
+pub mod filter_service;
 pub mod gather_service;
+pub mod history_service;
 pub mod interaction_service;
 pub mod normalize_service;
 pub mod notifier_service;
+pub mod panel_service;
 pub mod picker_service;
+pub mod player_service;
+pub mod playlist_service;
+pub mod queue_file_service;
+pub mod session_service;
+pub mod sponsorblock_service;
+pub mod vote_skip_service;
@@ -0,0 +1,104 @@
+//! Audio attachments played once, through `play` or the "Play audio" message
+//! command. Unlike `local upload` they never reach `downloads/`: each one is
+//! streamed into `cache/attachments/` and queued as a local file from there.
+//! The file is deleted once the player lets go of its last copy of the
+//! track, whether it drops out of the history or is removed, deduped,
+//! skipped past or cleared from the queue. Anything left over from the last
+//! run is cleared when the bot starts.
+
+use crate::bot::MusicBotError;
+use crate::player::track::{Track, TrackSource};
+use crate::service::cache_service;
+use serenity::all::AttachmentId;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+
+const ATTACHMENTS_SUBDIR: &str = "attachments";
+
+pub fn attachments_dir() -> PathBuf {
+    cache_service::cache_dir().join(ATTACHMENTS_SUBDIR)
+}
+
+/// Whether `track` plays from a temporary attachment file.
+pub fn is_temporary(track: &Track) -> bool {
+    matches!(&track.source, TrackSource::Local(path) if path.starts_with(attachments_dir()))
+}
+
+/// Stream the attachment at `url` into the temporary cache as `filename`,
+/// returning its path. An attachment that's already there (played again
+/// while still in history) is reused.
+pub async fn fetch(
+    client: &reqwest::Client,
+    id: AttachmentId,
+    url: &str,
+    filename: &str,
+) -> Result<PathBuf, MusicBotError> {
+    let dir = attachments_dir();
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| MusicBotError::InternalError(format!("Could not create attachment cache: {e}")))?;
+
+    // The id keeps two uploads with the same name apart.
+    let target = dir.join(format!("{id}_{filename}"));
+    if tokio::fs::try_exists(&target).await.unwrap_or(false) {
+        return Ok(target);
+    }
+
+    let mut response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(|e| MusicBotError::InternalError(format!("Could not fetch `{filename}`: {e}")))?;
+
+    let part = dir.join(format!("{id}_{filename}.part"));
+    let written: Result<(), String> = async {
+        let mut file = tokio::fs::File::create(&part)
+            .await
+            .map_err(|e| e.to_string())?;
+        while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+            file.write_all(&chunk).await.map_err(|e| e.to_string())?;
+        }
+        file.flush().await.map_err(|e| e.to_string())
+    }
+    .await;
+
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&part).await;
+        return Err(MusicBotError::InternalError(format!(
+            "Could not fetch `{filename}`: {e}"
+        )));
+    }
+
+    tokio::fs::rename(&part, &target)
+        .await
+        .map_err(|e| MusicBotError::InternalError(format!("Could not store `{filename}`: {e}")))?;
+    Ok(target)
+}
+
+/// Delete `track`'s temporary file in the background. A no-op for any
+/// other track.
+pub fn discard(track: &Track) {
+    if !is_temporary(track) {
+        return;
+    }
+    let TrackSource::Local(path) = track.source.clone() else {
+        return;
+    };
+    tokio::spawn(async move {
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            tracing::debug!("Could not delete attachment {}: {}", path.display(), e);
+        }
+    });
+}
+
+/// Empty the temporary cache. Called at startup, when no track can still
+/// be using a file from the last run.
+pub async fn clear() {
+    let dir = attachments_dir();
+    match tokio::fs::remove_dir_all(&dir).await {
+        Ok(()) => tracing::info!("Cleared leftover attachments in {}", dir.display()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => tracing::warn!("Could not clear {}: {}", dir.display(), e),
+    }
+}
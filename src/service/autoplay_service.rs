@@ -0,0 +1,70 @@
+//! Autoplay ("radio") picks: when the queue runs dry and autoplay is on, the
+//! queue handler asks for a few tracks related to whatever played last.
+//! YouTube tracks use YouTube's auto-generated mix for the video; Spotify
+//! tracks (resolved through `ytsearch1:`) search for more by the same artist.
+//! Everything goes through yt-dlp, so no YouTube API quota is spent.
+
+use crate::player::player::Player;
+use crate::player::track::{Requester, Track, TrackSource, MAX_TRACK_DURATION};
+use crate::sources::youtube_player;
+use std::collections::HashSet;
+
+/// `added_by` label for tracks queued by autoplay.
+pub const AUTOPLAY_REQUESTER: &str = "Autoplay";
+/// How many related tracks to queue each time the queue runs dry.
+const BATCH_SIZE: usize = 3;
+/// How many candidates to ask yt-dlp for before de-duplication.
+const CANDIDATES: usize = 25;
+
+/// Everything autoplay must not pick again: the player's history, the track
+/// that just ended, and whatever is still queued. Ids and lowercased titles
+/// are both recorded, since a Spotify track and its YouTube match share a
+/// title but not an id.
+pub fn exclusions(player: &Player) -> HashSet<String> {
+    player
+        .history
+        .iter()
+        .chain(player.current_track.iter())
+        .chain(player.queue.iter())
+        .flat_map(|t| [t.id.clone(), t.metadata.title.to_lowercase()])
+        .collect()
+}
+
+/// Up to `BATCH_SIZE` playable tracks related to `seed`, none of them in
+/// `exclude`. Empty when nothing suitable was found (or `seed` is a local
+/// file, which has nothing to relate to).
+pub async fn related_tracks(
+    seed: &Track,
+    exclude: &HashSet<String>,
+) -> Vec<Track> {
+    let candidates = match &seed.source {
+        TrackSource::YouTube => youtube_player::fetch_mix(&seed.id, CANDIDATES).await,
+        TrackSource::Spotify if !seed.metadata.channel.is_empty() => youtube_player::search_flat(&seed.metadata.channel, CANDIDATES).await,
+        _ => return Vec::new(),
+    };
+
+    let candidates = match candidates {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            tracing::warn!(
+                "Autoplay lookup failed for '{}': {}",
+                seed.metadata.title,
+                e
+            );
+            return Vec::new();
+        }
+    };
+
+    let mut seen: HashSet<String> = HashSet::new();
+    candidates
+        .into_iter()
+        .filter(|t| !exclude.contains(&t.id) && !exclude.contains(&t.metadata.title.to_lowercase()))
+        .filter(|t| t.duration().is_some_and(|d| d <= MAX_TRACK_DURATION))
+        .filter(|t| seen.insert(t.id.clone()))
+        .take(BATCH_SIZE)
+        .map(|mut t| {
+            t.added_by = Requester::named(AUTOPLAY_REQUESTER);
+            t
+        })
+        .collect()
+}
@@ -1,17 +1,19 @@
 //! On-disk cache for tracks resolved through yt-dlp. Once a track has played
 //! through, the audio is kept under `cache/<source>/<title>_<id>.<ext>`
-//! (with `<source>` being `youtube` or `spotify`, and `<ext>` whatever native
-//! container yt-dlp produced — usually `webm` or `m4a`) so subsequent plays
-//! skip the YouTube fetch (and the API/quota hit that goes with it). The
-//! project's symphonia decoder is built with `features = ["all"]`, so any
+//! (with `<source>` being `youtube`, `spotify` or `generic`, and `<ext>`
+//! whatever native container yt-dlp produced — usually `webm` or `m4a`) so
+//! subsequent plays skip the fetch (and, for YouTube, the API/quota hit that
+//! goes with it). The project's symphonia decoder is built with `features = ["all"]`, so any
 //! container yt-dlp picks plays back fine.
 //!
 //! Legacy flat `cache/<stem>.<ext>` files from before the split are still
 //! discovered on read, so an existing cache survives the upgrade — only new
 //! downloads land in the per-source folders.
 
-use crate::player::track::{Track, TrackSource};
-use crate::service::normalize_service;
+use crate::player::track::{Chapter, Track, TrackSource};
+use crate::service::{normalize_service, sponsorblock_service};
+use crate::sources::youtube_player;
+use crate::utils::lock_utils::KeyedLocks;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::time::Duration;
@@ -24,19 +26,29 @@ use tokio::process::Command;
 const CACHE_DIR: &str = "cache";
 const YOUTUBE_SUBDIR: &str = "youtube";
 const SPOTIFY_SUBDIR: &str = "spotify";
+const GENERIC_SUBDIR: &str = "generic";
 const MAX_FILENAME_STEM: usize = 80;
+/// Appended to the stem of a download with its SponsorBlock segments cut
+/// out, so it doesn't stand in for the full track (or the other way round).
+const SPONSORBLOCK_SUFFIX: &str = "_sponsorblock";
+
+/// One lock per cache stem being downloaded. A prefetch and the playing
+/// track's cache job can ask for the same track; the second one waits and
+/// then finds the first one's file instead of racing it on the `.part` file.
+static IN_FLIGHT: KeyedLocks<String> = KeyedLocks::new();
 
 pub fn cache_dir() -> PathBuf {
     PathBuf::from(CACHE_DIR)
 }
 
 /// Per-source cache directory. `None` for tracks that aren't fetched (local
-/// files), since we never write those to the cache.
+/// files) or never end (radio), since we never write those to the cache.
 pub fn cache_dir_for(source: &TrackSource) -> Option<PathBuf> {
     let sub = match source {
         TrackSource::YouTube => YOUTUBE_SUBDIR,
         TrackSource::Spotify => SPOTIFY_SUBDIR,
-        TrackSource::Local(_) => return None,
+        TrackSource::Generic { .. } => GENERIC_SUBDIR,
+        TrackSource::Local(_) | TrackSource::Radio => return None,
     };
     Some(cache_dir().join(sub))
 }
@@ -73,15 +85,16 @@ fn sanitize(name: &str) -> String {
 /// the track isn't a fetched source (e.g. local files) or it lacks a usable id.
 pub fn cache_stem_for(track: &Track) -> Option<String> {
     match &track.source {
-        TrackSource::YouTube | TrackSource::Spotify => {
+        TrackSource::YouTube | TrackSource::Spotify | TrackSource::Generic { .. } => {
             let id = sanitize(&track.metadata.id);
             if id.is_empty() {
                 return None;
             }
             let title = sanitize(&track.metadata.title);
-            Some(format!("{title}_{id}"))
+            let suffix = if track.metadata.skip_segments.is_empty() { "" } else { SPONSORBLOCK_SUFFIX };
+            Some(format!("{title}_{id}{suffix}"))
         }
-        TrackSource::Local(_) => None,
+        TrackSource::Local(_) | TrackSource::Radio => None,
     }
 }
 
@@ -136,10 +149,20 @@ async fn find_in_dir(
 }
 
 /// Download `track` through yt-dlp into the cache, returning the final path.
-/// No-op (returns existing path) if a cached copy already exists.
+/// No-op (returns existing path) if a cached copy already exists. Dropping
+/// the future (e.g. aborting a prefetch) kills the yt-dlp process.
 pub async fn cache_track(track: &Track) -> std::io::Result<PathBuf> {
     let stem = cache_stem_for(track).ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidInput, "track is not cacheable"))?;
 
+    let in_flight = IN_FLIGHT.join(stem.clone());
+    let _guard = in_flight.lock().await;
+    download_track(track, &stem).await
+}
+
+async fn download_track(
+    track: &Track,
+    stem: &str,
+) -> std::io::Result<PathBuf> {
     if let Some(existing) = find_cached(track).await {
         return Ok(existing);
     }
@@ -162,17 +185,27 @@ pub async fn cache_track(track: &Track) -> std::io::Result<PathBuf> {
     // picked up by `find_cached` on a concurrent lookup.
     let output_template = dir.join(format!("{stem}.part.%(ext)s"));
 
-    let output = Command::new("yt-dlp")
-        .args(["--no-warnings", "--no-playlist", "-f", "bestaudio/best", "-o"])
+    let mut command = Command::new("yt-dlp");
+    command.args(["--no-warnings", "--no-playlist", "-f", "bestaudio/best"]);
+    // Cut the same segments a stream of the track skips over.
+    if !track.metadata.skip_segments.is_empty() {
+        command
+            .arg("--sponsorblock-remove")
+            .arg(sponsorblock_service::CATEGORIES.join(","));
+    }
+
+    let output = command
+        .arg("-o")
         .arg(&output_template)
         .arg(&input_url)
         .stdout(Stdio::null())
         .stderr(Stdio::piped())
+        .kill_on_drop(true)
         .output()
         .await?;
 
     if !output.status.success() {
-        cleanup_part_files(&dir, &stem).await;
+        cleanup_part_files(&dir, stem).await;
         let stderr = String::from_utf8_lossy(&output.stderr);
         let tail = stderr
             .lines()
@@ -252,12 +285,15 @@ pub struct TrackProbe {
     pub duration: Option<Duration>,
     /// `true` when yt-dlp explicitly reports the video as a live broadcast.
     pub is_live: bool,
+    /// The video's chapters; empty when it has none.
+    pub chapters: Vec<Chapter>,
 }
 
-/// Probe `track` with a single yt-dlp invocation, fetching both duration and
-/// live-status at once. Returns `None` for local files (no probe needed).
+/// Probe `track` with a single yt-dlp invocation, fetching duration,
+/// live-status and chapters at once. Returns `None` for local files and radio
+/// streams (no probe needed).
 pub async fn probe_track(track: &Track) -> Option<TrackProbe> {
-    if matches!(track.source, TrackSource::Local(_)) {
+    if matches!(track.source, TrackSource::Local(_) | TrackSource::Radio) {
         return None;
     }
     let input_url = track
@@ -267,7 +303,7 @@ pub async fn probe_track(track: &Track) -> Option<TrackProbe> {
         .unwrap_or_else(|| track.metadata.track_url.clone());
 
     let output = Command::new("yt-dlp")
-        .args(["--no-warnings", "--no-playlist", "--print", "%(duration)s", "--print", "%(is_live)s"])
+        .args(["--no-warnings", "--no-playlist", "--print", "%(duration)s", "--print", "%(is_live)s", "--print", "%(chapters)j"])
         .arg(&input_url)
         .stdout(Stdio::piped())
         .stderr(Stdio::null())
@@ -283,6 +319,7 @@ pub async fn probe_track(track: &Track) -> Option<TrackProbe> {
     let mut lines = stdout.lines();
     let duration_line = lines.next().unwrap_or("").trim().to_owned();
     let is_live_line = lines.next().unwrap_or("").trim().to_owned();
+    let chapters_line = lines.next().unwrap_or("").trim().to_owned();
 
     let duration = duration_line.parse::<f64>().ok().and_then(
         |s| {
@@ -294,6 +331,10 @@ pub async fn probe_track(track: &Track) -> Option<TrackProbe> {
         },
     );
     let is_live = is_live_line.eq_ignore_ascii_case("true");
+    let chapters = serde_json::from_str(&chapters_line)
+        .ok()
+        .and_then(|v| youtube_player::parse_chapters(&v))
+        .unwrap_or_default();
 
-    Some(TrackProbe { duration, is_live })
+    Some(TrackProbe { duration, is_live, chapters })
 }
@@ -1,6 +1,8 @@
 use crate::bot::{Context, MusicBotError};
 use crate::handlers::error_handler::ErrorHandler;
+use crate::service::player_service;
 use serenity::all::{ChannelId, GuildId, UserId};
+use serenity::prelude::Context as SerenityContext;
 use songbird::{Call, Event, Songbird};
 use std::sync::Arc;
 use tokio::sync::MutexGuard;
@@ -21,11 +23,24 @@ pub async fn join_user_channel(ctx: Context<'_>) -> Result<ChannelId, MusicBotEr
         }
     };
 
-    let manager: Arc<Songbird> = songbird::get(ctx.serenity_context())
+    join_channel(ctx.serenity_context(), guild_id, chanel_id).await?;
+
+    Ok(chanel_id)
+}
+
+/// Join `channel_id` in `guild_id` and attach our songbird event listeners.
+/// Used directly where there is no command author to follow (e.g. restoring
+/// a saved session).
+pub async fn join_channel(
+    serenity_ctx: &SerenityContext,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Result<(), MusicBotError> {
+    let manager: Arc<Songbird> = songbird::get(serenity_ctx)
         .await
         .ok_or_else(|| MusicBotError::InternalError("Could not locate voice channel. Songbird manager does not exist".to_owned()))?;
 
-    match manager.join(guild_id, chanel_id).await {
+    match manager.join(guild_id, channel_id).await {
         Ok(handle_lock) => {
             let mut handle: MutexGuard<Call> = handle_lock.lock().await;
 
@@ -41,7 +56,7 @@ pub async fn join_user_channel(ctx: Context<'_>) -> Result<ChannelId, MusicBotEr
         }
     }
 
-    Ok(chanel_id)
+    Ok(())
 }
 
 /// Stop playback, clear the queue, and drop the songbird Call for this guild.
@@ -55,7 +70,9 @@ pub async fn leave_channel(ctx: Context<'_>) -> Result<(), MusicBotError> {
         .await
         .ok_or_else(|| MusicBotError::InternalError("Songbird manager not registered".to_owned()))?;
 
-    let _ = ctx.data().player.write().await.stop_playback().await;
+    if let Some(player) = player_service::existing_player(ctx.data(), guild_id).await {
+        let _ = player.write().await.stop_playback().await;
+    }
 
     // The alone-in-channel handler may have already removed the Call.
     if let Some(handle_lock) = manager.get(guild_id) {
@@ -85,3 +102,38 @@ pub fn get_user_voice_channel(
         .and_then(|guild| guild.voice_states.get(user_id))
         .and_then(|voice_state| voice_state.channel_id)
 }
+
+/// Voice channel the bot is connected to in `guild_id`, according to the cache.
+pub fn bot_voice_channel(
+    serenity_ctx: &SerenityContext,
+    guild_id: GuildId,
+) -> Option<ChannelId> {
+    let bot_id = serenity_ctx.cache.current_user().id;
+    serenity_ctx
+        .cache
+        .guild(guild_id)
+        .as_ref()
+        .and_then(|g| g.voice_states.get(&bot_id))
+        .and_then(|vs| vs.channel_id)
+}
+
+/// Everyone in `channel_id` except the bot itself.
+pub fn listeners(
+    serenity_ctx: &SerenityContext,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> Vec<UserId> {
+    let bot_id = serenity_ctx.cache.current_user().id;
+    serenity_ctx
+        .cache
+        .guild(guild_id)
+        .as_ref()
+        .map(|g| {
+            g.voice_states
+                .values()
+                .filter(|vs| vs.channel_id == Some(channel_id) && vs.user_id != bot_id)
+                .map(|vs| vs.user_id)
+                .collect()
+        })
+        .unwrap_or_default()
+}
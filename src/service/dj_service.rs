@@ -0,0 +1,127 @@
+//! Per-guild DJ role. Once a guild sets one, the commands in its gated list
+//! are reserved for members holding the role (see `dj_checks`). Guilds
+//! without a role keep every command open to everyone.
+
+use crate::bot::{Database, MusicBotError};
+use serenity::all::{GuildId, RoleId};
+
+/// Commands that carry the DJ check and can therefore be gated. `volume`
+/// only gates overdrive (`!volume 200!`), not ordinary volume changes.
+/// `skip` isn't here: it always needs the DJ role or the current track's
+/// requester, and sends everyone else to a vote (see
+/// `dj_checks::may_skip_instantly`).
+pub const GATEABLE_COMMANDS: &[&str] = &[
+    "clear",
+    "stop",
+    "shuffle",
+    "volume",
+    "remove",
+    "playnow",
+    "playtop",
+    "loop",
+    "seek",
+    "forward",
+    "rewind",
+    "leave",
+    "crossfade",
+    "move",
+    "swap",
+    "dedupe",
+    "fairqueue",
+    "filter",
+    "eq",
+    "sponsorblock",
+];
+
+/// Gated list for guilds that never changed it.
+pub const DEFAULT_GATED_COMMANDS: &[&str] = &["clear", "stop", "shuffle", "volume", "remove", "playnow"];
+
+pub struct DjSettings {
+    pub role_id: Option<RoleId>,
+    pub gated_commands: Vec<String>,
+}
+
+impl DjSettings {
+    pub fn gates(
+        &self,
+        command: &str,
+    ) -> bool {
+        self.role_id.is_some() && self.gated_commands.iter().any(|c| c == command)
+    }
+}
+
+pub async fn load(
+    database: &Database,
+    guild_id: GuildId,
+) -> Result<DjSettings, MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+
+    let row = sqlx::query!(
+        "SELECT role_id, gated_commands FROM dj_settings WHERE guild_id = $1",
+        guild_id_map
+    )
+    .fetch_optional(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(match row {
+        Some(row) => DjSettings {
+            role_id: row.role_id.map(|id| RoleId::new(id as u64)),
+            gated_commands: row
+                .gated_commands
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+        },
+        None => DjSettings {
+            role_id: None,
+            gated_commands: DEFAULT_GATED_COMMANDS
+                .iter()
+                .map(|c| c.to_string())
+                .collect(),
+        },
+    })
+}
+
+/// Set (or with `None`, remove) the guild's DJ role.
+pub async fn set_role(
+    database: &Database,
+    guild_id: GuildId,
+    role_id: Option<RoleId>,
+) -> Result<(), MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+    let role_id_map: Option<i64> = role_id.map(|id| id.get() as i64);
+
+    sqlx::query!(
+        "INSERT INTO dj_settings (guild_id, role_id) VALUES ($1, $2) ON CONFLICT(guild_id) DO UPDATE SET role_id = excluded.role_id",
+        guild_id_map,
+        role_id_map
+    )
+    .execute(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Replace the guild's gated command list. Callers validate names against
+/// `GATEABLE_COMMANDS`.
+pub async fn set_gated_commands(
+    database: &Database,
+    guild_id: GuildId,
+    commands: &[String],
+) -> Result<(), MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+    let joined: String = commands.join(" ");
+
+    sqlx::query!(
+        "INSERT INTO dj_settings (guild_id, gated_commands) VALUES ($1, $2) ON CONFLICT(guild_id) DO UPDATE SET gated_commands = excluded.gated_commands",
+        guild_id_map,
+        joined
+    )
+    .execute(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(())
+}
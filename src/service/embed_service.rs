@@ -1,5 +1,5 @@
 use crate::bot::{Context, MusicBotError};
-use serenity::all::{ChannelId, Color, CreateEmbed, CreateMessage, GuildChannel, Http, Message, MessageId};
+use serenity::all::{ChannelId, Color, CreateActionRow, CreateEmbed, CreateMessage, GuildChannel, Http, Message, MessageId};
 use std::sync::Arc;
 
 pub fn create_embed(
@@ -19,10 +19,23 @@ pub async fn send_channel_embed(
     embed: CreateEmbed,
     delete_after: Option<u64>,
     message: Option<String>,
+) -> Result<Message, MusicBotError> {
+    send_channel_embed_with_components(http, channel, embed, Vec::new(), delete_after, message).await
+}
+
+/// `send_channel_embed` with message components (buttons) attached.
+pub async fn send_channel_embed_with_components(
+    http: Arc<Http>,
+    channel: &GuildChannel,
+    embed: CreateEmbed,
+    components: Vec<CreateActionRow>,
+    delete_after: Option<u64>,
+    message: Option<String>,
 ) -> Result<Message, MusicBotError> {
     let created_message = CreateMessage::default()
         .content(message.unwrap_or_default())
-        .embed(embed);
+        .embed(embed)
+        .components(components);
 
     let message = channel
         .send_message(http.clone(), created_message)
@@ -40,7 +53,50 @@ pub async fn send_context_embed(
     reply: bool,
     delete_after: Option<u64>,
 ) -> Result<Message, MusicBotError> {
-    let created_reply = poise::CreateReply::default().embed(embed).reply(reply);
+    send_context_embed_with_components(ctx, embed, Vec::new(), reply, delete_after).await
+}
+
+/// `send_context_embed` with message components (buttons) attached.
+pub async fn send_context_embed_with_components(
+    ctx: Context<'_>,
+    embed: CreateEmbed,
+    components: Vec<CreateActionRow>,
+    reply: bool,
+    delete_after: Option<u64>,
+) -> Result<Message, MusicBotError> {
+    let created_reply = poise::CreateReply::default()
+        .embed(embed)
+        .components(components)
+        .reply(reply);
+
+    let reply_handle = ctx
+        .send(created_reply)
+        .await
+        .map_err(|error| MusicBotError::InternalError(error.to_string()))?;
+
+    let message = reply_handle
+        .into_message()
+        .await
+        .map_err(|error| MusicBotError::InternalError(error.to_string()))?;
+
+    let http = ctx.serenity_context().http.clone();
+    process_message(http, &message, delete_after).await;
+
+    Ok(message)
+}
+
+/// Send several embeds in one reply, e.g. a confirmation followed by the
+/// state it changed.
+pub async fn send_context_embeds(
+    ctx: Context<'_>,
+    embeds: Vec<CreateEmbed>,
+    reply: bool,
+    delete_after: Option<u64>,
+) -> Result<Message, MusicBotError> {
+    let mut created_reply = poise::CreateReply::default().reply(reply);
+    for embed in embeds {
+        created_reply = created_reply.embed(embed);
+    }
 
     let reply_handle = ctx
         .send(created_reply)
@@ -0,0 +1,532 @@
+//! Audio effects applied by re-rendering a track's file through an ffmpeg
+//! filter chain before it's handed to songbird: the session's filters and
+//! the guild's equalizer. Renders are kept under `cache/filtered/`, one per
+//! source file and effect setting, so replaying a track (or toggling back to
+//! a previous setting) doesn't render it again. Only the newest
+//! `MAX_RENDERS` files are kept.
+//!
+//! Streamed tracks have no file to render, so with an effect on the track is
+//! downloaded into the regular cache first; long-form tracks, which never
+//! land in the cache, play without effects. A track whose render isn't ready
+//! starts unfiltered and switches over once it is. Loudness normalization keeps
+//! measuring the unfiltered source, and its gain is applied on top.
+
+use crate::player::track::{Track, TrackSource};
+use crate::service::cache_service;
+use crate::utils::lock_utils::KeyedLocks;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::process::Command;
+
+const FILTERED_SUBDIR: &str = "filtered";
+const SAMPLE_RATE: u32 = 48_000;
+/// How many renders are kept on disk before the oldest are deleted.
+const MAX_RENDERS: usize = 50;
+
+pub const MIN_SPEED: f32 = 0.5;
+pub const MAX_SPEED: f32 = 2.0;
+/// Pitch shift range, in semitones either way.
+pub const MAX_PITCH_SEMITONES: f32 = 12.0;
+
+/// Centre frequencies of the equalizer bands, in Hz, an octave apart.
+pub const EQ_BANDS: [u32; 10] = [31, 62, 125, 250, 500, 1000, 2000, 4000, 8000, 16000];
+/// Equalizer gain range, in dB either way.
+pub const MAX_EQ_GAIN: f32 = 12.0;
+
+/// One lock per render target, so the prefetch and a starting track never
+/// write the same file at once.
+static IN_FLIGHT: KeyedLocks<PathBuf> = KeyedLocks::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterPreset {
+    Nightcore,
+    Vaporwave,
+    BassBoost,
+    EightD,
+    Karaoke,
+}
+
+impl FilterPreset {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "nightcore" | "nc" => Some(FilterPreset::Nightcore),
+            "vaporwave" | "vw" => Some(FilterPreset::Vaporwave),
+            "bassboost" | "bass" => Some(FilterPreset::BassBoost),
+            "8d" | "eightd" | "rotate" => Some(FilterPreset::EightD),
+            "karaoke" | "vocalcut" | "novocals" => Some(FilterPreset::Karaoke),
+            _ => None,
+        }
+    }
+}
+
+/// The effects applied to every track played while they're set. The default
+/// value plays tracks untouched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioFilters {
+    /// Playback rate; `2.0` plays twice as fast.
+    pub speed: f32,
+    /// Pitch as a frequency ratio, independent of `speed`; `2.0` is an
+    /// octave up.
+    pub pitch: f32,
+    pub bass_boost: bool,
+    /// Pans the audio slowly around the listener.
+    pub eight_d: bool,
+    /// Cancels what's mixed dead centre, which is usually the vocals.
+    pub karaoke: bool,
+}
+
+impl Default for AudioFilters {
+    fn default() -> Self {
+        AudioFilters {
+            speed: 1.0,
+            pitch: 1.0,
+            bass_boost: false,
+            eight_d: false,
+            karaoke: false,
+        }
+    }
+}
+
+impl AudioFilters {
+    pub fn is_active(&self) -> bool {
+        *self != AudioFilters::default()
+    }
+
+    /// Nightcore and vaporwave replace speed and pitch; the others toggle
+    /// their effect on top of whatever is set.
+    pub fn apply_preset(
+        &mut self,
+        preset: FilterPreset,
+    ) {
+        match preset {
+            FilterPreset::Nightcore => {
+                self.speed = 1.25;
+                self.pitch = 1.25;
+            }
+            FilterPreset::Vaporwave => {
+                self.speed = 0.8;
+                self.pitch = 0.8;
+            }
+            FilterPreset::BassBoost => self.bass_boost = !self.bass_boost,
+            FilterPreset::EightD => self.eight_d = !self.eight_d,
+            FilterPreset::Karaoke => self.karaoke = !self.karaoke,
+        }
+    }
+
+    pub fn pitch_semitones(&self) -> f32 {
+        12.0 * self.pitch.log2()
+    }
+
+    pub fn set_pitch_semitones(
+        &mut self,
+        semitones: f32,
+    ) {
+        self.pitch = 2f32.powf(semitones / 12.0);
+    }
+
+    /// Append the filters to an ffmpeg `-af` chain. Pitch is shifted by
+    /// resampling (which also changes speed) and `atempo` then corrects the
+    /// tempo to `speed`.
+    fn push_chain(
+        &self,
+        chain: &mut Vec<String>,
+    ) {
+        if self.karaoke {
+            chain.push("pan=stereo|c0=c0-c1|c1=c1-c0".to_string());
+        }
+        if (self.pitch - 1.0).abs() > f32::EPSILON {
+            chain.push(format!("asetrate={:.0}", SAMPLE_RATE as f32 * self.pitch));
+            chain.push(format!("aresample={SAMPLE_RATE}"));
+        }
+
+        // A single atempo only takes factors between 0.5 and 2.
+        let mut tempo = self.speed / self.pitch;
+        while tempo > 2.0 {
+            chain.push("atempo=2.0".to_string());
+            tempo /= 2.0;
+        }
+        while tempo < 0.5 {
+            chain.push("atempo=0.5".to_string());
+            tempo /= 0.5;
+        }
+        if (tempo - 1.0).abs() > 0.001 {
+            chain.push(format!("atempo={tempo:.4}"));
+        }
+
+        if self.bass_boost {
+            chain.push("bass=g=10:f=110:w=0.6".to_string());
+        }
+        if self.eight_d {
+            chain.push("apulsator=hz=0.125:amount=0.9".to_string());
+        }
+    }
+}
+
+impl fmt::Display for AudioFilters {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let mut parts: Vec<String> = Vec::new();
+        if (self.speed - 1.0).abs() > f32::EPSILON {
+            parts.push(format!("speed ×{:.2}", self.speed));
+        }
+        if (self.pitch - 1.0).abs() > f32::EPSILON {
+            parts.push(format!("pitch {:+.1} semitones", self.pitch_semitones()));
+        }
+        if self.bass_boost {
+            parts.push("bass boost".to_string());
+        }
+        if self.eight_d {
+            parts.push("8D".to_string());
+        }
+        if self.karaoke {
+            parts.push("karaoke".to_string());
+        }
+
+        if parts.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", parts.join(", "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EqPreset {
+    Flat,
+    Bass,
+    Vocal,
+    Treble,
+}
+
+impl EqPreset {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "flat" | "off" | "none" => Some(EqPreset::Flat),
+            "bass" | "bassboost" => Some(EqPreset::Bass),
+            "vocal" | "vocals" | "voice" => Some(EqPreset::Vocal),
+            "treble" | "bright" => Some(EqPreset::Treble),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EqPreset::Flat => "flat",
+            EqPreset::Bass => "bass",
+            EqPreset::Vocal => "vocal",
+            EqPreset::Treble => "treble",
+        }
+    }
+
+    fn gains(&self) -> [f32; 10] {
+        match self {
+            EqPreset::Flat => [0.0; 10],
+            EqPreset::Bass => [6.0, 5.0, 4.0, 2.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+            EqPreset::Vocal => [-2.0, -2.0, -1.0, 1.0, 3.0, 4.0, 3.0, 1.0, 0.0, -1.0],
+            EqPreset::Treble => [0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 2.0, 4.0, 5.0, 6.0],
+        }
+    }
+}
+
+/// A guild's equalizer: one gain per band in `EQ_BANDS`, in dB. The default
+/// is flat, which plays tracks untouched.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Equalizer {
+    pub gains: [f32; 10],
+}
+
+impl Equalizer {
+    pub fn from_preset(preset: EqPreset) -> Self {
+        Equalizer { gains: preset.gains() }
+    }
+
+    /// Read the `guilds.eq_gains` column; anything malformed reads as flat.
+    pub fn from_db(value: Option<&str>) -> Self {
+        let gains: Vec<f32> = value
+            .unwrap_or_default()
+            .split_whitespace()
+            .filter_map(|gain| gain.parse().ok())
+            .collect();
+        match <[f32; 10]>::try_from(gains) {
+            Ok(gains) => Equalizer {
+                gains: gains.map(|gain| gain.clamp(-MAX_EQ_GAIN, MAX_EQ_GAIN)),
+            },
+            Err(_) => Equalizer::default(),
+        }
+    }
+
+    /// The value stored in `guilds.eq_gains`; `None` when flat.
+    pub fn to_db(&self) -> Option<String> {
+        if self.is_flat() {
+            return None;
+        }
+        let gains: Vec<String> = self.gains.iter().map(|gain| format!("{gain:.1}")).collect();
+        Some(gains.join(" "))
+    }
+
+    pub fn is_flat(&self) -> bool {
+        self.gains.iter().all(|gain| gain.abs() < 0.05)
+    }
+
+    /// The preset these gains match, if any.
+    pub fn preset(&self) -> Option<EqPreset> {
+        [EqPreset::Flat, EqPreset::Bass, EqPreset::Vocal, EqPreset::Treble]
+            .into_iter()
+            .find(|preset| preset.gains() == self.gains)
+    }
+
+    /// Which band `name` means: its number (`1`-`10`) or its frequency, e.g.
+    /// `62`, `1k` or `16khz`.
+    pub fn band_index(name: &str) -> Option<usize> {
+        let name = name.trim().to_ascii_lowercase();
+        let name = name.trim_end_matches("hz");
+        let hz: f32 = match name.strip_suffix('k') {
+            Some(khz) => khz.parse::<f32>().ok()? * 1000.0,
+            None => name.parse().ok()?,
+        };
+
+        if (1.0..=EQ_BANDS.len() as f32).contains(&hz) && hz.fract() == 0.0 {
+            return Some(hz as usize - 1);
+        }
+        // Let "1k" and "16k" find 1000 and 16000 but also "60" find 62.
+        EQ_BANDS
+            .iter()
+            .position(|band| (*band as f32 / hz - 1.0).abs() < 0.1)
+    }
+
+    pub fn set_band(
+        &mut self,
+        index: usize,
+        gain: f32,
+    ) {
+        if let Some(band) = self.gains.get_mut(index) {
+            *band = gain.clamp(-MAX_EQ_GAIN, MAX_EQ_GAIN);
+        }
+    }
+
+    /// Append one octave-wide peaking filter per band that isn't at 0 dB.
+    fn push_chain(
+        &self,
+        chain: &mut Vec<String>,
+    ) {
+        for (hz, gain) in EQ_BANDS.iter().zip(self.gains) {
+            if gain.abs() >= 0.05 {
+                chain.push(format!("equalizer=f={hz}:t=o:w=1:g={gain:.1}"));
+            }
+        }
+    }
+}
+
+impl fmt::Display for Equalizer {
+    fn fmt(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        match self.preset() {
+            Some(preset) => write!(f, "{}", preset.name()),
+            None => write!(f, "custom"),
+        }
+    }
+}
+
+/// Whether tracks have to be rendered before they're played.
+pub fn is_active(
+    filters: &AudioFilters,
+    equalizer: &Equalizer,
+) -> bool {
+    filters.is_active() || !equalizer.is_flat()
+}
+
+/// The ffmpeg `-af` chain for the filters and the equalizer. The equalizer
+/// runs first, on the untouched audio.
+fn ffmpeg_chain(
+    filters: &AudioFilters,
+    equalizer: &Equalizer,
+) -> String {
+    let mut chain: Vec<String> = vec!["aformat=channel_layouts=stereo".to_string(), format!("aresample={SAMPLE_RATE}")];
+    equalizer.push_chain(&mut chain);
+    filters.push_chain(&mut chain);
+    // Boosting can push peaks past full scale.
+    chain.push("alimiter=limit=0.95".to_string());
+
+    chain.join(",")
+}
+
+/// A track rendered with the current filters and equalizer.
+pub struct Rendered {
+    /// The unfiltered file the render was made from — what normalization
+    /// measures.
+    pub source: PathBuf,
+    /// The filtered file to play.
+    pub path: PathBuf,
+}
+
+/// The render of `track` with `filters` and `equalizer`, when one is already
+/// on disk. Never downloads or renders, so it's cheap enough to call with
+/// the player locked.
+pub async fn find_render(
+    track: &Track,
+    filters: &AudioFilters,
+    equalizer: &Equalizer,
+) -> Option<Rendered> {
+    let source = match &track.source {
+        TrackSource::Local(path) => path.clone(),
+        _ => cache_service::find_cached(track).await?,
+    };
+    let path = render_path(&source, &ffmpeg_chain(filters, equalizer));
+    tokio::fs::try_exists(&path)
+        .await
+        .unwrap_or(false)
+        .then_some(Rendered { source, path })
+}
+
+/// Render `track` with `filters` and `equalizer`, downloading it into the
+/// cache first when it's streamed. `None` when there's no file to work from
+/// (long-form or uncacheable tracks) or ffmpeg failed — the caller plays it
+/// unfiltered. This can take as long as the download and a full ffmpeg pass,
+/// so it never runs with the player locked.
+pub async fn render_track(
+    track: &Track,
+    filters: &AudioFilters,
+    equalizer: &Equalizer,
+) -> Option<Rendered> {
+    let source = match &track.source {
+        TrackSource::Local(path) => path.clone(),
+        _ => match cache_service::find_cached(track).await {
+            Some(path) => path,
+            None if cache_service::is_cacheable(track) && !track.is_known_long_form() => match cache_service::cache_track(track).await {
+                Ok(path) => path,
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to cache '{}' for filtering: {}",
+                        track.metadata.title,
+                        e
+                    );
+                    return None;
+                }
+            },
+            None => return None,
+        },
+    };
+
+    match render(&source, &ffmpeg_chain(filters, equalizer)).await {
+        Ok(path) => Some(Rendered { source, path }),
+        Err(e) => {
+            tracing::warn!(
+                "Failed to apply filters to '{}': {}",
+                track.metadata.title,
+                e
+            );
+            None
+        }
+    }
+}
+
+/// Where the render of `source` through `chain` lives.
+fn render_path(
+    source: &Path,
+    chain: &str,
+) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+    source.hash(&mut hasher);
+    chain.hash(&mut hasher);
+
+    let stem = source
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("track");
+    let stem: String = stem.chars().take(60).collect();
+
+    cache_service::cache_dir()
+        .join(FILTERED_SUBDIR)
+        .join(format!("{stem}_{:016x}.flac", hasher.finish()))
+}
+
+/// Render `source` through the filter chain, reusing an earlier render.
+async fn render(
+    source: &Path,
+    chain: &str,
+) -> std::io::Result<PathBuf> {
+    let target = render_path(source, chain);
+
+    let in_flight = IN_FLIGHT.join(target.clone());
+    let _guard = in_flight.lock().await;
+    render_to(source, chain, &target).await
+}
+
+async fn render_to(
+    source: &Path,
+    chain: &str,
+    target: &Path,
+) -> std::io::Result<PathBuf> {
+    if tokio::fs::try_exists(target).await.unwrap_or(false) {
+        return Ok(target.to_path_buf());
+    }
+
+    let dir = target.parent().unwrap_or(Path::new("."));
+    tokio::fs::create_dir_all(dir).await?;
+
+    // Render to a `.part` file so an interrupted run is never played.
+    let part = target.with_extension("part.flac");
+    tracing::info!("Rendering {} with filters: {}", source.display(), chain);
+
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-nostats", "-nostdin", "-y", "-i"])
+        .arg(source)
+        .args(["-vn", "-af", chain, "-ar", &SAMPLE_RATE.to_string(), "-c:a", "flac"])
+        .arg(&part)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        let _ = tokio::fs::remove_file(&part).await;
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let tail = stderr.lines().last().unwrap_or_default().to_string();
+        return Err(std::io::Error::other(format!(
+            "ffmpeg failed ({}): {}",
+            output.status, tail
+        )));
+    }
+
+    tokio::fs::rename(&part, target).await?;
+    prune(dir).await;
+    Ok(target.to_path_buf())
+}
+
+/// Delete all but the newest `MAX_RENDERS` finished renders.
+async fn prune(dir: &Path) {
+    let Ok(mut read_dir) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+
+    let mut renders: Vec<(std::time::SystemTime, PathBuf)> = Vec::new();
+    while let Ok(Some(entry)) = read_dir.next_entry().await {
+        let path = entry.path();
+        let finished = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.ends_with(".flac") && !n.ends_with(".part.flac"));
+        if !finished {
+            continue;
+        }
+        if let Ok(modified) = entry.metadata().await.and_then(|m| m.modified()) {
+            renders.push((modified, path));
+        }
+    }
+
+    if renders.len() <= MAX_RENDERS {
+        return;
+    }
+    renders.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    for (_, path) in renders.into_iter().skip(MAX_RENDERS) {
+        let _ = tokio::fs::remove_file(path).await;
+    }
+}
@@ -0,0 +1,171 @@
+//! Persistent play history. Every started track gets a `play_history` row,
+//! closed when it ends with how long it played and whether it finished or
+//! was skipped. `stats` aggregates over it.
+
+use crate::bot::{Database, MusicBotError};
+use crate::player::track::Track;
+use serenity::all::{GuildId, UserId};
+use std::time::Duration;
+
+/// How long ago a `stats` window starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsPeriod {
+    Week,
+    Month,
+    AllTime,
+}
+
+impl StatsPeriod {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "the last 7 days",
+            StatsPeriod::Month => "the last 30 days",
+            StatsPeriod::AllTime => "all time",
+        }
+    }
+
+    /// SQLite `datetime('now', …)` modifier for the start of the window.
+    fn modifier(&self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "-7 days",
+            StatsPeriod::Month => "-30 days",
+            StatsPeriod::AllTime => "-100 years",
+        }
+    }
+}
+
+pub struct TrackCount {
+    pub title: String,
+    pub count: i64,
+}
+
+pub struct RequesterCount {
+    pub user_id: UserId,
+    pub count: i64,
+}
+
+pub struct GuildStats {
+    pub plays: i64,
+    pub listened: Duration,
+    pub top_tracks: Vec<TrackCount>,
+    pub top_requesters: Vec<RequesterCount>,
+    pub most_skipped: Vec<TrackCount>,
+}
+
+/// How many entries each `stats` ranking shows.
+const TOP_N: i64 = 5;
+
+/// Open a history row for a track that just started. Returns its id for
+/// `record_end`.
+pub async fn record_start(
+    database: &Database,
+    guild_id: GuildId,
+    track: &Track,
+) -> Result<i64, MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+    let source: String = track.source.kind();
+    let requester_id: Option<i64> = track.added_by.id.map(|id| id.get() as i64);
+
+    let row = sqlx::query!(
+        "INSERT INTO play_history (guild_id, track_id, source, title, requester_id, requester_name) VALUES ($1, $2, $3, $4, $5, $6) RETURNING id",
+        guild_id_map,
+        track.id,
+        source,
+        track.metadata.title,
+        requester_id,
+        track.added_by.name
+    )
+    .fetch_one(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(row.id)
+}
+
+/// Close the history row `entry_id` once its track stopped playing.
+pub async fn record_end(
+    database: &Database,
+    entry_id: i64,
+    listened: Duration,
+    skipped: bool,
+) -> Result<(), MusicBotError> {
+    let listened_ms: i64 = listened.as_millis() as i64;
+    let outcome: &str = if skipped { "skipped" } else { "finished" };
+
+    sqlx::query!(
+        "UPDATE play_history SET listened_ms = $1, outcome = $2 WHERE id = $3",
+        listened_ms,
+        outcome,
+        entry_id
+    )
+    .execute(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Aggregate the guild's history over `period`.
+pub async fn stats(
+    database: &Database,
+    guild_id: GuildId,
+    period: StatsPeriod,
+) -> Result<GuildStats, MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+    let since: &str = period.modifier();
+
+    let totals = sqlx::query!(
+        r#"SELECT COUNT(*) AS "plays!: i64", COALESCE(SUM(listened_ms), 0) AS "listened_ms!: i64" FROM play_history WHERE guild_id = $1 AND started_at >= datetime('now', $2)"#,
+        guild_id_map,
+        since
+    )
+    .fetch_one(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    let top_tracks = sqlx::query_as!(
+        TrackCount,
+        r#"SELECT MAX(title) AS "title!: String", COUNT(*) AS "count!: i64" FROM play_history WHERE guild_id = $1 AND started_at >= datetime('now', $2) GROUP BY track_id ORDER BY 2 DESC LIMIT $3"#,
+        guild_id_map,
+        since,
+        TOP_N
+    )
+    .fetch_all(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    let top_requesters = sqlx::query!(
+        r#"SELECT requester_id AS "requester_id!: i64", COUNT(*) AS "count!: i64" FROM play_history WHERE guild_id = $1 AND started_at >= datetime('now', $2) AND requester_id IS NOT NULL GROUP BY requester_id ORDER BY 2 DESC LIMIT $3"#,
+        guild_id_map,
+        since,
+        TOP_N
+    )
+    .fetch_all(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?
+    .into_iter()
+    .map(|row| RequesterCount {
+        user_id: UserId::new(row.requester_id as u64),
+        count: row.count,
+    })
+    .collect();
+
+    let most_skipped = sqlx::query_as!(
+        TrackCount,
+        r#"SELECT MAX(title) AS "title!: String", COUNT(*) AS "count!: i64" FROM play_history WHERE guild_id = $1 AND started_at >= datetime('now', $2) AND outcome = 'skipped' GROUP BY track_id ORDER BY 2 DESC LIMIT $3"#,
+        guild_id_map,
+        since,
+        TOP_N
+    )
+    .fetch_all(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(GuildStats {
+        plays: totals.plays,
+        listened: Duration::from_millis(totals.listened_ms.max(0) as u64),
+        top_tracks,
+        top_requesters,
+        most_skipped,
+    })
+}
@@ -143,6 +143,29 @@ pub async fn measurement_for(path: &Path) -> Measurement {
     }
 }
 
+/// Like `measurement_for`, but only recalls a measurement from memory or the
+/// sidecar and never runs ffmpeg, so it's quick enough to call while a track
+/// is being started. `None` when the file hasn't been measured yet.
+pub async fn known_measurement(path: &Path) -> Option<Measurement> {
+    let key = path.to_string_lossy().to_string();
+
+    let lufs = match cache_get(&key) {
+        Some(lufs) => lufs,
+        None => {
+            let lufs = read_sidecar(path).await?;
+            cache_set(key, lufs);
+            lufs
+        }
+    };
+
+    let gain_db = lufs_to_gain_db(lufs);
+    Some(Measurement {
+        lufs: Some(lufs),
+        gain_db,
+        multiplier: gain_to_multiplier(gain_db),
+    })
+}
+
 /// Measure-or-recall the gain offset (in dB) for `path`. Tries the memory
 /// cache, then the on-disk sidecar, then falls back to running ffmpeg. The
 /// raw LUFS measurement is persisted; the gain is derived on read from the
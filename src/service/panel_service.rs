@@ -0,0 +1,510 @@
+//! The Now Playing control panel: one message per guild that follows the
+//! playback session. It's posted when the first track starts, edited in
+//! place on every track change and every few seconds in between to move its
+//! progress bar, and deleted when playback stops or goes idle. Its buttons
+//! drive the player under the same voice-channel and DJ rules as the
+//! matching commands.
+
+use crate::bot::{Context, MusicBotError};
+use crate::checks::dj_checks;
+use crate::embeds::bot::bot_embeds::BotEmbed;
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::playback::Playback;
+use crate::player::player::{self, LoopMode, Player};
+use crate::service::embed_service::{self, SendEmbed};
+use crate::service::interaction_service::DeferredInteractionStream;
+use crate::service::vote_skip_service::VoteOutcome;
+use crate::service::{channel_service, vote_skip_service};
+use serenity::all::{ButtonStyle, ChannelId, ComponentInteraction, CreateActionRow, CreateButton, CreateEmbed, CreateInteractionResponseFollowup, EditMessage, GuildId, Http, MessageId, RoleId};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+/// How often the progress bar moves when nobody clicks.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(10);
+/// Percentage points one volume click changes.
+const VOLUME_STEP: f32 = 10.0;
+/// The volume buttons stop here; overdrive stays a `!volume` thing.
+const MAX_BUTTON_VOLUME: f32 = 100.0;
+
+const BTN_BACK: &str = "panel_back";
+const BTN_PAUSE: &str = "panel_pause";
+const BTN_SKIP: &str = "panel_skip";
+const BTN_VOTE_SKIP: &str = "panel_vote_skip";
+const BTN_STOP: &str = "panel_stop";
+const BTN_LOOP: &str = "panel_loop";
+const BTN_SHUFFLE: &str = "panel_shuffle";
+const BTN_VOLUME_DOWN: &str = "panel_volume_down";
+const BTN_VOLUME_UP: &str = "panel_volume_up";
+
+static PANELS: OnceLock<Mutex<HashMap<GuildId, ControlPanel>>> = OnceLock::new();
+
+fn panels() -> &'static Mutex<HashMap<GuildId, ControlPanel>> {
+    PANELS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+struct ControlPanel {
+    channel_id: ChannelId,
+    message_id: MessageId,
+    http: Arc<Http>,
+    /// Set once the panel is replaced or closed; its task stops at its next
+    /// wake-up.
+    closed: Arc<AtomicBool>,
+}
+
+fn buttons(paused: bool) -> Vec<CreateActionRow> {
+    let pause = if paused {
+        CreateButton::new(BTN_PAUSE)
+            .label("▶ Resume")
+            .style(ButtonStyle::Success)
+    } else {
+        CreateButton::new(BTN_PAUSE)
+            .label("⏸ Pause")
+            .style(ButtonStyle::Primary)
+    };
+
+    vec![
+        CreateActionRow::Buttons(vec![
+            CreateButton::new(BTN_BACK)
+                .label("⏮ Back")
+                .style(ButtonStyle::Secondary),
+            pause,
+            CreateButton::new(BTN_SKIP)
+                .label("⏭ Skip")
+                .style(ButtonStyle::Secondary),
+            CreateButton::new(BTN_VOTE_SKIP)
+                .label("🗳 Vote skip")
+                .style(ButtonStyle::Secondary),
+            CreateButton::new(BTN_STOP)
+                .label("⏹ Stop")
+                .style(ButtonStyle::Danger),
+        ]),
+        CreateActionRow::Buttons(vec![
+            CreateButton::new(BTN_LOOP)
+                .label("🔁 Loop")
+                .style(ButtonStyle::Secondary),
+            CreateButton::new(BTN_SHUFFLE)
+                .label("🔀 Shuffle")
+                .style(ButtonStyle::Secondary),
+            CreateButton::new(BTN_VOLUME_DOWN)
+                .label("🔉 Vol −")
+                .style(ButtonStyle::Secondary),
+            CreateButton::new(BTN_VOLUME_UP)
+                .label("🔊 Vol +")
+                .style(ButtonStyle::Secondary),
+        ]),
+    ]
+}
+
+/// The panel for `player`'s current track as it stands, `None` when nothing
+/// is playing.
+async fn contents(player: &Player) -> Option<CreateEmbed> {
+    let track = player.current_track.as_ref()?;
+    // Show the track's own time, not the input's.
+    let position = match &player.track_handle {
+        Some(handle) => handle
+            .get_info()
+            .await
+            .map(|state| player.track_position(state.position))
+            .unwrap_or_default(),
+        None => Duration::ZERO,
+    };
+
+    Some(
+        PlayerEmbed::ControlPanel {
+            track,
+            position,
+            paused: player.is_paused,
+            looping: player.looping,
+            volume: player.volume,
+            queued: player.queue.len(),
+        }
+        .to_embed(),
+    )
+}
+
+/// Show `embed` (a freshly started track) on the guild's panel. A panel in
+/// the playback channel is edited in place; otherwise a new one is posted.
+/// A slash command has to be answered, so when `ctx` is one its reply
+/// becomes the panel.
+pub async fn show(
+    playback: &Playback,
+    embed: CreateEmbed,
+    ctx: Option<Context<'_>>,
+) -> Result<(), MusicBotError> {
+    let http = playback.serenity_ctx.http.clone();
+
+    let existing = panels()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&playback.guild_id)
+        .map(|panel| (panel.channel_id, panel.message_id));
+    let is_slash = matches!(ctx, Some(poise::Context::Application(_)));
+
+    if let Some((channel_id, message_id)) = existing {
+        if channel_id == playback.text_channel.id && !is_slash {
+            let edit = EditMessage::new()
+                .embed(embed.clone())
+                .components(buttons(false));
+            match channel_id.edit_message(&http, message_id, edit).await {
+                Ok(_) => return Ok(()),
+                Err(e) => tracing::debug!("Control panel is gone, posting a new one: {:?}", e),
+            }
+        }
+    }
+
+    close(playback.guild_id);
+
+    let message = match ctx {
+        Some(ctx) => embed_service::send_context_embed_with_components(ctx, embed, buttons(false), false, None).await?,
+        None => {
+            embed_service::send_channel_embed_with_components(
+                http.clone(),
+                &playback.text_channel,
+                embed,
+                buttons(false),
+                None,
+                None,
+            )
+            .await?
+        }
+    };
+
+    let closed = Arc::new(AtomicBool::new(false));
+    panels().lock().unwrap_or_else(|e| e.into_inner()).insert(
+        playback.guild_id,
+        ControlPanel {
+            channel_id: message.channel_id,
+            message_id: message.id,
+            http,
+            closed: Arc::clone(&closed),
+        },
+    );
+
+    tokio::spawn(run(
+        playback.clone(),
+        message.channel_id,
+        message.id,
+        closed,
+    ));
+
+    Ok(())
+}
+
+/// Redraw the guild's panel from `player` now rather than at its next
+/// refresh, e.g. when a radio station moves on to another song.
+pub async fn refresh(
+    guild_id: GuildId,
+    player: &Player,
+) {
+    let Some((channel_id, message_id, http)) = panels()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&guild_id)
+        .map(|panel| (panel.channel_id, panel.message_id, panel.http.clone()))
+    else {
+        return;
+    };
+    let Some(embed) = contents(player).await else {
+        return;
+    };
+
+    let edit = EditMessage::new()
+        .embed(embed)
+        .components(buttons(player.is_paused));
+    if let Err(e) = channel_id.edit_message(&http, message_id, edit).await {
+        tracing::debug!("Failed to refresh control panel: {:?}", e);
+    }
+}
+
+/// Take down the guild's panel, if it has one.
+pub fn close(guild_id: GuildId) {
+    let Some(panel) = panels()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(&guild_id)
+    else {
+        return;
+    };
+
+    panel.closed.store(true, Ordering::Relaxed);
+    tokio::spawn(async move {
+        let _ = panel
+            .http
+            .delete_message(panel.channel_id, panel.message_id, Some("Playback ended"))
+            .await;
+    });
+}
+
+/// Handle the panel's clicks and keep its progress bar moving until it's
+/// closed.
+async fn run(
+    playback: Playback,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    closed: Arc<AtomicBool>,
+) {
+    let http = playback.serenity_ctx.http.clone();
+    let mut stream = DeferredInteractionStream::new(&playback.serenity_ctx, message_id);
+
+    loop {
+        let interaction = stream.next_within(REFRESH_INTERVAL).await;
+        if closed.load(Ordering::Relaxed) {
+            return;
+        }
+
+        if let Some(interaction) = interaction {
+            if let Err(e) = handle_click(&playback, &interaction).await {
+                let _ = interaction
+                    .create_followup(
+                        &http,
+                        CreateInteractionResponseFollowup::new()
+                            .content(e.to_string())
+                            .ephemeral(true),
+                    )
+                    .await;
+            }
+            if closed.load(Ordering::Relaxed) {
+                return;
+            }
+        }
+
+        let (embed, paused) = {
+            let player = playback.player.read().await;
+            (contents(&player).await, player.is_paused)
+        };
+        // Between tracks; the next one updates the panel itself.
+        let Some(embed) = embed else {
+            continue;
+        };
+
+        let edit = EditMessage::new().embed(embed).components(buttons(paused));
+        if let Err(e) = channel_id.edit_message(&http, message_id, edit).await {
+            tracing::debug!("Failed to refresh control panel: {:?}", e);
+        }
+    }
+}
+
+/// Cast the clicker's vote to skip and tell them how it went.
+async fn vote_skip(
+    playback: &Playback,
+    interaction: &ComponentInteraction,
+) -> Result<(), MusicBotError> {
+    let serenity_ctx = &playback.serenity_ctx;
+    let outcome = vote_skip_service::cast_vote(
+        serenity_ctx,
+        &playback.player,
+        playback.guild_id,
+        &interaction.user,
+    )
+    .await?;
+    // Only a skip is everyone's business; the rest goes to the voter alone.
+    let public = matches!(
+        outcome,
+        VoteOutcome::Passed { .. } | VoteOutcome::RequesterSkipped
+    );
+    reply(
+        serenity_ctx,
+        interaction,
+        PlayerEmbed::VoteSkip(&outcome).to_embed(),
+        !public,
+    )
+    .await
+}
+
+async fn handle_click(
+    playback: &Playback,
+    interaction: &ComponentInteraction,
+) -> Result<(), MusicBotError> {
+    let serenity_ctx = &playback.serenity_ctx;
+    let guild_id = playback.guild_id;
+    let user_id = interaction.user.id;
+
+    // Same rule as `check_author_in_same_voice_channel`.
+    let user_channel: Option<ChannelId> = serenity_ctx
+        .cache
+        .guild(guild_id)
+        .as_ref()
+        .and_then(|g| g.voice_states.get(&user_id))
+        .and_then(|vs| vs.channel_id);
+    match (
+        user_channel,
+        channel_service::bot_voice_channel(serenity_ctx, guild_id),
+    ) {
+        (Some(user_channel), Some(bot_channel)) if user_channel == bot_channel => {}
+        (Some(_), Some(bot_channel)) => {
+            return reply(
+                serenity_ctx,
+                interaction,
+                BotEmbed::CurrentUserNotInSharedChannel(&bot_channel).to_embed(),
+                true,
+            )
+            .await;
+        }
+        _ => {
+            return reply(
+                serenity_ctx,
+                interaction,
+                BotEmbed::CurrentUserNotInVoiceChannel.to_embed(),
+                true,
+            )
+            .await;
+        }
+    }
+
+    // Skip and back follow the `skip` command: only the DJ or the track's
+    // requester moves on outright. Anyone else's skip counts as a vote, and
+    // going back is refused.
+    let custom_id = interaction.data.custom_id.as_str();
+    if custom_id == BTN_SKIP || custom_id == BTN_BACK {
+        let database = playback.player.read().await.database();
+        let roles: &[RoleId] = interaction
+            .member
+            .as_ref()
+            .map(|member| member.roles.as_slice())
+            .unwrap_or_default();
+        let instant = dj_checks::may_skip_instantly(
+            serenity_ctx,
+            &database,
+            &playback.player,
+            guild_id,
+            user_id,
+            roles,
+        )
+        .await?;
+        if !instant && custom_id == BTN_BACK {
+            return reply(
+                serenity_ctx,
+                interaction,
+                BotEmbed::BackNotAllowed.to_embed(),
+                true,
+            )
+            .await;
+        }
+        if !instant {
+            return vote_skip(playback, interaction).await;
+        }
+    }
+
+    // The command each button stands in for, under the guild's DJ settings.
+    // Volume only gates overdrive, which the buttons never reach.
+    let command = match interaction.data.custom_id.as_str() {
+        BTN_STOP => Some("stop"),
+        BTN_LOOP => Some("loop"),
+        BTN_SHUFFLE => Some("shuffle"),
+        _ => None,
+    };
+    if let Some(command) = command {
+        let database = playback.player.read().await.database();
+        if let Some(role_id) = dj_checks::gating_role(&database, guild_id, command).await? {
+            let has_role = interaction
+                .member
+                .as_ref()
+                .is_some_and(|member| member.roles.contains(&role_id));
+            if !has_role && !dj_checks::is_exempt(serenity_ctx, &playback.player, guild_id, user_id).await {
+                return reply(
+                    serenity_ctx,
+                    interaction,
+                    BotEmbed::DjRoleRequired(&role_id).to_embed(),
+                    true,
+                )
+                .await;
+            }
+        }
+    }
+
+    match interaction.data.custom_id.as_str() {
+        BTN_PAUSE => {
+            let mut player = playback.player.write().await;
+            if player.is_paused {
+                player.resume().await?;
+            } else {
+                player.pause().await?;
+            }
+        }
+        BTN_SKIP => {
+            playback.player.write().await.skip(1).await?;
+        }
+        BTN_BACK => {
+            playback.player.write().await.back().await?;
+        }
+        BTN_VOTE_SKIP => {
+            return vote_skip(playback, interaction).await;
+        }
+        BTN_STOP => {
+            let mut player = playback.player.write().await;
+            // Like `!stop`: the End handler won't start the inactivity
+            // timer for a track stopped on purpose.
+            player.inactivity_cancel.store(false, Ordering::Relaxed);
+            let cancel = Arc::clone(&player.inactivity_cancel);
+            player.stop_playback().await?;
+            player::set_idle(serenity_ctx, guild_id);
+            drop(player);
+
+            player::spawn_inactivity_leave(
+                serenity_ctx.clone(),
+                playback.player.clone(),
+                guild_id,
+                playback.text_channel.clone(),
+                cancel,
+            );
+            PlayerEmbed::Stopped
+                .to_embed()
+                .send_channel(
+                    serenity_ctx.http.clone(),
+                    &playback.text_channel,
+                    Some(30),
+                    None,
+                )
+                .await?;
+        }
+        BTN_LOOP => {
+            let mut player = playback.player.write().await;
+            let next = match player.looping.mode {
+                LoopMode::Off => LoopMode::Track,
+                LoopMode::Track => LoopMode::Queue,
+                LoopMode::Queue => LoopMode::Off,
+            };
+            player.set_looping(next, None);
+        }
+        BTN_SHUFFLE => {
+            playback.player.write().await.shuffle().await?;
+            return reply(
+                serenity_ctx,
+                interaction,
+                PlayerEmbed::Shuffled.to_embed(),
+                true,
+            )
+            .await;
+        }
+        BTN_VOLUME_DOWN | BTN_VOLUME_UP => {
+            let mut player = playback.player.write().await;
+            let current = player.volume * 100.0;
+            let step = if interaction.data.custom_id == BTN_VOLUME_UP { VOLUME_STEP } else { -VOLUME_STEP };
+            // Overdrive set with `!volume` can be stepped down, never up.
+            let volume = (current + step).clamp(1.0, MAX_BUTTON_VOLUME.max(current));
+            player.set_volume(volume).await?;
+        }
+        _ => {}
+    }
+
+    Ok(())
+}
+
+async fn reply(
+    serenity_ctx: &serenity::prelude::Context,
+    interaction: &ComponentInteraction,
+    embed: CreateEmbed,
+    ephemeral: bool,
+) -> Result<(), MusicBotError> {
+    interaction
+        .create_followup(
+            &serenity_ctx.http,
+            CreateInteractionResponseFollowup::new()
+                .embed(embed)
+                .ephemeral(ephemeral),
+        )
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Per-guild `Player` registry. Every guild the bot is in gets its own
+//! `Player`, created lazily the first time something asks for it (a command,
+//! a `GuildCreate` event, …). Creation seeds the matching `guilds` row so the
+//! persisted volume has somewhere to live.
+
+use crate::bot::{Context, Database, MusicBotData, MusicBotError};
+use crate::player::player::Player;
+use serenity::all::GuildId;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// Default volume (as a 0..1 multiplier) for guilds seen for the first time.
+const DEFAULT_GUILD_VOLUME: f64 = 0.5;
+
+/// Resolve the player for the guild the command was invoked in. Fails with
+/// `NoGuildIdError` for DMs, which have no voice state to play into.
+pub async fn player(ctx: Context<'_>) -> Result<Arc<RwLock<Player>>, MusicBotError> {
+    let guild_id: GuildId = ctx.guild_id().ok_or(MusicBotError::NoGuildIdError)?;
+    player_for_guild(ctx.data(), guild_id).await
+}
+
+/// Return the player for `guild_id`, creating (and seeding the database row
+/// for) it on first use. The player is loaded before the registry is locked,
+/// so a guild's first command never holds up lookups in the others.
+pub async fn player_for_guild(
+    data: &MusicBotData,
+    guild_id: GuildId,
+) -> Result<Arc<RwLock<Player>>, MusicBotError> {
+    if let Some(existing) = data.players.read().await.get(&guild_id) {
+        return Ok(Arc::clone(existing));
+    }
+
+    seed_guild(&data.database_pool, guild_id).await?;
+    let player: Player = Player::new(guild_id, data.database_pool.clone()).await;
+
+    // Another task may have created it meanwhile; theirs wins and this one
+    // is dropped unused.
+    let mut players = data.players.write().await;
+    let handle = players.entry(guild_id).or_insert_with(|| {
+        tracing::info!("Creating player for guild {}", guild_id);
+        Arc::new(RwLock::new(player))
+    });
+
+    Ok(Arc::clone(handle))
+}
+
+/// Return the player for `guild_id` only if one was already created. Used by
+/// event handlers that clean up state and have nothing to do otherwise.
+pub async fn existing_player(
+    data: &MusicBotData,
+    guild_id: GuildId,
+) -> Option<Arc<RwLock<Player>>> {
+    data.players.read().await.get(&guild_id).cloned()
+}
+
+async fn seed_guild(
+    database: &Arc<Database>,
+    guild_id: GuildId,
+) -> Result<(), MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+
+    sqlx::query!(
+        "INSERT OR IGNORE INTO guilds (guild_id, volume) VALUES ($1, $2)",
+        guild_id_map,
+        DEFAULT_GUILD_VOLUME
+    )
+    .execute(&**database)
+    .await
+    .map_err(|e| {
+        tracing::error!("Failed to insert guild into database: {:?}", e);
+        MusicBotError::InternalError(e.to_string())
+    })?;
+
+    Ok(())
+}
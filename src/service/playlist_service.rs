@@ -0,0 +1,410 @@
+//! Named playlists saved per user. Entries store the track metadata and
+//! source as they were resolved when added, so playing a saved playlist
+//! rebuilds its tracks straight from SQLite without any YouTube or Spotify
+//! API calls. A playlist is visible only to its owner until shared with the
+//! guild; names are unique per owner and matched case-insensitively.
+
+use crate::bot::{Database, MusicBotError};
+use crate::player::track::{Playlist, Requester, Track, TrackMetadata, TrackRange, TrackSource};
+use crate::service::attachment_service;
+use serenity::all::{GuildId, UserId};
+use std::time::Duration;
+
+/// Most entries one saved playlist may hold.
+pub const MAX_PLAYLIST_TRACKS: usize = 500;
+/// Longest accepted playlist name, in characters.
+pub const MAX_NAME_LENGTH: usize = 50;
+
+pub struct SavedPlaylist {
+    pub id: i64,
+    pub name: String,
+    pub owner_id: UserId,
+    pub shared: bool,
+    pub track_count: usize,
+}
+
+struct PlaylistRow {
+    id: i64,
+    name: String,
+    owner_id: i64,
+    shared: bool,
+    track_count: i64,
+}
+
+impl From<PlaylistRow> for SavedPlaylist {
+    fn from(row: PlaylistRow) -> Self {
+        SavedPlaylist {
+            id: row.id,
+            name: row.name,
+            owner_id: UserId::new(row.owner_id as u64),
+            shared: row.shared,
+            track_count: row.track_count.max(0) as usize,
+        }
+    }
+}
+
+struct PlaylistTrackRow {
+    media_id: String,
+    title: String,
+    channel: String,
+    track_url: String,
+    play_url: Option<String>,
+    thumbnail: Option<String>,
+    duration_ms: Option<i64>,
+    source: String,
+    local_path: Option<String>,
+    range_start_ms: Option<i64>,
+    range_end_ms: Option<i64>,
+}
+
+impl PlaylistTrackRow {
+    fn into_track(self) -> Option<Track> {
+        let source = TrackSource::from_kind(&self.source, self.local_path)?;
+        Some(Track {
+            id: self.media_id.clone(),
+            metadata: TrackMetadata {
+                id: self.media_id,
+                title: self.title,
+                channel: self.channel,
+                track_url: self.track_url,
+                play_url: self.play_url,
+                thumbnail: self.thumbnail,
+                duration: self.duration_ms.map(|ms| Duration::from_millis(ms as u64)),
+                skip_segments: Vec::new(),
+                chapters: None,
+            },
+            added_by: Requester::default(),
+            source,
+            range: self.range_start_ms.map(|start| TrackRange {
+                start: Duration::from_millis(start.max(0) as u64),
+                end: self
+                    .range_end_ms
+                    .map(|end| Duration::from_millis(end.max(0) as u64)),
+            }),
+        })
+    }
+}
+
+impl SavedPlaylist {
+    /// Rebuild the saved entries as a `Playlist` ready for the queue, every
+    /// track credited to `requester`.
+    pub async fn load(
+        &self,
+        database: &Database,
+        requester: Requester,
+    ) -> Result<Playlist, MusicBotError> {
+        let mut tracks = load_tracks(database, self.id).await?;
+        for track in &mut tracks {
+            track.added_by = requester.clone();
+        }
+
+        Ok(Playlist {
+            id: self.id.to_string(),
+            title: self.name.clone(),
+            description: format!("Saved playlist by <@{}>", self.owner_id),
+            playlist_url: String::new(),
+            tracks,
+        })
+    }
+}
+
+/// Create an empty playlist owned by `owner_id`. Returns `None` when the
+/// owner already has one with that name.
+pub async fn create(
+    database: &Database,
+    guild_id: GuildId,
+    owner_id: UserId,
+    name: &str,
+) -> Result<Option<SavedPlaylist>, MusicBotError> {
+    if find_owned(database, guild_id, owner_id, name)
+        .await?
+        .is_some()
+    {
+        return Ok(None);
+    }
+
+    let guild_id_map: i64 = guild_id.get() as i64;
+    let owner_id_map: i64 = owner_id.get() as i64;
+
+    // A concurrent create of the same name loses to the unique constraint.
+    let row = match sqlx::query!(
+        "INSERT INTO user_playlists (guild_id, owner_id, name) VALUES ($1, $2, $3) RETURNING id",
+        guild_id_map,
+        owner_id_map,
+        name
+    )
+    .fetch_one(database)
+    .await
+    {
+        Ok(row) => row,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => return Ok(None),
+        Err(e) => return Err(MusicBotError::InternalError(e.to_string())),
+    };
+
+    Ok(Some(SavedPlaylist {
+        id: row.id,
+        name: name.to_owned(),
+        owner_id,
+        shared: false,
+        track_count: 0,
+    }))
+}
+
+/// The playlist called `name` that `owner_id` owns in this guild.
+pub async fn find_owned(
+    database: &Database,
+    guild_id: GuildId,
+    owner_id: UserId,
+    name: &str,
+) -> Result<Option<SavedPlaylist>, MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+    let owner_id_map: i64 = owner_id.get() as i64;
+
+    let row = sqlx::query_as!(
+        PlaylistRow,
+        r#"SELECT p.id AS "id!: i64", p.name, p.owner_id, p.shared AS "shared: bool", COUNT(t.position) AS "track_count!: i64" FROM user_playlists p LEFT JOIN user_playlist_tracks t ON t.playlist_id = p.id WHERE p.guild_id = $1 AND p.owner_id = $2 AND LOWER(p.name) = LOWER($3) GROUP BY p.id"#,
+        guild_id_map,
+        owner_id_map,
+        name
+    )
+    .fetch_optional(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(row.map(SavedPlaylist::from))
+}
+
+/// The playlist called `name` that `user_id` may play: their own first,
+/// otherwise the oldest one shared with the guild under that name.
+pub async fn find_visible(
+    database: &Database,
+    guild_id: GuildId,
+    user_id: UserId,
+    name: &str,
+) -> Result<Option<SavedPlaylist>, MusicBotError> {
+    if let Some(playlist) = find_owned(database, guild_id, user_id, name).await? {
+        return Ok(Some(playlist));
+    }
+
+    let guild_id_map: i64 = guild_id.get() as i64;
+
+    let row = sqlx::query_as!(
+        PlaylistRow,
+        r#"SELECT p.id AS "id!: i64", p.name, p.owner_id, p.shared AS "shared: bool", COUNT(t.position) AS "track_count!: i64" FROM user_playlists p LEFT JOIN user_playlist_tracks t ON t.playlist_id = p.id WHERE p.guild_id = $1 AND p.shared = 1 AND LOWER(p.name) = LOWER($2) GROUP BY p.id ORDER BY p.id LIMIT 1"#,
+        guild_id_map,
+        name
+    )
+    .fetch_optional(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(row.map(SavedPlaylist::from))
+}
+
+/// Every playlist `user_id` can see in this guild: their own plus the ones
+/// others shared, own first.
+pub async fn list_visible(
+    database: &Database,
+    guild_id: GuildId,
+    user_id: UserId,
+) -> Result<Vec<SavedPlaylist>, MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+    let user_id_map: i64 = user_id.get() as i64;
+
+    let rows = sqlx::query_as!(
+        PlaylistRow,
+        r#"SELECT p.id AS "id!: i64", p.name, p.owner_id, p.shared AS "shared: bool", COUNT(t.position) AS "track_count!: i64" FROM user_playlists p LEFT JOIN user_playlist_tracks t ON t.playlist_id = p.id WHERE p.guild_id = $1 AND (p.owner_id = $2 OR p.shared = 1) GROUP BY p.id ORDER BY p.owner_id != $2, LOWER(p.name)"#,
+        guild_id_map,
+        user_id_map
+    )
+    .fetch_all(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(rows.into_iter().map(SavedPlaylist::from).collect())
+}
+
+/// The entries of playlist `playlist_id`, in order. Entries whose source
+/// can no longer be read are skipped.
+pub async fn load_tracks(
+    database: &Database,
+    playlist_id: i64,
+) -> Result<Vec<Track>, MusicBotError> {
+    let rows = sqlx::query_as!(
+        PlaylistTrackRow,
+        "SELECT media_id, title, channel, track_url, play_url, thumbnail, duration_ms, source, local_path, range_start_ms, range_end_ms FROM user_playlist_tracks WHERE playlist_id = $1 ORDER BY position",
+        playlist_id
+    )
+    .fetch_all(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    let tracks = rows
+        .into_iter()
+        .filter_map(|row| {
+            let track = row.into_track();
+            if track.is_none() {
+                tracing::warn!(
+                    "Skipping unreadable entry in saved playlist {}",
+                    playlist_id
+                );
+            }
+            track
+        })
+        .collect();
+
+    Ok(tracks)
+}
+
+/// Append `tracks` to the end of `playlist`, stopping at
+/// `MAX_PLAYLIST_TRACKS`. Returns how many were added. One-off attachments
+/// are never stored: their files are deleted once played, so callers turn
+/// them away before getting here.
+pub async fn add_tracks(
+    database: &Database,
+    playlist: &SavedPlaylist,
+    tracks: &[Track],
+) -> Result<usize, MusicBotError> {
+    let room = MAX_PLAYLIST_TRACKS.saturating_sub(playlist.track_count);
+    let tracks: Vec<&Track> = tracks
+        .iter()
+        .filter(|t| !attachment_service::is_temporary(t))
+        .take(room)
+        .collect();
+
+    let mut tx = database
+        .begin()
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    let next = sqlx::query!(
+        r#"SELECT COALESCE(MAX(position) + 1, 0) AS "next!: i64" FROM user_playlist_tracks WHERE playlist_id = $1"#,
+        playlist.id
+    )
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?
+    .next;
+
+    for (offset, track) in tracks.iter().enumerate() {
+        let position: i64 = next + offset as i64;
+        let duration_ms: Option<i64> = track.metadata.duration.map(|d| d.as_millis() as i64);
+        let source: String = track.source.kind();
+        let local_path: Option<String> = match &track.source {
+            TrackSource::Local(path) => Some(path.to_string_lossy().to_string()),
+            _ => None,
+        };
+        let range_start_ms: Option<i64> = track.range.map(|range| range.start.as_millis() as i64);
+        let range_end_ms: Option<i64> = track
+            .range
+            .and_then(|range| range.end)
+            .map(|end| end.as_millis() as i64);
+
+        sqlx::query!(
+            "INSERT INTO user_playlist_tracks (playlist_id, position, media_id, title, channel, track_url, play_url, thumbnail, duration_ms, source, local_path, range_start_ms, range_end_ms) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+            playlist.id,
+            position,
+            track.metadata.id,
+            track.metadata.title,
+            track.metadata.channel,
+            track.metadata.track_url,
+            track.metadata.play_url,
+            track.metadata.thumbnail,
+            duration_ms,
+            source,
+            local_path,
+            range_start_ms,
+            range_end_ms
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+    Ok(tracks.len())
+}
+
+/// Remove the `index`-th entry (1-based) of `playlist_id`. Returns the
+/// removed entry's title, or `None` when there is no such entry. Positions
+/// only order the entries, so the ones after it keep theirs.
+pub async fn remove_track(
+    database: &Database,
+    playlist_id: i64,
+    index: usize,
+) -> Result<Option<String>, MusicBotError> {
+    let Some(offset) = index.checked_sub(1).map(|i| i as i64) else {
+        return Ok(None);
+    };
+
+    let Some(entry) = sqlx::query!(
+        "SELECT position, title FROM user_playlist_tracks WHERE playlist_id = $1 ORDER BY position LIMIT 1 OFFSET $2",
+        playlist_id,
+        offset
+    )
+    .fetch_optional(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?
+    else {
+        return Ok(None);
+    };
+
+    sqlx::query!(
+        "DELETE FROM user_playlist_tracks WHERE playlist_id = $1 AND position = $2",
+        playlist_id,
+        entry.position
+    )
+    .execute(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(Some(entry.title))
+}
+
+pub async fn set_shared(
+    database: &Database,
+    playlist_id: i64,
+    shared: bool,
+) -> Result<(), MusicBotError> {
+    sqlx::query!(
+        "UPDATE user_playlists SET shared = $1 WHERE id = $2",
+        shared,
+        playlist_id
+    )
+    .execute(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Delete `playlist_id` and all of its entries.
+pub async fn delete(
+    database: &Database,
+    playlist_id: i64,
+) -> Result<(), MusicBotError> {
+    let mut tx = database
+        .begin()
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    sqlx::query!(
+        "DELETE FROM user_playlist_tracks WHERE playlist_id = $1",
+        playlist_id
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    sqlx::query!("DELETE FROM user_playlists WHERE id = $1", playlist_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+    Ok(())
+}
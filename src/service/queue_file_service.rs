@@ -0,0 +1,260 @@
+//! Queue export/import file formats. An export is either an extended M3U
+//! playlist or a JSON array of entries; an import accepts either of those
+//! back, or any plain text file with one URL or search query per line.
+//! Parsing only produces the lines to resolve — resolving them is up to the
+//! caller, the same way `play` resolves its argument.
+
+use crate::player::track::{Track, TrackSource};
+use crate::service::attachment_service;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueFileFormat {
+    M3u,
+    Json,
+}
+
+impl QueueFileFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            QueueFileFormat::M3u => "m3u",
+            QueueFileFormat::Json => "json",
+        }
+    }
+}
+
+/// One track in a JSON export.
+#[derive(Debug, Serialize, Deserialize)]
+struct QueueFileEntry {
+    title: String,
+    #[serde(default)]
+    channel: String,
+    #[serde(default)]
+    url: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duration_secs: Option<u64>,
+    #[serde(default)]
+    source: String,
+    #[serde(default)]
+    added_by: String,
+}
+
+/// What an import line should be resolved from: a URL or search query that
+/// goes through `play`'s resolution, or a file in the local library.
+fn entry_location(track: &Track) -> String {
+    match &track.source {
+        TrackSource::Local(path) => path.to_string_lossy().to_string(),
+        // Spotify tracks without a permalink only have their `ytsearch1:`
+        // query; strip the prefix so the line re-imports as a plain search.
+        _ if track.metadata.track_url.is_empty() => track
+            .metadata
+            .play_url
+            .as_deref()
+            .map(|url| url.trim_start_matches("ytsearch1:").to_string())
+            .unwrap_or_else(|| track.metadata.title.clone()),
+        _ => track.metadata.track_url.clone(),
+    }
+}
+
+/// Render `tracks` (the current track first, then the queue) as a file in
+/// `format`. One-off attachments are left out: their files are deleted once
+/// played, so an import could never find them.
+pub fn export(
+    tracks: &[&Track],
+    format: QueueFileFormat,
+) -> String {
+    let tracks: Vec<&Track> = tracks
+        .iter()
+        .copied()
+        .filter(|t| !attachment_service::is_temporary(t))
+        .collect();
+    match format {
+        QueueFileFormat::M3u => {
+            let mut out = String::from("#EXTM3U\n");
+            for track in tracks {
+                let seconds: i64 = track.duration().map(|d| d.as_secs() as i64).unwrap_or(-1);
+                // Line breaks in a title would end the #EXTINF line early.
+                let title: String = format!("{} - {}", track.metadata.channel, track.metadata.title).replace(['\r', '\n'], " ");
+                out.push_str(&format!(
+                    "#EXTINF:{},{}\n{}\n",
+                    seconds,
+                    title,
+                    entry_location(track)
+                ));
+            }
+            out
+        }
+        QueueFileFormat::Json => {
+            let entries: Vec<QueueFileEntry> = tracks
+                .iter()
+                .map(|track| QueueFileEntry {
+                    title: track.metadata.title.clone(),
+                    channel: track.metadata.channel.clone(),
+                    url: entry_location(track),
+                    duration_secs: track.duration().map(|d| d.as_secs()),
+                    source: track.source.kind(),
+                    added_by: track.added_by.name.clone(),
+                })
+                .collect();
+            serde_json::to_string_pretty(&entries).unwrap_or_else(|_| "[]".to_string())
+        }
+    }
+}
+
+/// The lines to resolve from an uploaded file. A JSON export yields each
+/// entry's URL (or "title channel" as a search when it has none); anything
+/// else is read line by line, skipping blanks and `#` comments, which covers
+/// M3U and plain lists of URLs or queries alike. Only a `.json` file has to
+/// be valid JSON; a plain list may start with a bracket too, as in
+/// `[Official Video] Artist - Song`.
+pub fn parse(
+    filename: &str,
+    text: &str,
+) -> Result<Vec<String>, String> {
+    let text = text.trim_start_matches('\u{feff}').trim();
+    let named_json = filename.to_ascii_lowercase().ends_with(".json");
+
+    if named_json || text.starts_with('[') {
+        match serde_json::from_str::<Vec<QueueFileEntry>>(text) {
+            Ok(entries) => {
+                return Ok(entries
+                    .into_iter()
+                    .map(|entry| {
+                        if entry.url.trim().is_empty() {
+                            format!("{} {}", entry.title, entry.channel)
+                                .trim()
+                                .to_string()
+                        } else {
+                            entry.url.trim().to_string()
+                        }
+                    })
+                    .filter(|line| !line.is_empty())
+                    .collect());
+            }
+            Err(e) if named_json => return Err(format!("The file looks like JSON but can't be read: {e}")),
+            Err(_) => {}
+        }
+    }
+
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn track(
+        title: &str,
+        track_url: &str,
+        source: TrackSource,
+    ) -> Track {
+        Track::stub()
+            .titled("Channel", title)
+            .linked(track_url)
+            .sourced(source)
+            .lasting(212)
+    }
+
+    #[test]
+    fn m3u_export() {
+        let mut unknown = track("Live", "https://example.com/live", TrackSource::YouTube);
+        unknown.metadata.duration = None;
+        let tracks = [&track("Song\nTwo", "https://youtu.be/abc", TrackSource::YouTube), &unknown];
+        assert_eq!(
+            export(&tracks, QueueFileFormat::M3u),
+            "#EXTM3U\n\
+             #EXTINF:212,Channel - Song Two\nhttps://youtu.be/abc\n\
+             #EXTINF:-1,Channel - Live\nhttps://example.com/live\n"
+        );
+    }
+
+    #[test]
+    fn export_leaves_out_uploads() {
+        let upload = track(
+            "Upload",
+            "",
+            TrackSource::Local(attachment_service::attachments_dir().join("1_song.mp3")),
+        );
+        let kept = track("Kept", "https://youtu.be/kept", TrackSource::YouTube);
+        let text = export(&[&upload, &kept], QueueFileFormat::M3u);
+        assert_eq!(
+            parse("queue.m3u", &text).unwrap(),
+            vec!["https://youtu.be/kept"]
+        );
+    }
+
+    #[test]
+    fn entry_locations() {
+        let local = track(
+            "Song",
+            "",
+            TrackSource::Local(PathBuf::from("downloads/song.mp3")),
+        );
+        assert_eq!(entry_location(&local), "downloads/song.mp3");
+
+        let mut spotify = track("Song", "", TrackSource::Spotify);
+        spotify.metadata.play_url = Some("ytsearch1:Artist Song".to_string());
+        assert_eq!(entry_location(&spotify), "Artist Song");
+
+        let bare = track("Song", "", TrackSource::Spotify);
+        assert_eq!(entry_location(&bare), "Song");
+    }
+
+    #[test]
+    fn m3u_round_trip() {
+        let first = track("One", "https://youtu.be/one", TrackSource::YouTube);
+        let second = track("Two", "https://youtu.be/two", TrackSource::YouTube);
+        let text = export(&[&first, &second], QueueFileFormat::M3u);
+        assert_eq!(
+            parse("queue.m3u", &text).unwrap(),
+            vec!["https://youtu.be/one", "https://youtu.be/two"]
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let first = track("One", "https://youtu.be/one", TrackSource::YouTube);
+        let mut second = track("Two", "", TrackSource::Spotify);
+        second.metadata.play_url = Some("ytsearch1:Channel Two".to_string());
+        let text = export(&[&first, &second], QueueFileFormat::Json);
+        assert_eq!(
+            parse("queue.json", &text).unwrap(),
+            vec!["https://youtu.be/one", "Channel Two"]
+        );
+    }
+
+    #[test]
+    fn json_entry_without_url_becomes_a_search() {
+        let text = r#"[{"title": "Song", "channel": "Artist"}, {"title": " "}]"#;
+        assert_eq!(parse("queue.json", text).unwrap(), vec!["Song Artist"]);
+    }
+
+    #[test]
+    fn plain_list_skips_blanks_and_comments() {
+        let text = "\u{feff}# my queue\n\nhttps://youtu.be/one\r\n  never gonna give you up  \n";
+        assert_eq!(
+            parse("queue.txt", text).unwrap(),
+            vec!["https://youtu.be/one", "never gonna give you up"]
+        );
+    }
+
+    #[test]
+    fn broken_json_is_an_error() {
+        assert!(parse("queue.json", "[{\"title\": ").is_err());
+    }
+
+    #[test]
+    fn plain_list_may_start_with_a_bracket() {
+        let text = "[Official Video] Artist - Song\nhttps://youtu.be/two\n";
+        assert_eq!(
+            parse("queue.txt", text).unwrap(),
+            vec!["[Official Video] Artist - Song", "https://youtu.be/two"]
+        );
+    }
+}
@@ -0,0 +1,610 @@
+//! Persistence for each guild's player across restarts. A background task
+//! snapshots every active player (queue, now-playing track and its playback
+//! position) into SQLite every few seconds. When the bot comes back up, the
+//! first `GuildCreate` for a guild with a saved session posts an offer in the
+//! channel music was last started from; accepting it rejoins the saved voice
+//! channel and resumes the queue.
+//!
+//! The saved offset is only honoured for inputs that can seek instantly (a
+//! cached or local file) — a streamed track restarts from the beginning.
+
+use crate::bot::{Database, MusicBotData, MusicBotError};
+use crate::embeds::music::player_embed::PlayerEmbed;
+use crate::player::playback::Playback;
+use crate::player::player::{self, Player};
+use crate::player::track::{Requester, Track, TrackMetadata, TrackRange, TrackSource};
+use crate::service::attachment_service;
+use crate::service::channel_service;
+use crate::service::embed_service::SendEmbed;
+use crate::service::interaction_service::DeferredInteractionStream;
+use serenity::all::{ButtonStyle, ChannelId, CreateActionRow, CreateButton, CreateInteractionResponseFollowup, CreateMessage, GuildChannel, GuildId, Message, UserId};
+use serenity::prelude::Context as SerenityContext;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(15);
+const RESTORE_OFFER_TIMEOUT: Duration = Duration::from_secs(60 * 10);
+
+const RESTORE_BUTTON_ID: &str = "session_restore";
+const DISCARD_BUTTON_ID: &str = "session_discard";
+
+/// A guild's player as it was at the last snapshot.
+pub struct SavedSession {
+    pub voice_channel_id: ChannelId,
+    pub text_channel_id: ChannelId,
+    pub position: Duration,
+    pub current: Option<Track>,
+    pub queue: Vec<Track>,
+}
+
+#[derive(sqlx::FromRow)]
+struct SessionTrackRow {
+    position: i64,
+    track_id: String,
+    media_id: String,
+    title: String,
+    channel: String,
+    track_url: String,
+    play_url: Option<String>,
+    duration_ms: Option<i64>,
+    added_by: String,
+    added_by_id: Option<i64>,
+    source: String,
+    local_path: Option<String>,
+    range_start_ms: Option<i64>,
+    range_end_ms: Option<i64>,
+}
+
+impl SessionTrackRow {
+    fn into_track(self) -> Option<Track> {
+        let source = TrackSource::from_kind(&self.source, self.local_path)?;
+        Some(Track {
+            id: self.track_id,
+            metadata: TrackMetadata {
+                id: self.media_id,
+                title: self.title,
+                channel: self.channel,
+                track_url: self.track_url,
+                play_url: self.play_url,
+                thumbnail: None,
+                duration: self.duration_ms.map(|ms| Duration::from_millis(ms as u64)),
+                skip_segments: Vec::new(),
+                chapters: None,
+            },
+            added_by: Requester {
+                id: self.added_by_id.map(|id| UserId::new(id as u64)),
+                name: self.added_by,
+            },
+            source,
+            range: self.range_start_ms.map(|start| TrackRange {
+                start: Duration::from_millis(start.max(0) as u64),
+                end: self
+                    .range_end_ms
+                    .map(|end| Duration::from_millis(end.max(0) as u64)),
+            }),
+        })
+    }
+}
+
+/// Replace the saved session for `guild_id` with the given snapshot.
+pub async fn save_session(
+    database: &Database,
+    guild_id: GuildId,
+    voice_channel_id: ChannelId,
+    text_channel_id: ChannelId,
+    position: Duration,
+    current: Option<&Track>,
+    queue: &[Track],
+) -> Result<(), MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+    let voice_channel_map: i64 = voice_channel_id.get() as i64;
+    let text_channel_map: i64 = text_channel_id.get() as i64;
+    let position_ms: i64 = position.as_millis() as i64;
+
+    let mut tx = database
+        .begin()
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    sqlx::query!(
+        "DELETE FROM player_session_tracks WHERE guild_id = $1",
+        guild_id_map
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    sqlx::query!(
+        "INSERT OR REPLACE INTO player_sessions (guild_id, voice_channel_id, text_channel_id, position_ms, updated_at) VALUES ($1, $2, $3, $4, CURRENT_TIMESTAMP)",
+        guild_id_map,
+        voice_channel_map,
+        text_channel_map,
+        position_ms
+    )
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    // Slot 0 is reserved for the now-playing track so the queue keeps its
+    // 1-based numbering whether or not something was playing.
+    // One-off attachments only live as long as the bot's run; their files
+    // are gone by the time a session could be restored.
+    let slots = current
+        .map(|t| (0usize, t))
+        .into_iter()
+        .chain(queue.iter().enumerate().map(|(i, t)| (i + 1, t)))
+        .filter(|(_, t)| !attachment_service::is_temporary(t));
+
+    for (slot, track) in slots {
+        let slot: i64 = slot as i64;
+        let duration_ms: Option<i64> = track.metadata.duration.map(|d| d.as_millis() as i64);
+        let added_by_id: Option<i64> = track.added_by.id.map(|id| id.get() as i64);
+        let source: String = track.source.kind();
+        let local_path: Option<String> = match &track.source {
+            TrackSource::Local(path) => Some(path.to_string_lossy().to_string()),
+            _ => None,
+        };
+        let range_start_ms: Option<i64> = track.range.map(|range| range.start.as_millis() as i64);
+        let range_end_ms: Option<i64> = track
+            .range
+            .and_then(|range| range.end)
+            .map(|end| end.as_millis() as i64);
+
+        sqlx::query!(
+            "INSERT INTO player_session_tracks (guild_id, position, track_id, media_id, title, channel, track_url, play_url, duration_ms, added_by, added_by_id, source, local_path, range_start_ms, range_end_ms) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)",
+            guild_id_map,
+            slot,
+            track.id,
+            track.metadata.id,
+            track.metadata.title,
+            track.metadata.channel,
+            track.metadata.track_url,
+            track.metadata.play_url,
+            duration_ms,
+            track.added_by.name,
+            added_by_id,
+            source,
+            local_path,
+            range_start_ms,
+            range_end_ms
+        )
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+    Ok(())
+}
+
+/// Load the saved session for `guild_id`, if there is one.
+pub async fn load_session(
+    database: &Database,
+    guild_id: GuildId,
+) -> Result<Option<SavedSession>, MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+
+    let Some(session) = sqlx::query!(
+        "SELECT voice_channel_id, text_channel_id, position_ms FROM player_sessions WHERE guild_id = $1",
+        guild_id_map
+    )
+    .fetch_optional(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?
+    else {
+        return Ok(None);
+    };
+
+    let rows: Vec<SessionTrackRow> = sqlx::query_as!(
+        SessionTrackRow,
+        "SELECT position, track_id, media_id, title, channel, track_url, play_url, duration_ms, added_by, added_by_id, source, local_path, range_start_ms, range_end_ms FROM player_session_tracks WHERE guild_id = $1 ORDER BY position",
+        guild_id_map
+    )
+    .fetch_all(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    let mut current: Option<Track> = None;
+    let mut queue: Vec<Track> = Vec::new();
+    for row in rows {
+        let is_current = row.position == 0;
+        match row.into_track() {
+            Some(track) if is_current => current = Some(track),
+            Some(track) => queue.push(track),
+            None => tracing::warn!("Skipping unreadable saved track for guild {}", guild_id),
+        }
+    }
+
+    if current.is_none() && queue.is_empty() {
+        return Ok(None);
+    }
+
+    Ok(Some(SavedSession {
+        voice_channel_id: ChannelId::new(session.voice_channel_id as u64),
+        text_channel_id: ChannelId::new(session.text_channel_id as u64),
+        position: Duration::from_millis(session.position_ms.max(0) as u64),
+        current,
+        queue,
+    }))
+}
+
+pub async fn clear_session(
+    database: &Database,
+    guild_id: GuildId,
+) -> Result<(), MusicBotError> {
+    let guild_id_map: i64 = guild_id.get() as i64;
+
+    sqlx::query!(
+        "DELETE FROM player_session_tracks WHERE guild_id = $1",
+        guild_id_map
+    )
+    .execute(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    sqlx::query!(
+        "DELETE FROM player_sessions WHERE guild_id = $1",
+        guild_id_map
+    )
+    .execute(database)
+    .await
+    .map_err(|e| MusicBotError::InternalError(e.to_string()))?;
+
+    Ok(())
+}
+
+/// Periodically snapshot every active player. A guild's saved session is
+/// only cleared once a player this run had saved goes idle — sessions left
+/// over from the previous run stay put until they are restored, discarded,
+/// or overwritten by new playback.
+pub fn spawn_autosave(
+    serenity_ctx: SerenityContext,
+    players: Arc<RwLock<HashMap<GuildId, Arc<RwLock<Player>>>>>,
+    database: Arc<Database>,
+) {
+    tokio::spawn(async move {
+        let mut saved: HashSet<GuildId> = HashSet::new();
+
+        loop {
+            tokio::time::sleep(AUTOSAVE_INTERVAL).await;
+
+            let handles: Vec<(GuildId, Arc<RwLock<Player>>)> = players
+                .read()
+                .await
+                .iter()
+                .map(|(guild_id, player)| (*guild_id, Arc::clone(player)))
+                .collect();
+
+            for (guild_id, player_arc) in handles {
+                match snapshot(&serenity_ctx, &database, guild_id, &player_arc).await {
+                    Ok(true) => {
+                        saved.insert(guild_id);
+                    }
+                    Ok(false) => {
+                        if saved.remove(&guild_id) {
+                            if let Err(e) = clear_session(&database, guild_id).await {
+                                tracing::warn!(
+                                    "Failed to clear saved session for guild {}: {}",
+                                    guild_id,
+                                    e
+                                );
+                            }
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to save session for guild {}: {}", guild_id, e),
+                }
+            }
+        }
+    });
+}
+
+/// Save `player`'s state. Returns `false` (without touching the database)
+/// when there is nothing worth restoring.
+async fn snapshot(
+    serenity_ctx: &SerenityContext,
+    database: &Database,
+    guild_id: GuildId,
+    player_arc: &Arc<RwLock<Player>>,
+) -> Result<bool, MusicBotError> {
+    let voice_channel_id: Option<ChannelId> = channel_service::bot_voice_channel(serenity_ctx, guild_id);
+
+    let player = player_arc.read().await;
+
+    if player.current_track.is_none() && player.queue.is_empty() {
+        return Ok(false);
+    }
+
+    let (Some(voice_channel_id), Some(text_channel_id)) = (voice_channel_id, player.text_channel_id) else {
+        return Ok(false);
+    };
+
+    let position = match &player.track_handle {
+        Some(handle) => handle
+            .get_info()
+            .await
+            .map(|info| player.track_position(info.position))
+            .unwrap_or_default(),
+        None => Duration::ZERO,
+    };
+    let current: Option<Track> = player.current_track.clone();
+    let queue: Vec<Track> = player.queue.clone();
+    drop(player);
+
+    save_session(
+        database,
+        guild_id,
+        voice_channel_id,
+        text_channel_id,
+        position,
+        current.as_ref(),
+        &queue,
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Post a "restore last session?" offer for `guild_id` if a session was
+/// saved before the restart. Runs at most once per player; the offer itself
+/// waits in a background task.
+pub async fn offer_restore(
+    serenity_ctx: &SerenityContext,
+    data: &MusicBotData,
+    guild_id: GuildId,
+    player_arc: Arc<RwLock<Player>>,
+) -> Result<(), MusicBotError> {
+    {
+        let mut player = player_arc.write().await;
+        if player.restore_offered {
+            return Ok(());
+        }
+        player.restore_offered = true;
+    }
+
+    let Some(session) = load_session(&data.database_pool, guild_id).await? else {
+        return Ok(());
+    };
+
+    let text_channel: Option<GuildChannel> = session
+        .text_channel_id
+        .to_channel(serenity_ctx)
+        .await
+        .ok()
+        .and_then(|c| c.guild());
+
+    let Some(text_channel) = text_channel else {
+        tracing::info!(
+            "Saved session channel for guild {} is gone; discarding session",
+            guild_id
+        );
+        return clear_session(&data.database_pool, guild_id).await;
+    };
+
+    let serenity_ctx = serenity_ctx.clone();
+    let database = data.database_pool.clone();
+    let req_client = data.request_client.clone();
+
+    tokio::spawn(async move {
+        if let Err(e) = run_restore_offer(
+            serenity_ctx,
+            database,
+            req_client,
+            guild_id,
+            player_arc,
+            text_channel,
+            session,
+        )
+        .await
+        {
+            tracing::error!("Restore offer for guild {} failed: {}", guild_id, e);
+        }
+    });
+
+    Ok(())
+}
+
+async fn run_restore_offer(
+    serenity_ctx: SerenityContext,
+    database: Arc<Database>,
+    req_client: reqwest::Client,
+    guild_id: GuildId,
+    player_arc: Arc<RwLock<Player>>,
+    text_channel: GuildChannel,
+    session: SavedSession,
+) -> Result<(), MusicBotError> {
+    let http = serenity_ctx.http.clone();
+
+    let buttons = vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(RESTORE_BUTTON_ID)
+            .label("▶ Restore")
+            .style(ButtonStyle::Success),
+        CreateButton::new(DISCARD_BUTTON_ID)
+            .label("✖ Discard")
+            .style(ButtonStyle::Secondary),
+    ])];
+
+    let embed = PlayerEmbed::RestoreOffer {
+        current: session.current.as_ref(),
+        position: session.position,
+        queued: session.queue.len(),
+        voice_channel: session.voice_channel_id,
+    }
+    .to_embed();
+
+    let message: Message = text_channel
+        .send_message(&http, CreateMessage::new().embed(embed).components(buttons))
+        .await?;
+
+    let mut stream = DeferredInteractionStream::new(&serenity_ctx, message.id);
+    let deadline = Instant::now() + RESTORE_OFFER_TIMEOUT;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let Some(interaction) = stream.next_within(remaining).await else {
+            let _ = message.delete(&http).await;
+            return Ok(());
+        };
+
+        match interaction.data.custom_id.as_str() {
+            DISCARD_BUTTON_ID => {
+                let _ = message.delete(&http).await;
+                clear_session(&database, guild_id).await?;
+                PlayerEmbed::SessionDiscarded
+                    .to_embed()
+                    .send_channel(http.clone(), &text_channel, Some(30), None)
+                    .await?;
+                return Ok(());
+            }
+            RESTORE_BUTTON_ID => {
+                let clicker_channel: Option<ChannelId> = serenity_ctx
+                    .cache
+                    .guild(guild_id)
+                    .as_ref()
+                    .and_then(|g| g.voice_states.get(&interaction.user.id))
+                    .and_then(|vs| vs.channel_id);
+
+                if clicker_channel != Some(session.voice_channel_id) {
+                    let _ = interaction
+                        .create_followup(
+                            &http,
+                            CreateInteractionResponseFollowup::new()
+                                .content(format!(
+                                    "Join <#{}> first to restore the session.",
+                                    session.voice_channel_id
+                                ))
+                                .ephemeral(true),
+                        )
+                        .await;
+                    continue;
+                }
+
+                let _ = message.delete(&http).await;
+                break;
+            }
+            _ => continue,
+        }
+    }
+
+    drop(stream);
+
+    let restored = restore(
+        &serenity_ctx,
+        req_client,
+        guild_id,
+        &player_arc,
+        &text_channel,
+        session,
+    )
+    .await;
+
+    let embed = match restored {
+        Ok(count) => PlayerEmbed::SessionRestored(count).to_embed(),
+        Err(e) => {
+            let _ = clear_session(&database, guild_id).await;
+            PlayerEmbed::SessionRestoreFailed(e.to_string()).to_embed()
+        }
+    };
+    embed
+        .send_channel(http.clone(), &text_channel, Some(30), None)
+        .await?;
+
+    Ok(())
+}
+
+/// Rejoin the saved voice channel and start the saved queue. Returns how
+/// many tracks (including the resumed one) were restored.
+async fn restore(
+    serenity_ctx: &SerenityContext,
+    req_client: reqwest::Client,
+    guild_id: GuildId,
+    player_arc: &Arc<RwLock<Player>>,
+    text_channel: &GuildChannel,
+    session: SavedSession,
+) -> Result<usize, MusicBotError> {
+    ensure_idle(&player_arc.read().await)?;
+
+    channel_service::join_channel(serenity_ctx, guild_id, session.voice_channel_id).await?;
+
+    let manager = songbird::get(serenity_ctx)
+        .await
+        .ok_or_else(|| MusicBotError::InternalError("Songbird manager not registered".to_owned()))?
+        .get_or_insert(guild_id);
+
+    let playback = Playback::new(
+        serenity_ctx.clone(),
+        manager,
+        req_client,
+        Arc::clone(player_arc),
+        text_channel.clone(),
+        guild_id,
+    );
+
+    let count = session.queue.len() + usize::from(session.current.is_some());
+
+    let mut player = player_arc.write().await;
+    // Someone may have queued something while we were rejoining; that wins
+    // over the saved queue rather than being overwritten by it.
+    ensure_idle(&player)?;
+    player.inactivity_cancel.store(true, Ordering::SeqCst);
+    player.queue = session.queue;
+
+    // The saved position belongs to the saved track; if that one gets
+    // refused, whatever starts instead plays from the top.
+    let resume = session.current.map(|track| {
+        let id = track.id.clone();
+        player.queue.insert(0, track);
+        (id, session.position)
+    });
+
+    tracing::info!(
+        "Restoring session for guild {} ({} track(s))",
+        guild_id,
+        count
+    );
+
+    let (started, announcements) = playback.play_next(&mut player).await;
+    announcements.send_channel(&playback).await;
+
+    let Some(handle) = started else {
+        // Already back in voice with nothing to play: go idle the way the
+        // End event does, so the bot still leaves after a while.
+        player::set_idle(serenity_ctx, guild_id);
+        player.inactivity_cancel.store(false, Ordering::Relaxed);
+        player::spawn_inactivity_leave(
+            serenity_ctx.clone(),
+            Arc::clone(player_arc),
+            guild_id,
+            text_channel.clone(),
+            Arc::clone(&player.inactivity_cancel),
+        );
+        return Err(MusicBotError::InternalError(
+            "None of the saved tracks can be played anymore.".to_owned(),
+        ));
+    };
+
+    // Only file-backed inputs seek instantly; a stream would have to decode
+    // everything up to the offset, so it restarts from the top instead.
+    if let Some((id, resume_at)) = resume {
+        let resumed = player.current_track.as_ref().is_some_and(|t| t.id == id);
+        if resumed && !resume_at.is_zero() && player.current_input_seekable {
+            let _ = handle.seek(player.input_position(resume_at));
+        }
+    }
+
+    Ok(count)
+}
+
+/// A session is only restored into a player with nothing playing or queued.
+fn ensure_idle(player: &Player) -> Result<(), MusicBotError> {
+    if player.is_playing || !player.queue.is_empty() {
+        return Err(MusicBotError::InternalError(
+            "Something is already playing — the saved session was not restored.".to_owned(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,190 @@
+//! SponsorBlock lookups. With the guild's SponsorBlock mode on, YouTube
+//! tracks are annotated with the sponsor and non-music stretches the
+//! community marked in them. A cached copy is downloaded with those parts
+//! cut out; a stream still contains them, so `SponsorBlockHandler` seeks
+//! over each one as playback reaches it. Either way the track's length is
+//! what's left, which is what the length cap and queue times go by.
+
+use crate::player::track::{Track, TrackSource};
+use serde::Deserialize;
+use serenity::futures::StreamExt;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+const API_URL: &str = "https://sponsor.ajay.app/api/skipSegments";
+/// Segment categories that get cut: paid promotions, and the talking,
+/// intros and skits in music videos.
+pub const CATEGORIES: &[&str] = &["sponsor", "music_offtopic"];
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Lookups remembered at once; the map is emptied when it fills up.
+const MAX_CACHED_LOOKUPS: usize = 1000;
+/// How many lookups a playlist runs at the same time.
+const CONCURRENT_LOOKUPS: usize = 8;
+
+/// Answers per video id, so a track queued again (or annotated once when
+/// queued and again before it starts) doesn't ask twice.
+static LOOKUPS: OnceLock<Mutex<HashMap<String, Vec<SkipSegment>>>> = OnceLock::new();
+
+fn lookups() -> &'static Mutex<HashMap<String, Vec<SkipSegment>>> {
+    LOOKUPS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// A stretch of a video to leave out, on the video's own timeline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkipSegment {
+    pub start: Duration,
+    pub end: Duration,
+}
+
+impl SkipSegment {
+    pub fn length(&self) -> Duration {
+        self.end.saturating_sub(self.start)
+    }
+
+    pub fn contains(
+        &self,
+        position: Duration,
+    ) -> bool {
+        (self.start..self.end).contains(&position)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiSegment {
+    segment: [f64; 2],
+}
+
+/// The merged, sorted segments to skip in YouTube video `video_id`. Empty
+/// when nothing was submitted for it, and when SponsorBlock couldn't be
+/// reached (that answer isn't remembered, so it's asked again next time).
+pub async fn segments(
+    client: &reqwest::Client,
+    video_id: &str,
+) -> Vec<SkipSegment> {
+    if let Some(known) = lookups()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(video_id)
+    {
+        return known.clone();
+    }
+
+    let mut query: Vec<(&str, &str)> = vec![("videoID", video_id)];
+    query.extend(CATEGORIES.iter().map(|category| ("category", *category)));
+
+    let response = match client
+        .get(API_URL)
+        .query(&query)
+        .timeout(LOOKUP_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::warn!("SponsorBlock lookup failed for {}: {}", video_id, e);
+            return Vec::new();
+        }
+    };
+
+    // SponsorBlock answers 404 for videos without any segments.
+    let found: Vec<SkipSegment> = if response.status() == reqwest::StatusCode::NOT_FOUND {
+        Vec::new()
+    } else {
+        match response.error_for_status() {
+            Ok(response) => match response.json::<Vec<ApiSegment>>().await {
+                Ok(segments) => merge(segments),
+                Err(e) => {
+                    tracing::warn!("Unreadable SponsorBlock answer for {}: {}", video_id, e);
+                    return Vec::new();
+                }
+            },
+            Err(e) => {
+                tracing::warn!("SponsorBlock lookup failed for {}: {}", video_id, e);
+                return Vec::new();
+            }
+        }
+    };
+
+    let mut known = lookups().lock().unwrap_or_else(|e| e.into_inner());
+    if known.len() >= MAX_CACHED_LOOKUPS {
+        known.clear();
+    }
+    known.insert(video_id.to_string(), found.clone());
+
+    found
+}
+
+/// Sort `segments` and fold overlapping ones together, since submissions in
+/// different categories often cover the same stretch.
+fn merge(segments: Vec<ApiSegment>) -> Vec<SkipSegment> {
+    let mut sorted: Vec<SkipSegment> = segments
+        .into_iter()
+        .filter(|s| s.segment.iter().all(|t| t.is_finite() && *t >= 0.0) && s.segment[1] > s.segment[0])
+        .map(|s| SkipSegment {
+            start: Duration::from_secs_f64(s.segment[0]),
+            end: Duration::from_secs_f64(s.segment[1]),
+        })
+        .collect();
+    sorted.sort_by_key(|s| s.start);
+
+    let mut merged: Vec<SkipSegment> = Vec::new();
+    for segment in sorted {
+        match merged.last_mut() {
+            Some(last) if segment.start <= last.end => last.end = last.end.max(segment.end),
+            _ => merged.push(segment),
+        }
+    }
+    merged
+}
+
+/// Look up and attach the segments to skip in `track`. Only YouTube tracks
+/// have a video id to look up; tracks already annotated are left alone.
+pub async fn annotate(
+    client: &reqwest::Client,
+    track: &mut Track,
+) {
+    if !matches!(track.source, TrackSource::YouTube) || !track.metadata.skip_segments.is_empty() {
+        return;
+    }
+    track.metadata.skip_segments = segments(client, &track.metadata.id).await;
+}
+
+/// `annotate` every track in `tracks`, a few at a time.
+pub async fn annotate_all(
+    client: &reqwest::Client,
+    tracks: &mut [Track],
+) {
+    serenity::futures::stream::iter(tracks.iter_mut())
+        .for_each_concurrent(CONCURRENT_LOOKUPS, |track| annotate(client, track))
+        .await;
+}
+
+/// Position on the trimmed timeline for `position` on the full one: the
+/// time spent in skipped segments before it doesn't count.
+pub fn trimmed_position(
+    segments: &[SkipSegment],
+    position: Duration,
+) -> Duration {
+    let skipped: Duration = segments
+        .iter()
+        .filter(|s| s.start < position)
+        .map(|s| s.end.min(position).saturating_sub(s.start))
+        .sum();
+    position.saturating_sub(skipped)
+}
+
+/// Inverse of `trimmed_position`: where `position` on the trimmed timeline
+/// falls on the full one.
+pub fn full_position(
+    segments: &[SkipSegment],
+    position: Duration,
+) -> Duration {
+    let mut full = position;
+    for segment in segments {
+        if segment.start <= full {
+            full += segment.length();
+        }
+    }
+    full
+}
@@ -0,0 +1,89 @@
+//! Vote-skip: instead of anyone skipping outright, listeners vote and the
+//! track is skipped once enough of the bot's voice channel agrees. Votes come
+//! from the `voteskip` command and from the button on the control panel.
+//! The person who queued the current track can always skip it instantly.
+
+use crate::bot::MusicBotError;
+use crate::player::player::Player;
+use crate::service::channel_service;
+use serenity::all::{GuildId, User};
+use serenity::prelude::Context as SerenityContext;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+pub enum VoteOutcome {
+    /// Nothing is playing, so there is nothing to vote on.
+    NothingPlaying,
+    /// The voter isn't listening in the bot's voice channel.
+    NotListening,
+    /// The vote was counted but the threshold isn't met yet.
+    Counted { votes: usize, needed: usize },
+    /// The voter already voted on this track.
+    AlreadyVoted { votes: usize, needed: usize },
+    /// Enough votes — the track was skipped.
+    Passed { votes: usize, needed: usize },
+    /// The voter queued the track themselves and skipped it directly.
+    RequesterSkipped,
+}
+
+/// Votes needed out of `listeners` at a threshold of `percent`. Always at
+/// least one, so a lone listener can skip by voting.
+pub fn votes_needed(
+    listeners: usize,
+    percent: u8,
+) -> usize {
+    (listeners * percent as usize).div_ceil(100).max(1)
+}
+
+/// Record `voter`'s vote to skip the current track in `guild_id`, skipping
+/// it when the threshold is reached. Votes from people who since left the
+/// channel no longer count.
+pub async fn cast_vote(
+    serenity_ctx: &SerenityContext,
+    player_arc: &Arc<RwLock<Player>>,
+    guild_id: GuildId,
+    voter: &User,
+) -> Result<VoteOutcome, MusicBotError> {
+    let Some(bot_channel) = channel_service::bot_voice_channel(serenity_ctx, guild_id) else {
+        return Ok(VoteOutcome::NothingPlaying);
+    };
+    let listeners = channel_service::listeners(serenity_ctx, guild_id, bot_channel);
+
+    let mut player = player_arc.write().await;
+
+    let Some(track) = player.current_track.as_ref() else {
+        return Ok(VoteOutcome::NothingPlaying);
+    };
+    let is_requester = track.added_by.is(voter.id);
+
+    if !listeners.contains(&voter.id) {
+        return Ok(VoteOutcome::NotListening);
+    }
+
+    if is_requester {
+        tracing::info!("{} skipped their own track", voter.name);
+        player.skip(1).await?;
+        return Ok(VoteOutcome::RequesterSkipped);
+    }
+
+    let newly_voted = player.skip_votes.insert(voter.id);
+    let votes = player
+        .skip_votes
+        .iter()
+        .filter(|id| listeners.contains(id))
+        .count();
+    let needed = votes_needed(listeners.len(), player.vote_skip_percent);
+
+    tracing::info!("Vote to skip from {}: {}/{}", voter.name, votes, needed);
+
+    if votes >= needed {
+        player.skip(1).await?;
+        return Ok(VoteOutcome::Passed { votes, needed });
+    }
+
+    if newly_voted {
+        Ok(VoteOutcome::Counted { votes, needed })
+    } else {
+        Ok(VoteOutcome::AlreadyVoted { votes, needed })
+    }
+}
@@ -1,3 +1,5 @@
+pub mod generic_player;
 pub mod local_player;
+pub mod radio_player;
 pub mod spotify_player;
 pub mod youtube_player;
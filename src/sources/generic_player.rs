@@ -0,0 +1,120 @@
+//! Any http(s) URL that isn't YouTube or Spotify: SoundCloud, Bandcamp,
+//! Twitch VODs and whatever else yt-dlp has an extractor for. One yt-dlp
+//! probe reads the title, uploader, duration and thumbnail; a URL that turns
+//! out to be a set or album comes back as a playlist of its entries.
+
+use crate::player::track::{Playlist, Requester, Track, TrackMetadata, TrackSource};
+use crate::sources::youtube_player::{self, SearchError, YouTubeSearchResult};
+use serde_json::Value;
+use std::time::Duration;
+
+/// Whether `text` is a web URL worth handing to yt-dlp.
+pub fn is_web_url(text: &str) -> bool {
+    reqwest::Url::parse(text.trim()).is_ok_and(|url| matches!(url.scheme(), "http" | "https") && url.host_str().is_some())
+}
+
+/// Resolve `url` through yt-dlp into a track, or a playlist when it lists
+/// several.
+pub async fn resolve_url(url: &str) -> Result<YouTubeSearchResult, SearchError> {
+    let entries: Vec<Value> = youtube_player::flat_entries(url, None).await?;
+
+    // Flat playlist entries are `url` stubs; a single page is a full object.
+    let is_playlist = entries.len() > 1
+        || entries
+            .first()
+            .is_some_and(|v| v["_type"].as_str() == Some("url"));
+    if !is_playlist {
+        return entries
+            .first()
+            .and_then(|v| entry_to_track(v, url))
+            .map(YouTubeSearchResult::Track)
+            .ok_or_else(|| SearchError::VideoNotFound(format!("Nothing playable found at {url}")));
+    }
+
+    let tracks: Vec<Track> = entries
+        .iter()
+        .filter_map(|v| entry_to_track(v, ""))
+        .collect();
+    if tracks.is_empty() {
+        return Err(SearchError::PlaylistNotFound(format!(
+            "No tracks found at {url}"
+        )));
+    }
+
+    let first = &entries[0];
+    let title = first["playlist_title"]
+        .as_str()
+        .or_else(|| first["playlist"].as_str())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("Playlist")
+        .to_string();
+
+    Ok(YouTubeSearchResult::Playlist(Playlist {
+        id: first["playlist_id"].as_str().unwrap_or(url).to_string(),
+        title,
+        description: String::new(),
+        playlist_url: url.to_string(),
+        tracks,
+    }))
+}
+
+/// A track from one yt-dlp entry. `fallback_url` stands in for entries that
+/// don't say where they live (a single probed URL is its own page).
+fn entry_to_track(
+    v: &Value,
+    fallback_url: &str,
+) -> Option<Track> {
+    let id = v["id"].as_str().filter(|s| !s.is_empty())?;
+    let extractor = v["extractor_key"]
+        .as_str()
+        .or_else(|| v["ie_key"].as_str())
+        .unwrap_or("")
+        .to_string();
+    let track_url = v["webpage_url"]
+        .as_str()
+        .or_else(|| v["url"].as_str())
+        .filter(|url| is_web_url(url))
+        .unwrap_or(fallback_url)
+        .to_string();
+    if track_url.is_empty() {
+        return None;
+    }
+
+    let title = v["title"]
+        .as_str()
+        .or_else(|| v["track"].as_str())
+        .unwrap_or(id)
+        .to_string();
+    let channel = v["uploader"]
+        .as_str()
+        .or_else(|| v["artist"].as_str())
+        .or_else(|| v["channel"].as_str())
+        .unwrap_or("")
+        .to_string();
+    let duration = v["duration"]
+        .as_f64()
+        .filter(|d| d.is_finite() && *d > 0.0)
+        .map(|d| Duration::from_secs(d as u64));
+
+    // Prefixed so ids from different sites can't collide in the cache or
+    // the queue.
+    let id = if extractor.is_empty() { id.to_string() } else { format!("{}_{}", extractor.to_lowercase(), id) };
+
+    Some(Track {
+        id: id.clone(),
+        metadata: TrackMetadata {
+            id,
+            title,
+            channel,
+            track_url,
+            play_url: None,
+            thumbnail: youtube_player::entry_thumbnail(v),
+            duration,
+            skip_segments: Vec::new(),
+            chapters: youtube_player::parse_chapters(&v["chapters"]),
+        },
+        added_by: Requester::default(),
+        source: TrackSource::Generic { extractor },
+        range: None,
+    })
+}
@@ -0,0 +1,378 @@
+//! Internet radio: Icecast/Shoutcast stations and other endless HTTP audio
+//! streams. These play straight from the URL for as long as the station
+//! keeps sending, so they're never probed, length-capped or cached.
+//! `.m3u`/`.pls` station files are followed to the first stream they list.
+//! A plain audio file (`.mp3`, `.ogg`, … with a known length) isn't radio:
+//! it's left to yt-dlp like any other page, so it keeps its duration, ranges
+//! and seeking.
+//!
+//! Stations that support it interleave ICY metadata with the audio when
+//! asked to (`Icy-MetaData: 1`): every `icy-metaint` bytes of audio, one
+//! length byte and a block like `StreamTitle='Artist - Song';`. Songbird plays
+//! a clean copy of the stream; `IcyTitles` reads the titles off a second
+//! connection of its own.
+
+use crate::player::track::{Requester, Track, TrackMetadata, TrackSource};
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use std::time::Duration;
+
+const ICY_METADATA_HEADER: &str = "Icy-MetaData";
+/// How long a URL gets to answer before it's handed to the other sources.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Station files bigger than this aren't station files.
+const MAX_STATION_FILE: usize = 64 * 1024;
+
+/// Resolve `url` into a radio track when it serves a live audio stream (or
+/// a station file pointing at one). `None` for anything else, such as web
+/// pages, audio files and HLS playlists, which yt-dlp handles better.
+pub async fn resolve_url(
+    client: &reqwest::Client,
+    url: &str,
+) -> Option<Track> {
+    let response = open(client, url).await?;
+    let content_type = content_type(response.headers());
+
+    if is_station_file(url, &content_type) {
+        if response
+            .content_length()
+            .is_some_and(|n| n > MAX_STATION_FILE as u64)
+        {
+            return None;
+        }
+        let bytes = tokio::time::timeout(PROBE_TIMEOUT, response.bytes())
+            .await
+            .ok()?
+            .ok()?;
+        if bytes.len() > MAX_STATION_FILE {
+            return None;
+        }
+        let stream_url = station_file_stream(&String::from_utf8_lossy(&bytes))?;
+        let response = open(client, &stream_url).await?;
+        if !is_live_audio(&response) {
+            return None;
+        }
+        return Some(station_track(&stream_url, response.headers()));
+    }
+
+    is_live_audio(&response).then(|| station_track(url, response.headers()))
+}
+
+fn is_live_audio(response: &reqwest::Response) -> bool {
+    is_audio_stream(response.headers()) && is_live(response.headers(), response.content_length())
+}
+
+/// Ask for `url` with ICY metadata, returning once the headers are in.
+async fn open(
+    client: &reqwest::Client,
+    url: &str,
+) -> Option<reqwest::Response> {
+    let request = client.get(url).header(ICY_METADATA_HEADER, "1").send();
+    match tokio::time::timeout(PROBE_TIMEOUT, request).await {
+        Ok(Ok(response)) if response.status().is_success() => Some(response),
+        Ok(Ok(response)) => {
+            tracing::debug!("Radio probe of {} answered {}", url, response.status());
+            None
+        }
+        Ok(Err(e)) => {
+            tracing::debug!("Radio probe of {} failed: {}", url, e);
+            None
+        }
+        Err(_) => None,
+    }
+}
+
+fn content_type(headers: &HeaderMap) -> String {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+fn header<'a>(
+    headers: &'a HeaderMap,
+    name: &str,
+) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+}
+
+fn is_station_file(
+    url: &str,
+    content_type: &str,
+) -> bool {
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    matches!(
+        content_type,
+        "audio/x-mpegurl" | "audio/mpegurl" | "audio/x-scpls" | "application/pls+xml"
+    ) || path.ends_with(".m3u")
+        || path.ends_with(".pls")
+}
+
+/// Whether a response is audio to play as it arrives: an `audio/*` or Ogg
+/// body, or anything a station server marks with its ICY headers.
+fn is_audio_stream(headers: &HeaderMap) -> bool {
+    let content_type = content_type(headers);
+    let audio = (content_type.starts_with("audio/")
+        && !matches!(
+            content_type.as_str(),
+            "audio/x-mpegurl" | "audio/mpegurl" | "audio/x-scpls"
+        ))
+        || content_type == "application/ogg";
+    audio || header(headers, "icy-name").is_some() || header(headers, "icy-metaint").is_some()
+}
+
+/// Whether an audio response goes on for as long as it's read rather than
+/// being a file: station servers say so with their ICY headers, and a
+/// stream has no length to announce up front.
+fn is_live(
+    headers: &HeaderMap,
+    content_length: Option<u64>,
+) -> bool {
+    let icy = headers.keys().any(|name| name.as_str().starts_with("icy-"));
+    icy || content_length.is_none()
+}
+
+/// The first stream in an `.m3u` or `.pls` station file. `None` for HLS
+/// playlists, whose entries are segments rather than streams.
+fn station_file_stream(body: &str) -> Option<String> {
+    if body.contains("#EXT-X-") {
+        return None;
+    }
+    body.lines()
+        .map(str::trim)
+        .map(|line| match line.split_once('=') {
+            // `.pls`: `File1=http://…`
+            Some((key, value)) if key.to_ascii_lowercase().starts_with("file") => value.trim(),
+            _ => line,
+        })
+        .find(|line| line.starts_with("http://") || line.starts_with("https://"))
+        .map(str::to_string)
+}
+
+/// A track for the stream at `url`, named after the station when it says
+/// what it's called. The station name stays on as the author once the song
+/// titles start coming in.
+fn station_track(
+    url: &str,
+    headers: &HeaderMap,
+) -> Track {
+    let station = header(headers, "icy-name")
+        .map(str::to_string)
+        .or_else(|| {
+            reqwest::Url::parse(url)
+                .ok()
+                .and_then(|u| u.host_str().map(str::to_string))
+        })
+        .unwrap_or_else(|| url.to_string());
+
+    Track {
+        id: url.to_string(),
+        metadata: TrackMetadata {
+            id: url.to_string(),
+            title: station.clone(),
+            channel: station,
+            track_url: url.to_string(),
+            play_url: None,
+            thumbnail: None,
+            duration: None,
+            skip_segments: Vec::new(),
+            chapters: Some(Vec::new()),
+        },
+        added_by: Requester::default(),
+        source: TrackSource::Radio,
+        range: None,
+    }
+}
+
+/// The song titles a station announces, read off a metadata connection of
+/// their own.
+pub struct IcyTitles {
+    response: reqwest::Response,
+    metaint: usize,
+    buffer: Vec<u8>,
+}
+
+impl IcyTitles {
+    /// Start reading titles from `url`. `None` when the station doesn't send
+    /// ICY metadata.
+    pub async fn connect(
+        client: &reqwest::Client,
+        url: &str,
+    ) -> Option<Self> {
+        let response = open(client, url).await?;
+        let metaint: usize = header(response.headers(), "icy-metaint")?
+            .parse()
+            .ok()
+            .filter(|n| *n > 0)?;
+        Some(Self {
+            response,
+            metaint,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// The next `StreamTitle` the station sends, which can be the same one
+    /// again; empty when it's between songs. `None` once the stream ends.
+    pub async fn next_title(&mut self) -> Option<String> {
+        loop {
+            // The audio before the next metadata block isn't needed here.
+            let mut audio_left = self.metaint;
+            while audio_left > 0 {
+                if self.buffer.is_empty() {
+                    self.fill().await?;
+                }
+                let n = audio_left.min(self.buffer.len());
+                self.buffer.drain(..n);
+                audio_left -= n;
+            }
+
+            let length = usize::from(*self.take(1).await?.first()?) * 16;
+            if length == 0 {
+                continue;
+            }
+            let block = self.take(length).await?;
+            if let Some(title) = parse_stream_title(&block) {
+                return Some(title);
+            }
+        }
+    }
+
+    async fn take(
+        &mut self,
+        n: usize,
+    ) -> Option<Vec<u8>> {
+        while self.buffer.len() < n {
+            self.fill().await?;
+        }
+        Some(self.buffer.drain(..n).collect())
+    }
+
+    async fn fill(&mut self) -> Option<()> {
+        let chunk = self.response.chunk().await.ok()??;
+        self.buffer.extend_from_slice(&chunk);
+        Some(())
+    }
+}
+
+/// The `StreamTitle` in a metadata block. Plenty of stations still send
+/// Latin-1, so bytes that aren't UTF-8 are read as that.
+fn parse_stream_title(block: &[u8]) -> Option<String> {
+    let text = match std::str::from_utf8(block) {
+        Ok(text) => text.to_string(),
+        Err(_) => block.iter().map(|&b| char::from(b)).collect(),
+    };
+    let text = text.trim_end_matches('\0');
+
+    let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &text[start..];
+    // Titles can contain quotes themselves; the field ends at `';`.
+    let end = rest
+        .find("';")
+        .unwrap_or_else(|| rest.trim_end_matches(';').trim_end_matches('\'').len());
+    Some(rest[..end].trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_title() {
+        let block = b"StreamTitle='Artist - Song';StreamUrl='';\0\0\0\0";
+        assert_eq!(parse_stream_title(block), Some("Artist - Song".to_string()));
+    }
+
+    #[test]
+    fn stream_title_with_quotes_inside() {
+        let block = b"StreamTitle='Guns N' Roses - Don't Cry';\0";
+        assert_eq!(
+            parse_stream_title(block),
+            Some("Guns N' Roses - Don't Cry".to_string())
+        );
+    }
+
+    #[test]
+    fn stream_title_between_songs() {
+        assert_eq!(
+            parse_stream_title(b"StreamTitle='';\0\0"),
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn stream_title_without_terminator() {
+        assert_eq!(
+            parse_stream_title(b"StreamTitle='Artist - Song'\0"),
+            Some("Artist - Song".to_string())
+        );
+    }
+
+    #[test]
+    fn stream_title_in_latin1() {
+        let block = b"StreamTitle='Bj\xf6rk - J\xf3ga';\0";
+        assert_eq!(parse_stream_title(block), Some("Björk - Jóga".to_string()));
+    }
+
+    #[test]
+    fn block_without_stream_title() {
+        assert_eq!(
+            parse_stream_title(b"StreamUrl='http://example.com';\0"),
+            None
+        );
+    }
+
+    #[test]
+    fn station_file_streams() {
+        let m3u = "#EXTM3U\n#EXTINF:-1,Station\nhttp://stream.example.com/radio.mp3\n";
+        assert_eq!(
+            station_file_stream(m3u),
+            Some("http://stream.example.com/radio.mp3".to_string())
+        );
+
+        let pls = "[playlist]\nNumberOfEntries=2\nFile1=https://one.example.com/live\nTitle1=One\nFile2=https://two.example.com/live\n";
+        assert_eq!(
+            station_file_stream(pls),
+            Some("https://one.example.com/live".to_string())
+        );
+
+        let hls = "#EXTM3U\n#EXT-X-TARGETDURATION:10\nhttps://example.com/segment0.ts\n";
+        assert_eq!(station_file_stream(hls), None);
+        assert_eq!(station_file_stream("#EXTM3U\n"), None);
+    }
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, value.parse().unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn stations_are_live() {
+        let icy = headers(&[("content-type", "audio/mpeg"), ("icy-name", "Station")]);
+        assert!(is_live(&icy, None));
+        // Some stations send a huge placeholder length.
+        assert!(is_live(&icy, Some(u32::MAX as u64)));
+
+        let bare = headers(&[("content-type", "audio/ogg")]);
+        assert!(is_live(&bare, None));
+    }
+
+    #[test]
+    fn audio_files_are_not_live() {
+        let file = headers(&[("content-type", "audio/mpeg")]);
+        assert!(is_audio_stream(&file));
+        assert!(!is_live(&file, Some(4_321_000)));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::player::track::{Playlist, Track, TrackMetadata};
+use crate::player::track::{Playlist, Requester, Track, TrackMetadata};
 use base64::engine::general_purpose::STANDARD as BASE64;
 use base64::Engine;
 use dotenv::var;
@@ -20,6 +20,8 @@ pub enum SpotifyKind {
     Playlist,
 }
 
+// Short-lived lookup results; boxing the track isn't worth it.
+#[allow(clippy::large_enum_variant)]
 pub enum SpotifySearchResult {
     Track(Track),
     Playlist(Playlist),
@@ -396,9 +398,13 @@ fn build_track(sp: &SpTrack) -> Track {
             channel,
             track_url,
             play_url: Some(format!("ytsearch1:{query}")),
+            thumbnail: None,
             duration: sp.duration_ms.map(std::time::Duration::from_millis),
+            skip_segments: Vec::new(),
+            chapters: None,
         },
-        added_by: String::new(),
+        added_by: Requester::default(),
         source: crate::player::track::TrackSource::Spotify,
+        range: None,
     }
 }
@@ -1,4 +1,5 @@
-use crate::player::track::{Playlist, Track, TrackMetadata};
+use crate::player::track::{Chapter, Playlist, Requester, Track, TrackMetadata};
+use crate::utils::time_utils;
 use dotenv::var;
 use google_youtube3::api::{PlaylistItem, PlaylistItemSnippet, SearchResult, SearchResultSnippet};
 use google_youtube3::client::NoToken;
@@ -8,6 +9,7 @@ use google_youtube3::YouTube;
 use html_escape::decode_html_entities;
 use serde_json::Value;
 use std::process::Stdio;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, BufReader};
 
 pub struct YoutubeClient {
@@ -15,6 +17,8 @@ pub struct YoutubeClient {
     youtube: YouTube<HttpsConnector<HttpConnector>>,
 }
 
+// Short-lived lookup results; boxing the track isn't worth it.
+#[allow(clippy::large_enum_variant)]
 pub enum YouTubeSearchResult {
     Track(Track),
     Tracks(Vec<Track>),
@@ -39,6 +43,11 @@ pub enum SearchError {
 
     #[error("YouTube API quota exceeded — please try again later or contact the bot owner.")]
     QuotaExceeded,
+
+    /// A `from … to …` range after a link that can't be played: a typo'd
+    /// time, or an end before the start.
+    #[error("{0}")]
+    InvalidRange(String),
 }
 
 const SINGLE_URI: &str = "https://www.youtube.com/watch?v=";
@@ -115,17 +124,21 @@ impl YoutubeClient {
                     channel: decode_html_entities(channel).to_string(),
                     track_url: format!("{SINGLE_URI}{}", video_id),
                     play_url: None,
+                    thumbnail: None,
                     // YouTube Data API search/playlist responses don't carry
                     // duration; we'd need a separate videos.list call. Probed
                     // lazily via yt-dlp before playback instead.
                     duration: None,
+                    skip_segments: Vec::new(),
+                    chapters: None,
                 };
 
                 Some(Ok(Track {
                     id: video_id,
                     metadata,
-                    added_by: String::new(),
+                    added_by: Requester::default(),
                     source: crate::player::track::TrackSource::YouTube,
+                    range: None,
                 }))
             })
             .collect::<Result<Vec<Track>, SearchError>>()?;
@@ -206,14 +219,18 @@ impl YoutubeClient {
                         channel: decode_html_entities(channel).to_string(),
                         track_url: format!("{SINGLE_URI}{}", video_id),
                         play_url: None,
+                        thumbnail: None,
                         duration: None,
+                        skip_segments: Vec::new(),
+                        chapters: None,
                     };
 
                     Some(Ok(Track {
                         id: video_id,
                         metadata,
-                        added_by: String::new(),
+                        added_by: Requester::default(),
                         source: crate::player::track::TrackSource::YouTube,
+                        range: None,
                     }))
                 })
                 .collect::<Result<Vec<Track>, SearchError>>()?;
@@ -234,82 +251,29 @@ impl YoutubeClient {
     }
 
     /// Enumerate a YouTube playlist using yt-dlp's `--flat-playlist` mode.
-    /// Streams results line-by-line so we get track titles immediately from
-    /// yt-dlp scraping — zero YouTube Data API quota used.
+    /// Results come from yt-dlp scraping — zero YouTube Data API quota used.
     pub async fn fetch_playlist_lazy(
         &self,
         url: String,
     ) -> Result<YouTubeSearchResult, SearchError> {
         let playlist_id = url.trim_start_matches(PLAYLIST_URI).to_string();
 
-        let mut child = tokio::process::Command::new("yt-dlp")
-            .args([
-                "--flat-playlist",
-                "--no-warnings",
-                "--print",
-                "%j", // one JSON object per line
-                &url,
-            ])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::null())
-            .spawn()
-            .map_err(|e| SearchError::InternalError(format!("Failed to spawn yt-dlp: {e}")))?;
-
-        let stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| SearchError::InternalError("yt-dlp stdout missing".into()))?;
-
-        let mut lines = BufReader::new(stdout).lines();
-
-        let mut playlist_title = String::new();
-        let mut playlist_desc = String::new();
-        let mut tracks: Vec<Track> = Vec::new();
-
-        while let Ok(Some(line)) = lines.next_line().await {
-            let Ok(v): Result<Value, _> = serde_json::from_str(&line) else {
-                continue;
-            };
-
-            // Grab playlist metadata from the first entry.
-            if tracks.is_empty() {
-                if let Some(t) = v["playlist_title"].as_str().filter(|s| !s.is_empty()) {
-                    playlist_title = t.to_string();
-                }
-                if let Some(d) = v["playlist_description"].as_str() {
-                    playlist_desc = d.to_string();
-                }
-            }
+        let entries: Vec<Value> = flat_entries(&url, None).await?;
 
-            let Some(id) = v["id"].as_str() else { continue };
-            let title = v["title"].as_str().unwrap_or(id).to_string();
-            let channel = v["channel"]
-                .as_str()
-                .or_else(|| v["uploader"].as_str())
-                .unwrap_or("")
-                .to_string();
-            let duration = v["duration"]
-                .as_f64()
-                .filter(|d| d.is_finite() && *d > 0.0)
-                .map(|d| std::time::Duration::from_secs(d as u64));
-
-            tracks.push(Track {
-                id: id.to_string(),
-                metadata: TrackMetadata {
-                    id: id.to_string(),
-                    title,
-                    channel,
-                    track_url: format!("{SINGLE_URI}{id}"),
-                    play_url: None,
-                    duration,
-                },
-                added_by: String::new(),
-                source: crate::player::track::TrackSource::YouTube,
-            });
-        }
+        // Grab playlist metadata from the first entry.
+        let mut playlist_title = entries
+            .first()
+            .and_then(|v| v["playlist_title"].as_str())
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+            .unwrap_or_default();
+        let playlist_desc = entries
+            .first()
+            .and_then(|v| v["playlist_description"].as_str())
+            .map(str::to_string)
+            .unwrap_or_default();
 
-        // Reap the child process.
-        let _ = child.wait().await;
+        let tracks: Vec<Track> = entries.iter().filter_map(entry_to_track).collect();
 
         if tracks.is_empty() {
             return Err(SearchError::PlaylistNotFound(format!(
@@ -330,3 +294,262 @@ impl YoutubeClient {
         }))
     }
 }
+
+/// Tracks from YouTube's auto-generated mix ("radio") for `video_id`. The
+/// first entry is usually the seed video itself.
+pub async fn fetch_mix(
+    video_id: &str,
+    limit: usize,
+) -> Result<Vec<Track>, SearchError> {
+    let url = format!("{SINGLE_URI}{video_id}&list=RD{video_id}");
+    let entries = flat_entries(&url, Some(limit)).await?;
+    Ok(entries
+        .iter()
+        .filter(|v| !is_live_entry(v))
+        .filter_map(entry_to_track)
+        .collect())
+}
+
+/// First `limit` results of a yt-dlp `ytsearch` for `query`. Costs no API
+/// quota, unlike `search_track_url`.
+pub async fn search_flat(
+    query: &str,
+    limit: usize,
+) -> Result<Vec<Track>, SearchError> {
+    let url = format!("ytsearch{limit}:{query}");
+    let entries = flat_entries(&url, Some(limit)).await?;
+    Ok(entries
+        .iter()
+        .filter(|v| !is_live_entry(v))
+        .filter_map(entry_to_track)
+        .collect())
+}
+
+/// Run yt-dlp in `--flat-playlist` mode over `url` and collect one JSON
+/// object per entry, stopping after `limit` entries when given. A URL that
+/// isn't a playlist yields its one full info object.
+pub async fn flat_entries(
+    url: &str,
+    limit: Option<usize>,
+) -> Result<Vec<Value>, SearchError> {
+    let mut command = tokio::process::Command::new("yt-dlp");
+    command.args([
+        "--flat-playlist",
+        "--no-warnings",
+        "--print",
+        "%j", // one JSON object per line
+    ]);
+    if let Some(limit) = limit {
+        command.args(["--playlist-end", &limit.to_string()]);
+    }
+
+    let mut child = command
+        .arg(url)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| SearchError::InternalError(format!("Failed to spawn yt-dlp: {e}")))?;
+
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| SearchError::InternalError("yt-dlp stdout missing".into()))?;
+
+    let mut lines = BufReader::new(stdout).lines();
+    let mut entries: Vec<Value> = Vec::new();
+
+    while let Ok(Some(line)) = lines.next_line().await {
+        if let Ok(v) = serde_json::from_str::<Value>(&line) {
+            entries.push(v);
+        }
+    }
+
+    // Reap the child process.
+    let _ = child.wait().await;
+
+    Ok(entries)
+}
+
+fn is_live_entry(v: &Value) -> bool {
+    v["is_live"].as_bool().unwrap_or(false) || v["live_status"].as_str() == Some("is_live")
+}
+
+fn entry_to_track(v: &Value) -> Option<Track> {
+    let id = v["id"].as_str()?;
+    let title = v["title"].as_str().unwrap_or(id).to_string();
+    let channel = v["channel"]
+        .as_str()
+        .or_else(|| v["uploader"].as_str())
+        .unwrap_or("")
+        .to_string();
+    let duration = v["duration"]
+        .as_f64()
+        .filter(|d| d.is_finite() && *d > 0.0)
+        .map(|d| std::time::Duration::from_secs(d as u64));
+
+    Some(Track {
+        id: id.to_string(),
+        metadata: TrackMetadata {
+            id: id.to_string(),
+            title,
+            channel,
+            track_url: format!("{SINGLE_URI}{id}"),
+            play_url: None,
+            thumbnail: entry_thumbnail(v),
+            duration,
+            skip_segments: Vec::new(),
+            chapters: parse_chapters(&v["chapters"]),
+        },
+        added_by: Requester::default(),
+        source: crate::player::track::TrackSource::YouTube,
+        range: None,
+    })
+}
+
+/// Thumbnail URL of a yt-dlp entry: its `thumbnail`, or else the last (and
+/// usually largest) of its `thumbnails`.
+pub fn entry_thumbnail(v: &Value) -> Option<String> {
+    v["thumbnail"]
+        .as_str()
+        .or_else(|| v["thumbnails"].as_array()?.last()?["url"].as_str())
+        .filter(|url| url.starts_with("http"))
+        .map(str::to_string)
+}
+
+/// Chapters from a yt-dlp `chapters` field: an array of objects with a
+/// `title` and a `start_time` in seconds. `None` when there's no list, which
+/// is both how flat playlist entries leave it out and how a video without
+/// chapters reports it.
+pub fn parse_chapters(v: &Value) -> Option<Vec<Chapter>> {
+    let chapters = v.as_array()?;
+    Some(
+        chapters
+            .iter()
+            .filter_map(|chapter| {
+                let start = chapter["start_time"]
+                    .as_f64()
+                    .filter(|s| s.is_finite() && *s >= 0.0)?;
+                Some(Chapter {
+                    title: chapter["title"].as_str().unwrap_or("").trim().to_string(),
+                    start: std::time::Duration::from_secs_f64(start),
+                })
+            })
+            .collect(),
+    )
+}
+
+/// A link to one YouTube video, with where it asks playback to start.
+pub struct VideoLink {
+    pub id: String,
+    pub start: Option<Duration>,
+}
+
+/// Read a YouTube video link in any of its usual shapes — `watch?v=` on
+/// youtube.com (also `m.` and `music.`), `youtu.be/<id>`, `/shorts/<id>`,
+/// `/live/<id>` or `/embed/<id>` — with the start time from a `t` or `start`
+/// parameter (`95`, `95s`, `1m35s`), in the query or the fragment.
+pub fn parse_video_url(text: &str) -> Option<VideoLink> {
+    let url = reqwest::Url::parse(text.trim()).ok()?;
+    let host = url.host_str()?.trim_start_matches("www.");
+    let mut segments = url.path_segments()?.filter(|s| !s.is_empty());
+
+    let id: String = match host {
+        "youtu.be" => segments.next()?.to_string(),
+        "youtube.com" | "m.youtube.com" | "music.youtube.com" => match segments.next()? {
+            "watch" => url
+                .query_pairs()
+                .find(|(key, _)| key == "v")
+                .map(|(_, value)| value.to_string())?,
+            "shorts" | "live" | "embed" => segments.next()?.to_string(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    if id.is_empty()
+        || !id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+
+    // `#t=1m35s` is as common as `?t=95` in shared links.
+    let fragment_pairs = url
+        .fragment()
+        .unwrap_or("")
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()));
+    let start = url
+        .query_pairs()
+        .into_owned()
+        .chain(fragment_pairs)
+        .find(|(key, _)| key == "t" || key == "start")
+        .and_then(|(_, value)| time_utils::parse_timestamp(&value))
+        .filter(|start| !start.is_zero());
+
+    Some(VideoLink { id, start })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(text: &str) -> Option<(String, Option<u64>)> {
+        parse_video_url(text).map(|link| (link.id, link.start.map(|s| s.as_secs())))
+    }
+
+    #[test]
+    fn video_ids_from_each_link_shape() {
+        let plain = Some(("dQw4w9WgXcQ".to_string(), None));
+        assert_eq!(link("https://www.youtube.com/watch?v=dQw4w9WgXcQ"), plain);
+        assert_eq!(
+            link("https://m.youtube.com/watch?feature=share&v=dQw4w9WgXcQ"),
+            plain
+        );
+        assert_eq!(
+            link("https://music.youtube.com/watch?v=dQw4w9WgXcQ&list=RD"),
+            plain
+        );
+        assert_eq!(link("https://youtu.be/dQw4w9WgXcQ"), plain);
+        assert_eq!(link("https://youtube.com/shorts/dQw4w9WgXcQ"), plain);
+        assert_eq!(link("https://www.youtube.com/live/dQw4w9WgXcQ"), plain);
+        assert_eq!(link("https://www.youtube.com/embed/dQw4w9WgXcQ"), plain);
+    }
+
+    #[test]
+    fn start_from_t_or_start() {
+        let at = |secs| Some(("dQw4w9WgXcQ".to_string(), Some(secs)));
+        assert_eq!(link("https://youtu.be/dQw4w9WgXcQ?t=95"), at(95));
+        assert_eq!(link("https://youtu.be/dQw4w9WgXcQ?t=95s"), at(95));
+        assert_eq!(
+            link("https://www.youtube.com/watch?v=dQw4w9WgXcQ&t=1m35s"),
+            at(95)
+        );
+        assert_eq!(
+            link("https://www.youtube.com/embed/dQw4w9WgXcQ?start=42"),
+            at(42)
+        );
+        assert_eq!(
+            link("https://www.youtube.com/watch?v=dQw4w9WgXcQ#t=1m35s"),
+            at(95)
+        );
+    }
+
+    #[test]
+    fn zero_or_bad_start_is_ignored() {
+        let plain = Some(("dQw4w9WgXcQ".to_string(), None));
+        assert_eq!(link("https://youtu.be/dQw4w9WgXcQ?t=0"), plain);
+        assert_eq!(link("https://youtu.be/dQw4w9WgXcQ?t=soon"), plain);
+    }
+
+    #[test]
+    fn rejects_other_links() {
+        assert_eq!(link("https://www.youtube.com/playlist?list=PL123"), None);
+        assert_eq!(link("https://www.youtube.com/watch"), None);
+        assert_eq!(link("https://youtu.be/"), None);
+        assert_eq!(link("https://youtu.be/not%20an%20id"), None);
+        assert_eq!(link("https://vimeo.com/watch?v=dQw4w9WgXcQ"), None);
+        assert_eq!(link("never gonna give you up"), None);
+    }
+}
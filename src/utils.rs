@@ -1,2 +1,3 @@
+pub mod lock_utils;
 pub mod string_utils;
 pub mod time_utils;
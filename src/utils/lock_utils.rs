@@ -0,0 +1,108 @@
+//! Per-key async locks for work two tasks can start on the same target at
+//! once, such as a prefetch and the playing track downloading or rendering
+//! the same file. The second caller waits for the first and then finds its
+//! result instead of racing it.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::{Arc, Mutex, OnceLock};
+use tokio::sync::MutexGuard;
+
+type LockMap<K> = Mutex<HashMap<K, Arc<tokio::sync::Mutex<()>>>>;
+
+/// A map of locks, one per key in use. Meant to live in a `static`.
+pub struct KeyedLocks<K> {
+    locks: OnceLock<LockMap<K>>,
+}
+
+impl<K: Eq + Hash + Clone> KeyedLocks<K> {
+    pub const fn new() -> Self {
+        Self { locks: OnceLock::new() }
+    }
+
+    fn map(&self) -> std::sync::MutexGuard<'_, HashMap<K, Arc<tokio::sync::Mutex<()>>>> {
+        self.locks
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take a share of `key`'s lock, creating it if nobody holds one.
+    pub fn join(
+        &'static self,
+        key: K,
+    ) -> InFlight<K> {
+        let lock = Arc::clone(self.map().entry(key.clone()).or_default());
+        InFlight { locks: self, key, lock }
+    }
+}
+
+impl<K: Eq + Hash + Clone> Default for KeyedLocks<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A caller's share of one key's lock. Dropping it, whether the work
+/// finished or its future was dropped (a skipped prefetch, a killed
+/// ffmpeg), removes the map entry when nobody else shares it, so the map
+/// doesn't grow with every key.
+pub struct InFlight<K: Eq + Hash + Clone + 'static> {
+    locks: &'static KeyedLocks<K>,
+    key: K,
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl<K: Eq + Hash + Clone> InFlight<K> {
+    /// Wait for whoever else is working on the key.
+    pub async fn lock(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().await
+    }
+}
+
+impl<K: Eq + Hash + Clone> Drop for InFlight<K> {
+    fn drop(&mut self) {
+        let mut locks = self.locks.map();
+        // The map's own reference plus ours.
+        if Arc::strong_count(&self.lock) == 2 {
+            locks.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static LOCKS: KeyedLocks<&str> = KeyedLocks::new();
+
+    #[test]
+    fn entry_stays_while_shared() {
+        let first = LOCKS.join("shared");
+        let second = LOCKS.join("shared");
+        assert!(Arc::ptr_eq(&first.lock, &second.lock));
+
+        drop(first);
+        assert!(LOCKS.map().contains_key("shared"));
+        drop(second);
+        assert!(!LOCKS.map().contains_key("shared"));
+    }
+
+    #[tokio::test]
+    async fn entry_removed_when_waiter_is_cancelled() {
+        let holder = LOCKS.join("cancelled");
+        let guard = holder.lock().await;
+
+        let waiter = tokio::spawn(async {
+            let in_flight = LOCKS.join("cancelled");
+            let _guard = in_flight.lock().await;
+        });
+        tokio::task::yield_now().await;
+        waiter.abort();
+        let _ = waiter.await;
+
+        drop(guard);
+        drop(holder);
+        assert!(!LOCKS.map().contains_key("cancelled"));
+    }
+}
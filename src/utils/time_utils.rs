@@ -106,6 +106,36 @@ pub fn parse_duration_from_string(text: &str) -> Option<Duration> {
     Some(Duration::from_secs(secs))
 }
 
+/// Parse a position inside a track: `mm:ss`, `h:mm:ss`, bare seconds (`95`)
+/// or a unit string (`1m35s`). Unlike `parse_duration_from_string`, zero is
+/// a valid answer — it means the start of the track.
+pub fn parse_timestamp(text: &str) -> Option<Duration> {
+    let text = text.trim();
+
+    if text.contains(':') {
+        let parts: Vec<&str> = text.split(':').collect();
+        if parts.len() > 3 {
+            return None;
+        }
+
+        let mut secs: u64 = 0;
+        for (i, part) in parts.iter().enumerate() {
+            let value: u64 = part.parse().ok()?;
+            if i > 0 && value >= 60 {
+                return None;
+            }
+            secs = secs.saturating_mul(60).saturating_add(value);
+        }
+        return Some(Duration::from_secs(secs));
+    }
+
+    if let Ok(secs) = text.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+
+    parse_duration_from_string(text)
+}
+
 pub fn convert_time_offset_from_string(text: String) -> Option<OffsetDateTime> {
     let secs = parse_offset_secs(text.as_str())?;
     Some(get_current_time().add(Duration::from_secs(secs)))
@@ -148,3 +178,39 @@ pub fn parse_text(text: String) -> Result<OffsetDateTime, TimeParseError> {
         .or_else(|| convert_time_offset_from_string(trimmed.clone()))
         .ok_or(TimeParseError::InvalidTimeFormat)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(n: u64) -> Option<Duration> {
+        Some(Duration::from_secs(n))
+    }
+
+    #[test]
+    fn colon_timestamps() {
+        assert_eq!(parse_timestamp("1:35"), secs(95));
+        assert_eq!(parse_timestamp("01:02:03"), secs(3723));
+        assert_eq!(parse_timestamp("0:00"), secs(0));
+        // The leading segment may run past 59.
+        assert_eq!(parse_timestamp("90:00"), secs(5400));
+    }
+
+    #[test]
+    fn bare_seconds_and_units() {
+        assert_eq!(parse_timestamp("95"), secs(95));
+        assert_eq!(parse_timestamp("0"), secs(0));
+        assert_eq!(parse_timestamp(" 1m35s "), secs(95));
+        assert_eq!(parse_timestamp("1h 30s"), secs(3630));
+    }
+
+    #[test]
+    fn rejects_malformed_timestamps() {
+        assert_eq!(parse_timestamp("1:60"), None);
+        assert_eq!(parse_timestamp("1:2:3:4"), None);
+        assert_eq!(parse_timestamp("1::2"), None);
+        assert_eq!(parse_timestamp("-5"), None);
+        assert_eq!(parse_timestamp("soon"), None);
+        assert_eq!(parse_timestamp(""), None);
+    }
+}